@@ -5,6 +5,15 @@ pub const APP_NAME: &str = "Zed";
 
 pub const CONFIG_FILE_NAME: &str = "config.toml";
 
+// data directory, holds settings and recovery files
+pub const DATA_DIR_NAME: &str = "byok-1";
+pub const SETTINGS_FILE_NAME: &str = "settings.json";
+pub const SEARCH_HISTORY_FILE_NAME: &str = "search_history.json";
+pub const LAYOUT_FILE_NAME: &str = "layout.json";
+pub const RECENT_WORKSPACES_FILE_NAME: &str = "recent_workspaces.json";
+pub const RECOVERY_DIR_NAME: &str = "recovery";
+pub const RECOVERY_INTERVAL_SECS: u64 = 5;
+
 // colours
 pub const PRIMARY_COLOR: Rgba = hex_to_rgba(0x3498db);
 pub const SECONDARY_COLOR: Rgba = hex_to_rgba(0x2ecc71);
@@ -21,8 +30,51 @@ pub const BASE_FONT_SIZE: f32 = 16.0;
 pub const DEFAULT_WINDOW_WIDTH: f32 = 800.0;
 pub const DEFAULT_WINDOW_HEIGHT: f32 = 600.0;
 
+// file browser
+pub const BROWSER_DEFAULT_WIDTH: f32 = 256.0;
+pub const BROWSER_MIN_WIDTH: f32 = 160.0;
+pub const BROWSER_MAX_WIDTH: f32 = 640.0;
+
+/// Fallback icons, see `IconTheme` for the per-type ones
 pub const DIR_ICON: &str = "📁";
+pub const DIR_OPEN_ICON: &str = "📂";
 pub const FILE_ICON: &str = "📄";
+/// Optional icon theme in the data directory, merged over the built-in icons
+pub const ICON_THEME_FILE_NAME: &str = "icon_theme.json";
+
+/// Upper bound on file contents kept in the ResourcePool cache
+pub const RESOURCE_CACHE_BUDGET_BYTES: usize = 64 * 1024 * 1024;
 
 pub const INITIAL_LOAD_LINES: usize = 100;
 pub const CHUNK_LOAD_LINES: usize = 500;
+
+// image viewer
+pub const IMAGE_ZOOM_STEP: f32 = 1.25;
+pub const IMAGE_MIN_ZOOM: f32 = 0.05;
+pub const IMAGE_MAX_ZOOM: f32 = 32.0;
+
+// search
+/// Lines shown above and below each match in the search results panel
+pub const SEARCH_CONTEXT_LINES: usize = 1;
+/// A NUL byte within this many leading bytes marks a file as binary
+pub const SEARCH_BINARY_SNIFF_BYTES: usize = 8 * 1024;
+/// Past search queries remembered for up/down recall
+pub const SEARCH_HISTORY_LIMIT: usize = 100;
+/// Subdirectory of the data directory holding one trigram index per workspace root
+pub const SEARCH_INDEX_DIR_NAME: &str = "index";
+/// How often a changed search index is written back to disk
+pub const SEARCH_INDEX_SAVE_INTERVAL_SECS: u64 = 30;
+
+// file finder
+/// Rows shown in the quick-open list
+pub const FILE_FINDER_MAX_RESULTS: usize = 50;
+/// Recently opened files remembered for ranking quick-open results
+pub const RECENT_FILES_LIMIT: usize = 50;
+/// Score added to the most recently opened file, tapering off for older ones
+pub const FILE_FINDER_RECENCY_BONUS: i64 = 40;
+
+// workspaces
+/// Folders offered under "Open Recent" in the command palette
+pub const RECENT_WORKSPACES_LIMIT: usize = 10;
+/// Extension of files listing the folders of a multi-root workspace
+pub const WORKSPACE_FILE_EXTENSION: &str = "byok-workspace";
@@ -1,201 +1,1310 @@
+use futures::StreamExt;
+use futures::channel::{mpsc, oneshot};
 use gpui::{
-    Context, Entity, IntoElement, MouseButton, MouseDownEvent, MouseUpEvent, Render, Window, div,
-    prelude::*, px, rgb,
+    AnyElement, App, Bounds, ClipboardItem, Context, DragMoveEvent, Empty, Entity, EventEmitter,
+    FocusHandle, Focusable, IntoElement, KeyContext, KeyDownEvent, MouseButton, MouseDownEvent,
+    Pixels, Point, PromptLevel, Render, ScrollStrategy, SharedString, Subscription,
+    UniformListDecoration, UniformListScrollHandle, Window, actions, anchored, deferred, div,
+    prelude::*, px, rgb, rgba, uniform_list,
 };
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
 use std::collections::HashSet;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
-use crate::ui::workspace::OpenPath;
+use std::time::Duration;
+use crate::editor::fileops;
+use crate::editor::filetree::{self, FileTree, TreeRow};
+use crate::editor::search::{SearchScope, project_files};
+use crate::ui::text_input::TextInput;
+use crate::editor::git::{GitStatus, GitStatuses};
+use crate::icons::IconTheme;
+use crate::roots::{WorkspaceRoots, root_name};
+use crate::ui::workspace::{OpenPath, RemoveWorkspaceFolder};
 
-use crate::config::{DIR_ICON, FILE_ICON};
 use crate::state::appstate::AppState;
 
+actions!(
+    FileBrowser,
+    [
+        CreateFile,
+        CreateFolder,
+        RenameEntry,
+        TrashEntry,
+        DuplicateEntry,
+        CopyPath,
+        CopyRelativePath,
+        SelectPrevious,
+        SelectNext,
+        SelectFirst,
+        SelectLast,
+        CollapseEntry,
+        ExpandEntry,
+        OpenEntry,
+    ]
+);
+
+/// An entry was renamed or moved on disk, so anything open from `from`
+/// (or from inside it, for a folder) now lives under `to`.
+pub struct EntryMoved {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum EditKind {
+    NewFile,
+    NewFolder,
+    Rename,
+}
+
+/// A name being typed in place in the tree.
+struct PendingEdit {
+    kind: EditKind,
+    /// The folder to create in, or the entry being renamed
+    target: PathBuf,
+    input: Entity<TextInput>,
+    _blur: Subscription,
+}
+
+/// What is being dragged when an entry is moved with the mouse.
+#[derive(Clone)]
+pub struct DraggedEntry {
+    path: PathBuf,
+}
+
+impl Render for DraggedEntry {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        let name = self
+            .path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        div()
+            .px_2()
+            .py_1()
+            .bg(rgb(0x04395e))
+            .text_color(rgb(0xffffff))
+            .text_xs()
+            .child(name)
+    }
+}
+
+/// Drag payload for the scrollbar thumb.
+struct DraggedScrollbar;
+
+impl Render for DraggedScrollbar {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        Empty
+    }
+}
+
+/// Height of a row in the tree; the list is virtualized so every row
+/// must be the same height.
+const ROW_HEIGHT: f32 = 22.0;
+
+const SCROLLBAR_WIDTH: f32 = 8.0;
+
+/// Shortest the thumb gets, so it stays grabbable in huge trees.
+const SCROLLBAR_MIN_THUMB: f32 = 20.0;
+
+/// Top and height of the scrollbar thumb in a `track` tall list that can
+/// scroll `max_scroll` further and is `scrolled` down, or `None` when
+/// everything fits.
+pub fn scrollbar_thumb(track: f32, max_scroll: f32, scrolled: f32) -> Option<(f32, f32)> {
+    if track <= 0.0 || max_scroll <= 0.0 {
+        return None;
+    }
+    let height = track * track / (track + max_scroll);
+    let height = height.clamp(SCROLLBAR_MIN_THUMB.min(track), track);
+    let top = (scrolled / max_scroll).clamp(0.0, 1.0) * (track - height);
+    Some((top, height))
+}
+
+/// A scrollbar over the right edge of the tree while its rows don't all
+/// fit. Laid out along with the rows, so it is placed after any scroll to
+/// the selection has landed. Clicking the track jumps there, dragging
+/// scrolls along.
+struct TreeScrollbar {
+    scroll_handle: UniformListScrollHandle,
+}
+
+impl UniformListDecoration for TreeScrollbar {
+    fn compute(
+        &self,
+        _visible_range: Range<usize>,
+        bounds: Bounds<Pixels>,
+        scroll_offset: Point<Pixels>,
+        item_height: Pixels,
+        item_count: usize,
+        _window: &mut Window,
+        _cx: &mut App,
+    ) -> AnyElement {
+        let track = f32::from(bounds.size.height);
+        let row = f32::from(item_height);
+        let max_scroll = item_count as f32 * row - track;
+        let Some((top, height)) = scrollbar_thumb(track, max_scroll, -f32::from(scroll_offset.y))
+        else {
+            return Empty.into_any_element();
+        };
+
+        // `bounds` move with the rows; the track stays put
+        let track_top = bounds.top() - scroll_offset.y;
+        // Row to scroll to the top so the thumb is centered on `y`, a window position
+        let row_at = move |y: Pixels| {
+            let travel = (track - height).max(1.0);
+            let fraction = (f32::from(y - track_top) - height / 2.0) / travel;
+            (fraction.clamp(0.0, 1.0) * max_scroll / row).round() as usize
+        };
+        let on_click = self.scroll_handle.clone();
+        let on_drag = self.scroll_handle.clone();
+
+        div()
+            .size_full()
+            .child(
+                div()
+                    .id("file-browser-scrollbar")
+                    .absolute()
+                    .top(-scroll_offset.y)
+                    .right_0()
+                    .w(px(SCROLLBAR_WIDTH))
+                    .h(bounds.size.height)
+                    .block_mouse_except_scroll()
+                    .on_mouse_down(MouseButton::Left, move |event, window, _| {
+                        on_click
+                            .scroll_to_item_strict(row_at(event.position.y), ScrollStrategy::Top);
+                        window.refresh();
+                    })
+                    .on_drag(DraggedScrollbar, |_, _, _, cx| cx.new(|_| DraggedScrollbar))
+                    .on_drag_move(move |event: &DragMoveEvent<DraggedScrollbar>, window, _| {
+                        on_drag.scroll_to_item_strict(
+                            row_at(event.event.position.y),
+                            ScrollStrategy::Top,
+                        );
+                        window.refresh();
+                    })
+                    .child(
+                        div()
+                            .absolute()
+                            .top(px(top))
+                            .w_full()
+                            .h(px(height))
+                            .bg(rgba(0x79797966))
+                            .hover(|s| s.bg(rgba(0x797979b3))),
+                    ),
+            )
+            .into_any_element()
+    }
+}
+
+/// How long to wait for more file-system events before reloading folders.
+const FS_EVENT_DEBOUNCE: Duration = Duration::from_millis(100);
+
 pub struct FileBrowser {
-    root_path: PathBuf,
+    roots: WorkspaceRoots,
     selected_file: Option<PathBuf>,
-    expanded_paths: HashSet<PathBuf>,
-    // State for resizing
-    width: f32,
-    is_resizing: bool,
+    /// Cached listings of loaded folders, filled in on the thread pool
+    tree: FileTree,
+    /// Folders being listed right now
+    loading: HashSet<PathBuf>,
+    /// Folders that changed again while being listed
+    stale: HashSet<PathBuf>,
+    scroll_handle: UniformListScrollHandle,
+    _watcher: Option<RecommendedWatcher>,
+    /// Typed while the tree has focus; narrows it to matching paths
+    filter: String,
+    /// The tree narrowed to `filter`, shown instead of `tree`
+    filtered: Option<FileTree>,
+    /// Every file under the roots, listed when filtering starts
+    project_files: Option<Vec<PathBuf>>,
+    /// A path to scroll to once the folders above it are listed
+    pending_reveal: Option<PathBuf>,
+    /// Git state of the roots, read on the thread pool
+    git: GitStatuses,
+    /// Set while git is being read, and whether it changed again meanwhile
+    git_reading: bool,
+    git_stale: bool,
     app_state: Entity<AppState>,
+    focus_handle: FocusHandle,
+    edit: Option<PendingEdit>,
+    /// Where the context menu is open; it acts on `selected_file`
+    context_menu: Option<Point<Pixels>>,
+    /// Last failed file operation or name, shown under the header
+    error: Option<String>,
 }
 
-impl FileBrowser {
-    pub fn new(app_state: Entity<AppState>, _cx: &mut Context<Self>) -> Self {
-        let root = PathBuf::from(".");
-        let mut expanded = HashSet::new();
-        expanded.insert(root.clone()); // Expand root by default
+impl EventEmitter<EntryMoved> for FileBrowser {}
+
+/// Emitted when the git state of the roots was read again and differs.
+pub struct GitStatusChanged;
 
-        Self {
-            root_path: root,
+impl EventEmitter<GitStatusChanged> for FileBrowser {}
+
+/// Files or folders under the roots changed on disk, as reported by the
+/// browser's watcher. Paths are as the platform spelled them, absolute on
+/// some. Lets others follow changes without watching the tree again.
+pub struct FilesChanged {
+    pub paths: Vec<PathBuf>,
+}
+
+impl EventEmitter<FilesChanged> for FileBrowser {}
+
+impl Focusable for FileBrowser {
+    fn focus_handle(&self, _: &gpui::App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl FileBrowser {
+    pub fn new(roots: WorkspaceRoots, app_state: Entity<AppState>, cx: &mut Context<Self>) -> Self {
+        let mut this = Self {
+            tree: FileTree::new(roots.folders.clone()),
+            roots: roots.clone(),
             selected_file: None,
-            expanded_paths: expanded,
-            width: 256.0,
-            is_resizing: false,
+            loading: HashSet::new(),
+            stale: HashSet::new(),
+            scroll_handle: UniformListScrollHandle::new(),
+            _watcher: None,
+            filter: String::new(),
+            filtered: None,
+            project_files: None,
+            pending_reveal: None,
+            git: GitStatuses::default(),
+            git_reading: false,
+            git_stale: false,
             app_state,
+            focus_handle: cx.focus_handle(),
+            edit: None,
+            context_menu: None,
+            error: None,
+        };
+        for folder in roots.folders {
+            this.load_dir(folder, cx);
+        }
+        this.watch(cx);
+        this.refresh_git(cx);
+        this
+    }
+
+    /// Shows a different set of folders, starting over with nothing expanded.
+    pub fn set_roots(&mut self, roots: WorkspaceRoots, cx: &mut Context<Self>) {
+        self.tree = FileTree::new(roots.folders.clone());
+        self.roots = roots.clone();
+        self.selected_file = None;
+        self.loading.clear();
+        self.stale.clear();
+        self.filter.clear();
+        self.filtered = None;
+        self.project_files = None;
+        self.pending_reveal = None;
+        self.edit = None;
+        self.context_menu = None;
+        self.error = None;
+        self.scroll_handle.scroll_to_item(0, ScrollStrategy::Top);
+        for folder in roots.folders {
+            self.load_dir(folder, cx);
+        }
+        self.watch(cx);
+        self.refresh_git(cx);
+        cx.notify();
+    }
+
+    /// Lists `dir` on the thread pool and stores the result in the tree.
+    fn load_dir(&mut self, dir: PathBuf, cx: &mut Context<Self>) {
+        if !self.loading.insert(dir.clone()) {
+            // Whatever is being read now may already be out of date
+            self.stale.insert(dir);
+            return;
+        }
+        let (sender, receiver) = oneshot::channel();
+        let list_path = dir.clone();
+        self.app_state.read(cx).pools.threads.spawn(move || {
+            sender.send(filetree::list_dir(&list_path)).ok();
+        });
+        cx.spawn(async move |this, cx| {
+            let Ok(result) = receiver.await else {
+                return;
+            };
+            this.update(cx, |this, cx| {
+                // Listings from before the roots changed are of no use
+                if this.roots.root_of(&dir).is_none() {
+                    return;
+                }
+                this.loading.remove(&dir);
+                match result {
+                    Ok(entries) => this.tree.set_children(dir.clone(), entries),
+                    Err(_) => this.tree.remove_dir(&dir),
+                }
+                if this.stale.remove(&dir) {
+                    this.load_dir(dir, cx);
+                }
+                this.load_unloaded(cx);
+                this.scroll_to_pending_reveal();
+                cx.notify();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Reads the git state of the roots again on the thread pool, such as
+    /// after files changed or were saved.
+    pub fn refresh_git(&mut self, cx: &mut Context<Self>) {
+        if self.git_reading {
+            self.git_stale = true;
+            return;
+        }
+        self.git_reading = true;
+        let (sender, receiver) = oneshot::channel();
+        let folders = self.roots.folders.clone();
+        let read_folders = folders.clone();
+        self.app_state.read(cx).pools.threads.spawn(move || {
+            sender.send(GitStatuses::read(&read_folders)).ok();
+        });
+        cx.spawn(async move |this, cx| {
+            let Ok(git) = receiver.await else {
+                return;
+            };
+            this.update(cx, |this, cx| {
+                this.git_reading = false;
+                if std::mem::take(&mut this.git_stale) || this.roots.folders != folders {
+                    this.refresh_git(cx);
+                    return;
+                }
+                if this.git != git {
+                    this.git = git;
+                    cx.emit(GitStatusChanged);
+                    cx.notify();
+                }
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// State of a file or folder in git, for the tab bar.
+    pub fn git_status(&self, path: &Path) -> Option<GitStatus> {
+        self.git.get(path)
+    }
+
+    /// Lists expanded folders that haven't been read yet, such as ones a
+    /// reveal expanded before their parent was loaded.
+    fn load_unloaded(&mut self, cx: &mut Context<Self>) {
+        for dir in self.tree.unloaded_dirs() {
+            if !self.loading.contains(&dir) {
+                self.load_dir(dir, cx);
+            }
+        }
+    }
+
+    /// Re-lists the folder `path` is in, after it was changed from here.
+    fn reload_parent(&mut self, path: &Path, cx: &mut Context<Self>) {
+        if let Some(parent) = path.parent()
+            && self.tree.is_loaded(parent)
+        {
+            self.load_dir(parent.to_path_buf(), cx);
         }
     }
 
+    /// Watches the roots so changes made outside the editor show up.
+    /// Events are batched briefly and only loaded folders are re-listed.
+    fn watch(&mut self, cx: &mut Context<Self>) {
+        let (sender, mut receiver) = mpsc::unbounded::<Vec<PathBuf>>();
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            if let Ok(event) = event {
+                sender.unbounded_send(event.paths).ok();
+            }
+        })
+        .and_then(|mut watcher| {
+            for folder in &self.roots.folders {
+                watcher.watch(folder, RecursiveMode::Recursive)?;
+            }
+            Ok(watcher)
+        });
+        self._watcher = match watcher {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                eprintln!("Failed to watch workspace folders for changes: {}", e);
+                None
+            }
+        };
+
+        cx.spawn(async move |this, cx| {
+            while let Some(mut paths) = receiver.next().await {
+                cx.background_executor().timer(FS_EVENT_DEBOUNCE).await;
+                while let Ok(more) = receiver.try_recv() {
+                    paths.extend(more);
+                }
+                let mut dirs = HashSet::new();
+                let updated = this.update(cx, |this, cx| {
+                    for path in paths.iter().filter_map(|path| this.tree_path(path)) {
+                        dirs.extend(this.tree.stale_dirs(&path));
+                    }
+                    for dir in dirs {
+                        this.load_dir(dir, cx);
+                    }
+                    // Saves, checkouts and staging all show up here
+                    this.refresh_git(cx);
+                    cx.emit(FilesChanged { paths });
+                });
+                if updated.is_err() {
+                    break;
+                }
+            }
+        })
+        .detach();
+    }
+
+    /// `path` as the tree spells it (`./src/main.rs`), or `None` if it is
+    /// outside the roots. Some platforms report absolute paths for events,
+    /// and files opened from a dialog have them too. Other relative paths
+    /// are taken to be in the primary root.
+    fn tree_path(&self, path: &Path) -> Option<PathBuf> {
+        if path.is_absolute() {
+            return self.roots.folders.iter().find_map(|folder| {
+                let absolute = std::path::absolute(folder).ok()?;
+                Some(folder.join(path.strip_prefix(absolute).ok()?))
+            });
+        }
+        if self.roots.root_of(path).is_some() {
+            return Some(path.to_path_buf());
+        }
+        Some(self.roots.primary().join(path))
+    }
+
+    fn is_root(&self, path: &Path) -> bool {
+        self.roots.folders.iter().any(|folder| folder == path)
+    }
+
+    /// The tree being shown, narrowed by the filter if there is one.
+    fn visible(&self) -> &FileTree {
+        self.filtered.as_ref().unwrap_or(&self.tree)
+    }
+
     fn toggle_expand(&mut self, path: PathBuf, cx: &mut Context<Self>) {
-        if self.expanded_paths.contains(&path) {
-            self.expanded_paths.remove(&path);
+        if let Some(filtered) = &mut self.filtered {
+            // Everything in a filtered tree is already listed
+            if filtered.is_expanded(&path) {
+                filtered.collapse(&path);
+            } else {
+                filtered.expand(&path);
+            }
+        } else if self.tree.is_expanded(&path) {
+            self.tree.collapse(&path);
+        } else if self.tree.expand(&path) {
+            self.load_dir(path, cx);
+        }
+        cx.notify();
+    }
+
+    fn set_filter(&mut self, filter: String, cx: &mut Context<Self>) {
+        self.filter = filter;
+        if self.filter.is_empty() {
+            self.filtered = None;
+            self.project_files = None;
+            if let Some(ix) = self.selected_file.as_ref().and_then(|p| self.tree.position(p)) {
+                self.scroll_handle.scroll_to_item(ix, ScrollStrategy::Center);
+            }
+        } else if self.project_files.is_none() {
+            self.filtered = Some(FileTree::new(self.roots.folders.clone()));
+            self.list_project_files(cx);
         } else {
-            self.expanded_paths.insert(path);
+            self.apply_filter();
         }
         cx.notify();
     }
 
+    /// Lists every file under the roots on the thread pool, then filters.
+    fn list_project_files(&mut self, cx: &mut Context<Self>) {
+        let (sender, receiver) = oneshot::channel();
+        let folders = self.roots.folders.clone();
+        let list_folders = folders.clone();
+        self.app_state.read(cx).pools.threads.spawn(move || {
+            let files: Vec<PathBuf> = list_folders
+                .iter()
+                .flat_map(|folder| project_files(folder, &SearchScope::default()).unwrap_or_default())
+                .collect();
+            sender.send(files).ok();
+        });
+        cx.spawn(async move |this, cx| {
+            let Ok(files) = receiver.await else {
+                return;
+            };
+            this.update(cx, |this, cx| {
+                // The filter may have been cleared or the roots changed in the meantime
+                if !this.filter.is_empty() && this.roots.folders == folders {
+                    this.project_files = Some(files);
+                    this.apply_filter();
+                    cx.notify();
+                }
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    fn apply_filter(&mut self) {
+        let Some(files) = &self.project_files else {
+            return;
+        };
+        let filtered = FileTree::matching(self.roots.folders.clone(), files, &self.filter);
+        // Keep the selection if it still matches, otherwise take the first match
+        let selected = match &self.selected_file {
+            Some(path) if filtered.position(path).is_some() => Some(path.clone()),
+            _ => filtered.rows().first().map(|row| row.path.clone()),
+        };
+        if let Some(ix) = selected.as_ref().and_then(|p| filtered.position(p)) {
+            self.scroll_handle.scroll_to_item(ix, ScrollStrategy::Top);
+        }
+        self.selected_file = selected;
+        self.filtered = Some(filtered);
+    }
+
+    /// Typing narrows the tree; backspace and escape undo it.
+    fn handle_key(&mut self, event: &KeyDownEvent, _: &mut Window, cx: &mut Context<Self>) {
+        if self.edit.is_some() {
+            return;
+        }
+        let keystroke = &event.keystroke;
+        let modifiers = &keystroke.modifiers;
+        if modifiers.control || modifiers.alt || modifiers.platform || modifiers.function {
+            return;
+        }
+        match keystroke.key.as_str() {
+            "backspace" if !self.filter.is_empty() => {
+                let mut filter = self.filter.clone();
+                filter.pop();
+                self.set_filter(filter, cx);
+            }
+            "escape" if !self.filter.is_empty() => self.set_filter(String::new(), cx),
+            _ => match &keystroke.key_char {
+                Some(text) if !text.chars().any(char::is_control) => {
+                    let filter = format!("{}{}", self.filter, text);
+                    self.set_filter(filter, cx);
+                }
+                _ => return,
+            },
+        }
+        cx.stop_propagation();
+    }
+
+    fn select_row(&mut self, ix: usize, cx: &mut Context<Self>) {
+        if let Some(row) = self.visible().rows().get(ix) {
+            self.selected_file = Some(row.path.clone());
+            self.scroll_handle.scroll_to_item(ix, ScrollStrategy::Top);
+            cx.notify();
+        }
+    }
+
+    fn selected_row(&self) -> Option<(usize, &TreeRow)> {
+        let rows = self.visible().rows();
+        let ix = self.visible().position(self.selected_file.as_ref()?)?;
+        Some((ix, &rows[ix]))
+    }
+
+    fn select_previous(&mut self, _: &SelectPrevious, _: &mut Window, cx: &mut Context<Self>) {
+        let ix = match self.selected_row() {
+            Some((ix, _)) => ix.saturating_sub(1),
+            None => self.visible().rows().len().saturating_sub(1),
+        };
+        self.select_row(ix, cx);
+    }
+
+    fn select_next(&mut self, _: &SelectNext, _: &mut Window, cx: &mut Context<Self>) {
+        let ix = match self.selected_row() {
+            Some((ix, _)) => (ix + 1).min(self.visible().rows().len().saturating_sub(1)),
+            None => 0,
+        };
+        self.select_row(ix, cx);
+    }
+
+    fn select_first(&mut self, _: &SelectFirst, _: &mut Window, cx: &mut Context<Self>) {
+        self.select_row(0, cx);
+    }
+
+    fn select_last(&mut self, _: &SelectLast, _: &mut Window, cx: &mut Context<Self>) {
+        self.select_row(self.visible().rows().len().saturating_sub(1), cx);
+    }
+
+    /// Collapses the selected folder, or moves up to the parent folder.
+    fn collapse_entry(&mut self, _: &CollapseEntry, _: &mut Window, cx: &mut Context<Self>) {
+        let Some((_, row)) = self.selected_row() else {
+            return;
+        };
+        if row.expanded {
+            let path = row.path.clone();
+            self.toggle_expand(path, cx);
+        } else if let Some(ix) = row.path.parent().and_then(|p| self.visible().position(p)) {
+            self.select_row(ix, cx);
+        }
+    }
+
+    /// Expands the selected folder, or moves down into it once expanded.
+    fn expand_entry(&mut self, _: &ExpandEntry, _: &mut Window, cx: &mut Context<Self>) {
+        let Some((ix, row)) = self.selected_row() else {
+            return;
+        };
+        if !row.is_dir {
+            return;
+        }
+        if !row.expanded {
+            let path = row.path.clone();
+            self.toggle_expand(path, cx);
+        } else if self
+            .visible()
+            .rows()
+            .get(ix + 1)
+            .is_some_and(|next| next.depth > row.depth)
+        {
+            self.select_row(ix + 1, cx);
+        }
+    }
+
+    fn open_entry(&mut self, _: &OpenEntry, window: &mut Window, cx: &mut Context<Self>) {
+        let Some((_, row)) = self.selected_row() else {
+            return;
+        };
+        let path = row.path.clone();
+        if row.is_dir {
+            self.toggle_expand(path, cx);
+        } else {
+            self.open_file(path, window, cx);
+        }
+    }
+
+    /// Selects `path` in the tree, expanding the folders above it and
+    /// scrolling to it once they are listed. Clears the filter.
+    pub fn reveal_file(&mut self, path: &Path, cx: &mut Context<Self>) {
+        let Some(path) = self.tree_path(path) else {
+            return;
+        };
+        if !self.filter.is_empty() {
+            self.set_filter(String::new(), cx);
+        }
+        self.reveal(&path, cx);
+        self.selected_file = Some(path.clone());
+        self.pending_reveal = Some(path);
+        self.scroll_to_pending_reveal();
+        cx.notify();
+    }
+
+    fn scroll_to_pending_reveal(&mut self) {
+        if let Some(ix) = self.pending_reveal.as_ref().and_then(|p| self.tree.position(p)) {
+            self.scroll_handle.scroll_to_item(ix, ScrollStrategy::Center);
+            self.pending_reveal = None;
+        }
+    }
+
     fn open_file(&mut self, path: PathBuf, window: &mut Window, cx: &mut Context<Self>) {
         if path.is_file() {
             self.selected_file = Some(path.clone());
-            window.dispatch_action(Box::new(OpenPath { path }), cx);
+            window.dispatch_action(Box::new(OpenPath::new(path)), cx);
         }
     }
 
-    fn render_tree(
-        &self,
-        path: &Path,
-        depth: usize,
-        cx: &mut Context<Self>,
-    ) -> Vec<impl IntoElement> {
-        let mut elements = Vec::new();
-        let app_state = self.app_state.read(cx);
-
-        if let Ok(entries) = app_state.pools.resources.list_dir(path) {
-            let mut files: Vec<PathBuf> =
-                entries.filter_map(|e| e.ok()).map(|e| e.path()).collect();
-
-            // Sort directories first, then files
-            files.sort_by(|a, b| {
-                let a_is_dir = a.is_dir();
-                let b_is_dir = b.is_dir();
-                if a_is_dir == b_is_dir {
-                    a.cmp(b)
-                } else if a_is_dir {
-                    std::cmp::Ordering::Less
-                } else {
-                    std::cmp::Ordering::Greater
+    /// Expands every folder from its root down to `path` and scrolls to it
+    /// if it is already listed.
+    fn reveal(&mut self, path: &Path, cx: &mut Context<Self>) {
+        for ancestor in path.ancestors().skip(1) {
+            self.tree.expand(ancestor);
+            if self.is_root(ancestor) {
+                break;
+            }
+        }
+        self.load_unloaded(cx);
+        if let Some(ix) = self.tree.position(path) {
+            self.scroll_handle.scroll_to_item(ix, ScrollStrategy::Center);
+        }
+    }
+
+    /// The folder new entries go in: the selected folder, or the folder of
+    /// the selected file.
+    fn target_dir(&self) -> PathBuf {
+        match &self.selected_file {
+            Some(path) if path.is_dir() => path.clone(),
+            Some(path) => path
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| self.roots.primary().to_path_buf()),
+            None => self.roots.primary().to_path_buf(),
+        }
+    }
+
+    fn start_edit(&mut self, kind: EditKind, window: &mut Window, cx: &mut Context<Self>) {
+        self.context_menu = None;
+        self.error = None;
+        if !self.filter.is_empty() {
+            self.set_filter(String::new(), cx);
+        }
+        let target = match kind {
+            EditKind::Rename => match &self.selected_file {
+                Some(path) if !self.is_root(path) => path.clone(),
+                _ => return,
+            },
+            EditKind::NewFile | EditKind::NewFolder => {
+                let dir = self.target_dir();
+                if self.tree.expand(&dir) {
+                    self.load_dir(dir.clone(), cx);
                 }
-            });
+                dir
+            }
+        };
 
-            for file_path in files {
-                let file_name = file_path
+        let input = cx.new(|cx| {
+            let placeholder = match kind {
+                EditKind::NewFile => "File name",
+                EditKind::NewFolder => "Folder name",
+                EditKind::Rename => "New name",
+            };
+            let mut input = TextInput::new(placeholder, cx);
+            if kind == EditKind::Rename {
+                let name = target
                     .file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("?")
-                    .to_string();
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                // Select the name without its extension, like most file managers
+                let stem_len = match name.rfind('.') {
+                    Some(i) if i > 0 && !target.is_dir() => i,
+                    _ => name.len(),
+                };
+                input.set_text(name, cx);
+                input.select(0..stem_len, cx);
+            }
+            input
+        });
+        let focus = input.read(cx).focus_handle.clone();
+        let blur = cx.on_blur(&focus, window, |this, _, cx| this.cancel_edit(cx));
+        window.focus(&focus);
+        self.edit = Some(PendingEdit {
+            kind,
+            target,
+            input,
+            _blur: blur,
+        });
+        cx.notify();
+    }
 
-                let is_dir = file_path.is_dir();
-                let is_expanded = self.expanded_paths.contains(&file_path);
+    fn cancel_edit(&mut self, cx: &mut Context<Self>) {
+        if self.edit.take().is_some() {
+            self.error = None;
+            cx.notify();
+        }
+    }
 
-                let icon = if is_dir {
-                    if is_expanded { DIR_ICON } else { DIR_ICON }
-                } else {
-                    FILE_ICON
-                };
-                // let display_icon = if is_dir { DIR_ICON } else { FILE_ICON }; // Removed redundant variable
+    fn confirm_edit(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(edit) = &self.edit else {
+            return;
+        };
+        let name = edit.input.read(cx).text().to_string();
+        let target = edit.target.clone();
+        let result = match edit.kind {
+            EditKind::NewFile => fileops::create_file(&target, &name),
+            EditKind::NewFolder => fileops::create_dir(&target, &name),
+            EditKind::Rename => fileops::rename(&target, &name),
+        };
+        let kind = edit.kind;
 
-                let padding = 10.0 + (depth as f32 * 15.0);
-                let path_clone = file_path.clone();
-                let path_clone_2 = file_path.clone();
+        match result {
+            Ok(path) => {
+                self.edit = None;
+                self.error = None;
+                window.focus(&self.focus_handle);
+                match kind {
+                    EditKind::NewFile | EditKind::NewFolder => {
+                        self.load_dir(target, cx);
+                        self.reveal(&path, cx);
+                        if kind == EditKind::NewFile {
+                            self.open_file(path, window, cx);
+                        } else {
+                            self.selected_file = Some(path);
+                        }
+                    }
+                    EditKind::Rename => self.entry_moved(target, path, cx),
+                }
+            }
+            Err(e) => self.error = Some(e.to_string()),
+        }
+        cx.notify();
+    }
 
-                let element = div()
-                    .pl(px(padding))
-                    .pr_2()
-                    .py_1()
-                    .text_color(rgb(0xcccccc))
-                    .text_xs()
-                    .hover(|d| d.bg(rgb(0x303030)))
-                    .cursor_pointer()
-                    .child(format!("{} {}", icon, file_name))
-                    .on_mouse_down(
-                        MouseButton::Left,
-                        cx.listener(move |this, _, window, cx| {
-                            if is_dir {
-                                this.toggle_expand(path_clone.clone(), cx);
-                            } else {
-                                this.open_file(path_clone.clone(), window, cx);
-                            }
-                        }),
-                    );
+    /// Keeps selection, expanded folders and open tabs pointing at an
+    /// entry that moved.
+    fn entry_moved(&mut self, from: PathBuf, to: PathBuf, cx: &mut Context<Self>) {
+        if from == to {
+            return;
+        }
+        self.app_state.read(cx).pools.resources.forget(&from);
+        self.tree.moved(&from, &to);
+        self.reload_parent(&from, cx);
+        self.reload_parent(&to, cx);
+        self.load_unloaded(cx);
+        self.reveal(&to, cx);
+        self.selected_file = Some(to.clone());
+        cx.emit(EntryMoved { from, to });
+    }
 
-                elements.push(element.into_any_element());
+    fn move_entry(&mut self, path: PathBuf, dir: PathBuf, cx: &mut Context<Self>) {
+        // Workspace folders stay where they are
+        if self.is_root(&path) {
+            return;
+        }
+        self.error = None;
+        match fileops::move_into(&path, &dir) {
+            Ok(to) => self.entry_moved(path, to, cx),
+            Err(e) => self.error = Some(format!("Couldn't move {}: {}", path.display(), e)),
+        }
+        cx.notify();
+    }
 
-                if is_dir && is_expanded {
-                    elements.extend(
-                        self.render_tree(&path_clone_2, depth + 1, cx)
-                            .into_iter()
-                            .map(|e| e.into_any_element()),
-                    );
-                }
+    fn create_file(&mut self, _: &CreateFile, window: &mut Window, cx: &mut Context<Self>) {
+        self.start_edit(EditKind::NewFile, window, cx);
+    }
+
+    fn create_folder(&mut self, _: &CreateFolder, window: &mut Window, cx: &mut Context<Self>) {
+        self.start_edit(EditKind::NewFolder, window, cx);
+    }
+
+    fn rename_entry(&mut self, _: &RenameEntry, window: &mut Window, cx: &mut Context<Self>) {
+        self.start_edit(EditKind::Rename, window, cx);
+    }
+
+    fn duplicate_entry(&mut self, _: &DuplicateEntry, _: &mut Window, cx: &mut Context<Self>) {
+        self.context_menu = None;
+        self.error = None;
+        let Some(path) = self.selected_file.clone().filter(|p| !self.is_root(p)) else {
+            return;
+        };
+        match fileops::duplicate(&path) {
+            Ok(copy) => {
+                self.reload_parent(&copy, cx);
+                self.selected_file = Some(copy);
+            }
+            Err(e) => self.error = Some(format!("Couldn't duplicate {}: {}", path.display(), e)),
+        }
+        cx.notify();
+    }
+
+    /// Asks before moving the selected entry to the trash.
+    fn trash_entry(&mut self, _: &TrashEntry, window: &mut Window, cx: &mut Context<Self>) {
+        self.context_menu = None;
+        self.error = None;
+        let Some(path) = self.selected_file.clone().filter(|p| !self.is_root(p)) else {
+            return;
+        };
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let answer = window.prompt(
+            PromptLevel::Warning,
+            &format!("Move \"{}\" to the trash?", name),
+            Some("You can restore it from the trash."),
+            &["Move to Trash", "Cancel"],
+            cx,
+        );
+        let pools = self.app_state.read(cx).pools.clone();
+        cx.spawn(async move |this, cx| {
+            if answer.await != Ok(0) {
+                return;
             }
+            // Across file systems trashing copies the whole entry
+            let (sender, receiver) = oneshot::channel();
+            let trash_path = path.clone();
+            pools.threads.spawn(move || {
+                sender.send(fileops::trash(&trash_path)).ok();
+            });
+            let Ok(result) = receiver.await else {
+                return;
+            };
+            this.update(cx, |this, cx| {
+                match result {
+                    Ok(_) => {
+                        this.app_state.read(cx).pools.resources.forget(&path);
+                        this.tree.remove_dir(&path);
+                        this.reload_parent(&path, cx);
+                        this.selected_file = path.parent().map(Path::to_path_buf);
+                    }
+                    Err(e) => {
+                        this.error = Some(format!("Couldn't trash {}: {}", path.display(), e))
+                    }
+                }
+                cx.notify();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    fn copy_path(&mut self, _: &CopyPath, _: &mut Window, cx: &mut Context<Self>) {
+        self.context_menu = None;
+        if let Some(path) = &self.selected_file {
+            let absolute = std::fs::canonicalize(path)
+                .or_else(|_| std::path::absolute(path))
+                .unwrap_or_else(|_| path.clone());
+            cx.write_to_clipboard(ClipboardItem::new_string(
+                absolute.to_string_lossy().to_string(),
+            ));
         }
-        elements
+        cx.notify();
+    }
+
+    fn copy_relative_path(&mut self, _: &CopyRelativePath, _: &mut Window, cx: &mut Context<Self>) {
+        self.context_menu = None;
+        if let Some(path) = &self.selected_file {
+            cx.write_to_clipboard(ClipboardItem::new_string(self.roots.display_path(path)));
+        }
+        cx.notify();
+    }
+
+    fn render_edit_row(&self, edit: &PendingEdit, depth: usize, cx: &mut Context<Self>) -> AnyElement {
+        let theme = IconTheme::global(cx);
+        let icon = match edit.kind {
+            EditKind::NewFolder => theme.folder_icon(false),
+            EditKind::Rename if edit.target.is_dir() => theme.folder_icon(false),
+            EditKind::Rename => theme.file_icon(&edit.target),
+            _ => theme.file.as_str(),
+        }
+        .to_string();
+        div()
+            .h(px(ROW_HEIGHT))
+            .pl(px(10.0 + depth as f32 * 15.0))
+            .pr_2()
+            .text_xs()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap_1()
+            .on_key_down(cx.listener(|this, event: &KeyDownEvent, window, cx| {
+                match event.keystroke.key.as_str() {
+                    "enter" => this.confirm_edit(window, cx),
+                    "escape" => {
+                        this.cancel_edit(cx);
+                        window.focus(&this.focus_handle);
+                    }
+                    _ => return,
+                }
+                cx.stop_propagation();
+            }))
+            .child(icon)
+            .child(
+                div()
+                    .flex_1()
+                    .px_1()
+                    .bg(rgb(0x252526))
+                    .border_1()
+                    .border_color(if self.error.is_some() { rgb(0xe74c3c) } else { rgb(0x007acc) })
+                    .text_color(rgb(0xffffff))
+                    .child(edit.input.clone()),
+            )
+            .into_any_element()
+    }
+
+    /// Where the row for a new entry being named goes: the top of its
+    /// folder, as (row index, depth).
+    fn new_entry_row(&self) -> Option<(usize, usize)> {
+        let edit = self.edit.as_ref().filter(|edit| edit.kind != EditKind::Rename)?;
+        match self.visible().position(&edit.target) {
+            Some(ix) => Some((ix + 1, self.visible().rows()[ix].depth + 1)),
+            // A lone root has no row, its entries start at the top
+            None if self.is_root(&edit.target) => Some((0, 0)),
+            None => None,
+        }
+    }
+
+    /// Renders the visible slice of the tree, with the new-entry row
+    /// spliced in at `new_entry`.
+    fn render_rows(
+        &self,
+        range: Range<usize>,
+        new_entry: Option<(usize, usize)>,
+        cx: &mut Context<Self>,
+    ) -> Vec<AnyElement> {
+        range
+            .filter_map(|ix| {
+                let ix = match new_entry {
+                    Some((row, depth)) if row == ix => {
+                        return Some(self.render_edit_row(self.edit.as_ref()?, depth, cx));
+                    }
+                    Some((row, _)) if ix > row => ix - 1,
+                    _ => ix,
+                };
+                let row = self.visible().rows().get(ix)?;
+                Some(match &self.edit {
+                    Some(edit) if edit.kind == EditKind::Rename && edit.target == row.path => {
+                        self.render_edit_row(edit, row.depth, cx)
+                    }
+                    _ => self.render_entry(row, cx),
+                })
+            })
+            .collect()
+    }
+
+    fn render_entry(&self, row: &TreeRow, cx: &mut Context<Self>) -> AnyElement {
+        let file_path = row.path.as_path();
+        let is_dir = row.is_dir;
+        let file_name = if self.is_root(file_path) {
+            root_name(file_path)
+        } else {
+            file_path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("?")
+                .to_string()
+        };
+        let icon = IconTheme::global(cx)
+            .icon(file_path, is_dir, row.expanded)
+            .to_string();
+        let status = self.git.get(file_path);
+        let padding = 10.0 + (row.depth as f32 * 15.0);
+        let is_selected = self.selected_file.as_deref() == Some(file_path);
+        // Dropping on a file moves into the folder it is in
+        let drop_dir = if is_dir {
+            file_path.to_path_buf()
+        } else {
+            file_path
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| self.roots.primary().to_path_buf())
+        };
+        let path = file_path.to_path_buf();
+        let click_path = path.clone();
+        let menu_path = path.clone();
+
+        div()
+            .id(SharedString::from(file_path.to_string_lossy().to_string()))
+            .h(px(ROW_HEIGHT))
+            .pl(px(padding))
+            .pr_2()
+            .flex()
+            .items_center()
+            .whitespace_nowrap()
+            .text_color(rgb(match status {
+                _ if row.ignored => 0x6e6e6e,
+                Some(status) => status.color(),
+                None => 0xcccccc,
+            }))
+            .text_xs()
+            .when(is_selected, |this| this.bg(rgb(0x37373d)))
+            .hover(|d| d.bg(rgb(0x303030)))
+            .cursor_pointer()
+            .child(div().flex_1().child(format!("{} {}", icon, file_name)))
+            .children(
+                status
+                    .filter(|status| *status != GitStatus::Ignored)
+                    .map(|status| div().pl_1().child(status.letter())),
+            )
+            .on_click(cx.listener(move |this, _, window, cx| {
+                this.context_menu = None;
+                if is_dir {
+                    this.selected_file = Some(click_path.clone());
+                    this.toggle_expand(click_path.clone(), cx);
+                    window.focus(&this.focus_handle);
+                } else {
+                    this.open_file(click_path.clone(), window, cx);
+                }
+            }))
+            .on_mouse_down(
+                MouseButton::Right,
+                cx.listener(move |this, event: &MouseDownEvent, window, cx| {
+                    this.selected_file = Some(menu_path.clone());
+                    this.context_menu = Some(event.position);
+                    window.focus(&this.focus_handle);
+                    cx.stop_propagation();
+                    cx.notify();
+                }),
+            )
+            .on_drag(DraggedEntry { path }, |dragged, _, _, cx| {
+                cx.new(|_| dragged.clone())
+            })
+            .drag_over::<DraggedEntry>(|style, _, _, _| style.bg(rgb(0x04395e)))
+            .on_drop(cx.listener(move |this, dragged: &DraggedEntry, _, cx| {
+                this.move_entry(dragged.path.clone(), drop_dir.clone(), cx);
+                cx.stop_propagation();
+            }))
+            .into_any_element()
+    }
+
+    fn render_context_menu(&self, position: Point<Pixels>, cx: &mut Context<Self>) -> impl IntoElement {
+        // Only one of several roots can be taken out of the workspace
+        let remove_folder = self
+            .selected_file
+            .clone()
+            .filter(|path| self.roots.folders.len() > 1 && self.is_root(path));
+        let item = |id: &'static str, label: &'static str, action: Box<dyn gpui::Action>| {
+            div()
+                .id(id)
+                .px_3()
+                .py_1()
+                .text_color(rgb(0xcccccc))
+                .hover(|s| s.bg(rgb(0x04395e)).text_color(rgb(0xffffff)))
+                .cursor_pointer()
+                .child(label)
+                .on_click(cx.listener(move |this, _, window, cx| {
+                    this.context_menu = None;
+                    window.focus(&this.focus_handle);
+                    window.dispatch_action(action.boxed_clone(), cx);
+                }))
+        };
+
+        deferred(
+            anchored().position(position).snap_to_window().child(
+                div()
+                    .min_w(px(180.0))
+                    .py_1()
+                    .bg(rgb(0x252526))
+                    .border_1()
+                    .border_color(rgb(0x454545))
+                    .shadow_lg()
+                    .text_xs()
+                    .flex()
+                    .flex_col()
+                    .on_mouse_down_out(cx.listener(|this, _, _, cx| {
+                        this.context_menu = None;
+                        cx.notify();
+                    }))
+                    .child(item("menu-new-file", "New File", Box::new(CreateFile)))
+                    .child(item("menu-new-folder", "New Folder", Box::new(CreateFolder)))
+                    .child(item("menu-rename", "Rename", Box::new(RenameEntry)))
+                    .child(item("menu-duplicate", "Duplicate", Box::new(DuplicateEntry)))
+                    .child(item("menu-copy-path", "Copy Path", Box::new(CopyPath)))
+                    .child(item("menu-copy-relative-path", "Copy Relative Path", Box::new(CopyRelativePath)))
+                    .child(item("menu-trash", "Move to Trash", Box::new(TrashEntry)))
+                    .children(remove_folder.map(|path| {
+                        item(
+                            "menu-remove-folder",
+                            "Remove Folder from Workspace",
+                            Box::new(RemoveWorkspaceFolder { path }),
+                        )
+                    })),
+            ),
+        )
+        .with_priority(1)
     }
 }
 
 impl Render for FileBrowser {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        let root_path_str = self.root_path.to_string_lossy().to_string();
+        let title = match (&self.roots.file, self.roots.folders.as_slice()) {
+            (Some(file), _) => file
+                .file_stem()
+                .map(|stem| format!("{} (Workspace)", stem.to_string_lossy()))
+                .unwrap_or_default(),
+            (None, [folder]) => root_name(folder),
+            (None, _) => "Workspace".to_string(),
+        };
+        let root = self.roots.primary().to_path_buf();
+        let new_entry = self.new_entry_row();
+        let row_count = self.visible().rows().len() + new_entry.is_some() as usize;
+
+        // Single-key bindings only apply while no name is being typed
+        let mut key_context = KeyContext::new_with_defaults();
+        key_context.add("FileBrowser");
+        if self.edit.is_none() {
+            key_context.add("not_editing");
+        }
 
         div()
             .relative()
             .flex()
-            .h_full()
-            .on_mouse_up(
-                MouseButton::Left,
-                cx.listener(|this, _: &MouseUpEvent, _, cx| {
-                    this.is_resizing = false;
-                    cx.notify();
-                }),
-            )
+            .size_full()
             .child(
                 // The Sidebar Content
                 div()
-                    .w(px(self.width))
-                    .h_full()
+                    .id("file-browser")
+                    .key_context(key_context)
+                    .track_focus(&self.focus_handle)
+                    .on_action(cx.listener(Self::create_file))
+                    .on_action(cx.listener(Self::create_folder))
+                    .on_action(cx.listener(Self::rename_entry))
+                    .on_action(cx.listener(Self::trash_entry))
+                    .on_action(cx.listener(Self::duplicate_entry))
+                    .on_action(cx.listener(Self::copy_path))
+                    .on_action(cx.listener(Self::copy_relative_path))
+                    .on_action(cx.listener(Self::select_previous))
+                    .on_action(cx.listener(Self::select_next))
+                    .on_action(cx.listener(Self::select_first))
+                    .on_action(cx.listener(Self::select_last))
+                    .on_action(cx.listener(Self::collapse_entry))
+                    .on_action(cx.listener(Self::expand_entry))
+                    .on_action(cx.listener(Self::open_entry))
+                    .on_key_down(cx.listener(Self::handle_key))
+                    .size_full()
                     .bg(rgb(0x1e1e1e))
-                    .border_r_1()
-                    .border_color(rgb(0x404040))
                     .flex()
                     .flex_col()
                     .overflow_hidden()
+                    // Right-clicking empty space acts on the root
+                    .on_mouse_down(
+                        MouseButton::Right,
+                        cx.listener(|this, event: &MouseDownEvent, window, cx| {
+                            this.selected_file = None;
+                            this.context_menu = Some(event.position);
+                            window.focus(&this.focus_handle);
+                            cx.notify();
+                        }),
+                    )
+                    .drag_over::<DraggedEntry>(|style, _, _, _| style.bg(rgb(0x252526)))
+                    .on_drop(cx.listener(move |this, dragged: &DraggedEntry, _, cx| {
+                        this.move_entry(dragged.path.clone(), root.clone(), cx);
+                    }))
                     .child(
                         div()
                             .px_2()
                             .py_2()
                             .text_color(rgb(0x888888))
                             .text_xs()
-                            .child(
-                                root_path_str
-                                    .split('/')
-                                    .last()
-                                    .unwrap_or(&root_path_str)
-                                    .to_string()
-                                    .to_uppercase(),
-                            ),
+                            .child(title.to_uppercase()),
                     )
+                    .children(self.error.clone().map(|error| {
+                        div()
+                            .px_2()
+                            .pb_1()
+                            .text_xs()
+                            .text_color(rgb(0xe74c3c))
+                            .child(error)
+                    }))
+                    .when(!self.filter.is_empty(), |this| {
+                        let no_matches =
+                            self.project_files.is_some() && self.visible().rows().is_empty();
+                        this.child(
+                            div()
+                                .mx_2()
+                                .mb_1()
+                                .px_1()
+                                .text_xs()
+                                .bg(rgb(0x252526))
+                                .border_1()
+                                .border_color(if no_matches { rgb(0xe74c3c) } else { rgb(0x007acc) })
+                                .text_color(rgb(0xffffff))
+                                .child(format!("Filter: {}", self.filter)),
+                        )
+                    })
                     .child(
                         div()
+                            .relative()
+                            .flex()
                             .flex_col()
-                            // .overflow_y_scrollbar()
-                            .children(self.render_tree(&self.root_path, 0, cx)),
+                            .flex_1()
+                            .overflow_hidden()
+                            .child(
+                                uniform_list(
+                                    "file-browser-rows",
+                                    row_count,
+                                    cx.processor(move |this, range, _, cx| {
+                                        this.render_rows(range, new_entry, cx)
+                                    }),
+                                )
+                                .flex_1()
+                                .track_scroll(self.scroll_handle.clone())
+                                .with_decoration(TreeScrollbar {
+                                    scroll_handle: self.scroll_handle.clone(),
+                                }),
+                            ),
                     ),
             )
-            // The Resize Handle (invisible but wide enough to grab)
-            .child(
-                div()
-                    .absolute()
-                    .left(px(self.width - 2.0))
-                    .w_2()
-                    .h_full()
-                    .cursor_col_resize()
-                    .on_mouse_down(
-                        MouseButton::Left,
-                        cx.listener(|this, _: &MouseDownEvent, _, cx| {
-                            this.is_resizing = true;
-                            cx.notify();
-                        }),
-                    ),
+            .children(
+                self.context_menu
+                    .as_ref()
+                    .map(|position| self.render_context_menu(*position, cx)),
             )
     }
 }
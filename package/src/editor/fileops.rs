@@ -0,0 +1,283 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// Checks a name typed into the file browser. Slashes are allowed when
+/// `nested` so `src/new.rs` creates the folder on the way.
+fn validate_name(name: &str, nested: bool) -> io::Result<&str> {
+    let name = name.trim();
+    let invalid = |message: &str| Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+    if name.is_empty() {
+        return invalid("A name is required");
+    }
+    if name.contains('\0') {
+        return invalid("Names can't contain NUL characters");
+    }
+    if !nested && name.contains(['/', '\\']) {
+        return invalid("Names can't contain slashes");
+    }
+    let path = Path::new(name);
+    if !path.components().all(|c| matches!(c, Component::Normal(_))) {
+        return invalid("Names can't be absolute or contain . or ..");
+    }
+    Ok(name)
+}
+
+/// Whether anything, even a dangling symlink, is at `path`.
+fn occupied(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok()
+}
+
+/// Whether `a` and `b` are the same entry, as when they differ only in case
+/// on a case-insensitive file system.
+fn same_entry(a: &Path, b: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (fs::symlink_metadata(a), fs::symlink_metadata(b)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+            _ => false,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        match (fs::canonicalize(a), fs::canonicalize(b)) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+fn already_exists(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        format!("{} already exists", path.display()),
+    )
+}
+
+/// Creates an empty file under `dir`, along with any folders in `name`.
+pub fn create_file(dir: &Path, name: &str) -> io::Result<PathBuf> {
+    let path = dir.join(validate_name(name, true)?);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    match fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+    {
+        Ok(_) => Ok(path),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Err(already_exists(&path)),
+        Err(e) => Err(e),
+    }
+}
+
+/// Creates a folder under `dir`, along with any parents in `name`.
+pub fn create_dir(dir: &Path, name: &str) -> io::Result<PathBuf> {
+    let path = dir.join(validate_name(name, true)?);
+    if path.exists() {
+        return Err(already_exists(&path));
+    }
+    fs::create_dir_all(&path)?;
+    Ok(path)
+}
+
+/// Renames an entry within its folder.
+pub fn rename(path: &Path, name: &str) -> io::Result<PathBuf> {
+    let name = validate_name(name, false)?;
+    let target = path.with_file_name(name);
+    if target == path {
+        return Ok(target);
+    }
+    // Changing only the case finds the entry itself on case-insensitive file
+    // systems, anything else there is another file
+    if occupied(&target) && !same_entry(path, &target) {
+        return Err(already_exists(&target));
+    }
+    fs::rename(path, &target)?;
+    Ok(target)
+}
+
+/// Moves an entry into another folder, keeping its name.
+pub fn move_into(path: &Path, dir: &Path) -> io::Result<PathBuf> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Nothing to move"))?;
+    if dir.starts_with(path) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "A folder can't be moved into itself",
+        ));
+    }
+    let target = dir.join(name);
+    if target == path {
+        return Ok(target);
+    }
+    if occupied(&target) {
+        return Err(already_exists(&target));
+    }
+    fs::rename(path, &target)?;
+    Ok(target)
+}
+
+/// `name copy.ext`, then `name copy 2.ext` and so on, whichever is free.
+fn duplicate_path(path: &Path) -> PathBuf {
+    let is_dir = path.is_dir();
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    // Dot files like `.env` have no extension
+    let (stem, extension) = match name.rfind('.') {
+        Some(i) if i > 0 && !is_dir => (&name[..i], &name[i..]),
+        _ => (&name[..], ""),
+    };
+    (1..)
+        .map(|n| match n {
+            1 => path.with_file_name(format!("{} copy{}", stem, extension)),
+            n => path.with_file_name(format!("{} copy {}{}", stem, n, extension)),
+        })
+        .find(|candidate| !candidate.exists())
+        .unwrap()
+}
+
+/// Copies `from` to `to`, recreating symlinks as links rather than copying
+/// what they point at, which may be huge or contain the link itself.
+fn copy_recursive(from: &Path, to: &Path) -> io::Result<()> {
+    let file_type = fs::symlink_metadata(from)?.file_type();
+    if file_type.is_symlink() {
+        copy_symlink(from, to)?;
+    } else if file_type.is_dir() {
+        fs::create_dir(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        fs::copy(from, to)?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(from: &Path, to: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(from)?, to)
+}
+
+#[cfg(windows)]
+fn copy_symlink(from: &Path, to: &Path) -> io::Result<()> {
+    use std::os::windows::fs::{symlink_dir, symlink_file};
+    let target = fs::read_link(from)?;
+    if fs::metadata(from).is_ok_and(|m| m.is_dir()) {
+        symlink_dir(target, to)
+    } else {
+        symlink_file(target, to)
+    }
+}
+
+/// Copies a file or folder next to itself.
+pub fn duplicate(path: &Path) -> io::Result<PathBuf> {
+    let target = duplicate_path(path);
+    if let Err(e) = copy_recursive(path, &target) {
+        remove(&target).ok();
+        return Err(e);
+    }
+    Ok(target)
+}
+
+fn remove(path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// Where `path` ended up after `from` was moved to `to`, if it was `from`
+/// or inside it.
+pub fn moved_path(path: &Path, from: &Path, to: &Path) -> Option<PathBuf> {
+    let rest = path.strip_prefix(from).ok()?;
+    Some(if rest.as_os_str().is_empty() {
+        to.to_path_buf()
+    } else {
+        to.join(rest)
+    })
+}
+
+/// Percent-encodes a path for a `.trashinfo` file, leaving slashes alone.
+fn encode_trash_path(path: &Path) -> String {
+    path.to_string_lossy()
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Moves `path` into the trash folder `trash_dir` as laid out by the
+/// freedesktop.org trash spec, so file managers can restore it. Returns
+/// where the entry now lives.
+pub fn trash_into(path: &Path, trash_dir: &Path) -> io::Result<PathBuf> {
+    let original = std::path::absolute(path)?;
+    let files = trash_dir.join("files");
+    let info = trash_dir.join("info");
+    fs::create_dir_all(&files)?;
+    fs::create_dir_all(&info)?;
+
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    // Claiming the info file first keeps two trashings from picking the same name
+    let mut n = 1;
+    let (trashed, info_path, mut info_file) = loop {
+        let candidate = match n {
+            1 => name.to_string(),
+            n => format!("{}.{}", name, n),
+        };
+        let info_path = info.join(format!("{}.trashinfo", candidate));
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&info_path)
+        {
+            Ok(file) => break (files.join(&candidate), info_path, file),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => n += 1,
+            // Such as a name too long once `.trashinfo` is added, or a
+            // read-only trash, which no other name would get past
+            Err(e) => return Err(e),
+        }
+    };
+
+    let result = (|| {
+        write!(
+            info_file,
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            encode_trash_path(&original),
+            chrono::Local::now().format("%Y-%m-%dT%H:%M:%S")
+        )?;
+        // Across file systems a rename fails, fall back to copying
+        if fs::rename(path, &trashed).is_err() {
+            copy_recursive(path, &trashed)?;
+            remove(path)?;
+        }
+        Ok(())
+    })();
+    if let Err(e) = result {
+        fs::remove_file(info_path).ok();
+        return Err(e);
+    }
+    Ok(trashed)
+}
+
+/// Moves `path` to the user's trash.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn trash(path: &Path) -> io::Result<PathBuf> {
+    let data_dir = dirs::data_dir().ok_or_else(|| io::Error::other("No data directory"))?;
+    trash_into(path, &data_dir.join("Trash"))
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+pub fn trash(_path: &Path) -> io::Result<PathBuf> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Moving to the trash isn't supported on this platform",
+    ))
+}
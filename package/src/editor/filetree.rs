@@ -0,0 +1,297 @@
+use ignore::WalkBuilder;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A file or folder in a listed directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TreeEntry {
+    pub path: PathBuf,
+    pub is_dir: bool,
+    /// Matched by a `.gitignore` or similar, shown dimmed
+    pub ignored: bool,
+}
+
+/// One visible line of the tree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TreeRow {
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub depth: usize,
+    pub expanded: bool,
+    pub ignored: bool,
+}
+
+/// Reads a directory, folders first and then by name. Meant to run on the
+/// thread pool since it touches the disk for every entry.
+pub fn list_dir(dir: &Path) -> io::Result<Vec<TreeEntry>> {
+    let visible = unignored_entries(dir);
+    let mut entries: Vec<TreeEntry> = fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| {
+            let path = e.path();
+            // Follows symlinks, so linked folders can be expanded
+            let is_dir = path.is_dir();
+            let ignored = e.file_name() == ".git" || !visible.contains(&path);
+            TreeEntry {
+                path,
+                is_dir,
+                ignored,
+            }
+        })
+        .collect();
+    sort_entries(&mut entries);
+    Ok(entries)
+}
+
+/// Entries of `dir` that the ignore files (`.gitignore`, `.ignore`, also
+/// from parent folders) let through. Hidden files count as not ignored.
+fn unignored_entries(dir: &Path) -> HashSet<PathBuf> {
+    WalkBuilder::new(dir)
+        .max_depth(Some(1))
+        .hidden(false)
+        .require_git(false)
+        .build()
+        .flatten()
+        .filter(|entry| entry.depth() == 1)
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+fn sort_entries(entries: &mut [TreeEntry]) {
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.path.cmp(&b.path)));
+}
+
+/// The browser's view of the workspace: listings of the folders that have
+/// been loaded, which of them are expanded, and the flattened rows that
+/// result. Listings are filled in from outside (see `list_dir`), so nothing
+/// here blocks on the disk.
+///
+/// A single root is shown by its contents alone. With several, each root
+/// gets a row of its own that can be collapsed.
+pub struct FileTree {
+    roots: Vec<PathBuf>,
+    /// Whether roots get rows of their own
+    show_roots: bool,
+    children: HashMap<PathBuf, Vec<TreeEntry>>,
+    expanded: HashSet<PathBuf>,
+    rows: Vec<TreeRow>,
+}
+
+impl FileTree {
+    pub fn new(roots: Vec<PathBuf>) -> Self {
+        let expanded = roots.iter().cloned().collect();
+        Self {
+            show_roots: roots.len() > 1,
+            roots,
+            children: HashMap::new(),
+            expanded,
+            rows: Vec::new(),
+        }
+    }
+
+    /// A fully expanded tree of the `files` whose path under one of the
+    /// `roots` contains `query`, ignoring case, along with the folders
+    /// leading to them. Used to filter the browser without listing every
+    /// folder. Roots without matches are left out.
+    pub fn matching(roots: Vec<PathBuf>, files: &[PathBuf], query: &str) -> Self {
+        let query = query.to_lowercase();
+        let mut children: HashMap<PathBuf, Vec<TreeEntry>> = HashMap::new();
+        let mut seen = HashSet::new();
+        for file in files {
+            let Some((root, relative)) = roots
+                .iter()
+                .find_map(|root| Some((root, file.strip_prefix(root).ok()?)))
+            else {
+                continue;
+            };
+            if !relative.to_string_lossy().to_lowercase().contains(&query) {
+                continue;
+            }
+            // Add the file, then its folders until one is already there
+            let mut path = file.as_path();
+            let mut is_dir = false;
+            while let Some(parent) = path.parent() {
+                if !seen.insert(path.to_path_buf()) {
+                    break;
+                }
+                children
+                    .entry(parent.to_path_buf())
+                    .or_default()
+                    .push(TreeEntry {
+                        path: path.to_path_buf(),
+                        is_dir,
+                        ignored: false,
+                    });
+                if parent == root.as_path() {
+                    break;
+                }
+                path = parent;
+                is_dir = true;
+            }
+        }
+        for entries in children.values_mut() {
+            sort_entries(entries);
+        }
+
+        let expanded: HashSet<PathBuf> = children.keys().cloned().collect();
+        let mut tree = Self {
+            show_roots: roots.len() > 1,
+            roots: roots
+                .into_iter()
+                .filter(|root| children.contains_key(root))
+                .collect(),
+            children,
+            expanded,
+            rows: Vec::new(),
+        };
+        tree.rebuild();
+        tree
+    }
+
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    /// Visible rows, top to bottom. A lone root isn't one of them.
+    pub fn rows(&self) -> &[TreeRow] {
+        &self.rows
+    }
+
+    /// Row index of `path`, if it is visible.
+    pub fn position(&self, path: &Path) -> Option<usize> {
+        self.rows.iter().position(|row| row.path == path)
+    }
+
+    pub fn is_expanded(&self, dir: &Path) -> bool {
+        self.expanded.contains(dir)
+    }
+
+    pub fn is_loaded(&self, dir: &Path) -> bool {
+        self.children.contains_key(dir)
+    }
+
+    /// Expanded folders that haven't been listed yet.
+    pub fn unloaded_dirs(&self) -> Vec<PathBuf> {
+        self.expanded
+            .iter()
+            .filter(|dir| !self.children.contains_key(*dir))
+            .cloned()
+            .collect()
+    }
+
+    /// Expands a folder. Returns true if it still needs to be listed.
+    pub fn expand(&mut self, dir: &Path) -> bool {
+        self.expanded.insert(dir.to_path_buf());
+        self.rebuild();
+        !self.is_loaded(dir)
+    }
+
+    pub fn collapse(&mut self, dir: &Path) {
+        let lone_root = !self.show_roots && self.roots.iter().any(|root| root == dir);
+        if !lone_root && self.expanded.remove(dir) {
+            self.rebuild();
+        }
+    }
+
+    /// Stores a folder's listing. Folders that disappeared from it are
+    /// forgotten along with everything under them.
+    pub fn set_children(&mut self, dir: PathBuf, entries: Vec<TreeEntry>) {
+        if let Some(old) = self.children.get(&dir) {
+            let gone: Vec<PathBuf> = old
+                .iter()
+                .filter(|e| e.is_dir && !entries.iter().any(|n| n.path == e.path && n.is_dir))
+                .map(|e| e.path.clone())
+                .collect();
+            for path in gone {
+                self.forget(&path);
+            }
+        }
+        self.children.insert(dir, entries);
+        self.rebuild();
+    }
+
+    /// Drops a folder that can no longer be listed. Roots stay expanded so
+    /// they are listed again if they come back.
+    pub fn remove_dir(&mut self, dir: &Path) {
+        self.forget(dir);
+        if self.roots.iter().any(|root| root == dir) {
+            self.expanded.insert(dir.to_path_buf());
+        }
+        self.rebuild();
+    }
+
+    fn forget(&mut self, dir: &Path) {
+        self.children.retain(|p, _| !p.starts_with(dir));
+        self.expanded.retain(|p| !p.starts_with(dir));
+    }
+
+    /// Loaded folders whose listing may be out of date after `path`
+    /// changed on disk: the folder it is in, and itself if it is a folder.
+    pub fn stale_dirs(&self, path: &Path) -> Vec<PathBuf> {
+        path.parent()
+            .into_iter()
+            .chain(Some(path))
+            .filter(|dir| self.children.contains_key(*dir))
+            .map(Path::to_path_buf)
+            .collect()
+    }
+
+    /// Carries expansion state over to where a folder was moved.
+    pub fn moved(&mut self, from: &Path, to: &Path) {
+        self.expanded = self
+            .expanded
+            .drain()
+            .map(|p| super::fileops::moved_path(&p, from, to).unwrap_or(p))
+            .collect();
+        self.children.retain(|p, _| !p.starts_with(from));
+        self.rebuild();
+    }
+
+    fn rebuild(&mut self) {
+        let mut rows = Vec::new();
+        if !self.show_roots {
+            for root in &self.roots {
+                self.push_rows(root, 0, false, &mut rows);
+            }
+        } else {
+            for root in &self.roots {
+                let expanded = self.expanded.contains(root);
+                rows.push(TreeRow {
+                    path: root.clone(),
+                    is_dir: true,
+                    depth: 0,
+                    expanded,
+                    ignored: false,
+                });
+                if expanded {
+                    self.push_rows(root, 1, false, &mut rows);
+                }
+            }
+        }
+        self.rows = rows;
+    }
+
+    /// Everything inside an ignored folder counts as ignored too, the ignore
+    /// files only name the folder.
+    fn push_rows(&self, dir: &Path, depth: usize, ignored: bool, rows: &mut Vec<TreeRow>) {
+        let Some(entries) = self.children.get(dir) else {
+            return;
+        };
+        for entry in entries {
+            let expanded = entry.is_dir && self.expanded.contains(&entry.path);
+            let ignored = ignored || entry.ignored;
+            rows.push(TreeRow {
+                path: entry.path.clone(),
+                is_dir: entry.is_dir,
+                depth,
+                expanded,
+                ignored,
+            });
+            if expanded {
+                self.push_rows(&entry.path, depth + 1, ignored, rows);
+            }
+        }
+    }
+}
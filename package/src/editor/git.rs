@@ -0,0 +1,688 @@
+use git2::{DiffOptions, Patch, Repository, Status, StatusOptions};
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// Version control state of a file, from least to most important. A folder
+/// shows the most important state found under it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum GitStatus {
+    Ignored,
+    Untracked,
+    Added,
+    Modified,
+    Conflicted,
+}
+
+impl GitStatus {
+    fn from_git(status: Status) -> Option<Self> {
+        if status.is_conflicted() {
+            Some(GitStatus::Conflicted)
+        } else if status.is_ignored() {
+            Some(GitStatus::Ignored)
+        } else if status.intersects(Status::INDEX_NEW | Status::INDEX_RENAMED) {
+            Some(GitStatus::Added)
+        } else if status.is_wt_new() {
+            Some(GitStatus::Untracked)
+        } else if status.is_empty() || status == Status::CURRENT {
+            None
+        } else {
+            Some(GitStatus::Modified)
+        }
+    }
+
+    /// Short badge shown next to the name.
+    pub fn letter(&self) -> &'static str {
+        match self {
+            GitStatus::Ignored => "I",
+            GitStatus::Untracked => "U",
+            GitStatus::Added => "A",
+            GitStatus::Modified => "M",
+            GitStatus::Conflicted => "C",
+        }
+    }
+
+    pub fn color(&self) -> u32 {
+        match self {
+            GitStatus::Ignored => 0x6e6e6e,
+            GitStatus::Untracked => 0x73c991,
+            GitStatus::Added => 0x81b88b,
+            GitStatus::Modified => 0xe2c08d,
+            GitStatus::Conflicted => 0xe4676b,
+        }
+    }
+}
+
+/// Git state of everything under the workspace roots, read in one go from
+/// the repositories they are in. Paths are kept absolute so tree paths
+/// (`./src/main.rs`) and paths from file dialogs both find their entry.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GitStatuses {
+    files: HashMap<PathBuf, GitStatus>,
+    /// Untracked or ignored folders git reports as a whole, with everything in them
+    dirs: Vec<(PathBuf, GitStatus)>,
+    /// Most important state under each folder
+    folders: HashMap<PathBuf, GitStatus>,
+}
+
+impl GitStatuses {
+    /// Reads the status of every root that is inside a git repository.
+    /// Roots outside one are left out. Meant to run on the thread pool.
+    pub fn read(roots: &[PathBuf]) -> Self {
+        let mut statuses = Self::default();
+        for root in roots {
+            if let Err(e) = statuses.read_root(root) {
+                // Not being in a repository is the usual case, not worth logging
+                if e.code() != git2::ErrorCode::NotFound {
+                    eprintln!("Failed to read git status of {}: {}", root.display(), e);
+                }
+            }
+        }
+        statuses
+    }
+
+    fn read_root(&mut self, root: &Path) -> Result<(), git2::Error> {
+        let repo = Repository::discover(root)?;
+        let Some(workdir) = repo.workdir().and_then(|dir| dir.canonicalize().ok()) else {
+            return Ok(());
+        };
+        let (Ok(canonical_root), Ok(absolute_root)) =
+            (root.canonicalize(), std::path::absolute(root))
+        else {
+            return Ok(());
+        };
+
+        let mut options = StatusOptions::new();
+        options
+            .include_untracked(true)
+            .include_ignored(true)
+            .recurse_untracked_dirs(false)
+            .recurse_ignored_dirs(false)
+            .exclude_submodules(true);
+        for entry in repo.statuses(Some(&mut options))?.iter() {
+            let (Some(relative), Some(status)) =
+                (entry.path(), GitStatus::from_git(entry.status()))
+            else {
+                continue;
+            };
+            // Only what's under the root, spelled the way the root is
+            let Ok(rest) = workdir
+                .join(relative)
+                .strip_prefix(&canonical_root)
+                .map(Path::to_path_buf)
+            else {
+                continue;
+            };
+            let path = absolute_root.join(rest);
+            if relative.ends_with('/') {
+                self.dirs.push((path.clone(), status));
+            } else {
+                self.files.insert(path.clone(), status);
+            }
+            // Ignored files don't make their folders look changed
+            if status == GitStatus::Ignored {
+                continue;
+            }
+            for folder in path.ancestors().skip(1) {
+                let current = self.folders.entry(folder.to_path_buf()).or_insert(status);
+                *current = (*current).max(status);
+                if folder == absolute_root {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// State of a file or folder, or `None` if it is unchanged or not in a
+    /// repository.
+    pub fn get(&self, path: &Path) -> Option<GitStatus> {
+        let path = std::path::absolute(path).ok()?;
+        if let Some(status) = self.files.get(&path) {
+            return Some(*status);
+        }
+        let inherited = self
+            .dirs
+            .iter()
+            .filter(|(dir, _)| path.starts_with(dir))
+            .map(|(_, status)| *status)
+            .max();
+        inherited.max(self.folders.get(&path).copied())
+    }
+}
+
+/// How a run of lines differs from HEAD, for the editor gutter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HunkKind {
+    Added,
+    Modified,
+    /// Lines removed just above `Hunk::new_lines.start`
+    Deleted,
+}
+
+impl HunkKind {
+    pub fn color(&self) -> u32 {
+        match self {
+            HunkKind::Added => 0x81b88b,
+            HunkKind::Modified => 0x1b81a8,
+            HunkKind::Deleted => 0xe4676b,
+        }
+    }
+}
+
+/// One changed run of lines. Ranges are 0-based line numbers into the HEAD
+/// text (`old_lines`) and the buffer (`new_lines`); one of them is empty for
+/// pure additions and deletions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hunk {
+    pub kind: HunkKind,
+    pub old_lines: Range<usize>,
+    pub new_lines: Range<usize>,
+}
+
+impl Hunk {
+    /// The buffer line the hunk is marked on. Deletions are marked on the
+    /// line after them, or the last line at the end of the file.
+    pub fn marker_line(&self, line_count: usize) -> usize {
+        match self.kind {
+            HunkKind::Deleted => self.new_lines.start.min(line_count.saturating_sub(1)),
+            _ => self.new_lines.start,
+        }
+    }
+
+    pub fn contains_line(&self, line: usize, line_count: usize) -> bool {
+        self.new_lines.contains(&line) || self.marker_line(line_count) == line
+    }
+}
+
+/// The committed contents of `path` at HEAD, or `None` if it isn't in a
+/// repository or isn't committed. Meant to run on the thread pool.
+pub fn head_text(path: &Path) -> Option<String> {
+    let path = path.canonicalize().ok()?;
+    let repo = Repository::discover(path.parent()?).ok()?;
+    let workdir = repo.workdir()?.canonicalize().ok()?;
+    let relative = path.strip_prefix(workdir).ok()?;
+    let tree = repo.head().ok()?.peel_to_tree().ok()?;
+    let blob = tree
+        .get_path(relative)
+        .ok()?
+        .to_object(&repo)
+        .ok()?
+        .peel_to_blob()
+        .ok()?;
+    Some(String::from_utf8_lossy(blob.content()).into_owned())
+}
+
+/// Line hunks turning `base` into `text`, in buffer order.
+pub fn diff_lines(base: &str, text: &str) -> Vec<Hunk> {
+    diff_bytes(base.as_bytes(), text.as_bytes())
+}
+
+/// `diff_lines` for contents that may not be UTF-8, as files in a
+/// repository can be.
+fn diff_bytes(base: &[u8], text: &[u8]) -> Vec<Hunk> {
+    let mut options = DiffOptions::new();
+    options.context_lines(0);
+    let Ok(patch) = Patch::from_buffers(base, None, text, None, Some(&mut options)) else {
+        return Vec::new();
+    };
+    // With no lines on a side, git's start is the line before the change
+    let range = |start: u32, count: u32| {
+        let start = if count == 0 { start } else { start - 1 } as usize;
+        start..start + count as usize
+    };
+    (0..patch.num_hunks())
+        .filter_map(|ix| patch.hunk(ix).ok())
+        .map(|(hunk, _)| {
+            let old_lines = range(hunk.old_start(), hunk.old_lines());
+            let new_lines = range(hunk.new_start(), hunk.new_lines());
+            let kind = match (old_lines.is_empty(), new_lines.is_empty()) {
+                (true, _) => HunkKind::Added,
+                (_, true) => HunkKind::Deleted,
+                _ => HunkKind::Modified,
+            };
+            Hunk {
+                kind,
+                old_lines,
+                new_lines,
+            }
+        })
+        .collect()
+}
+
+/// Lines of `text` with their line endings, so they can be put back together.
+fn split_lines(text: &[u8]) -> Vec<&[u8]> {
+    text.split_inclusive(|&b| b == b'\n').collect()
+}
+
+/// The HEAD lines `hunk` replaced, for previewing.
+pub fn original_lines(base: &str, hunk: &Hunk) -> Vec<String> {
+    lines_in(base, hunk.old_lines.clone())
+}
+
+/// The lines `hunk` put in their place.
+pub fn changed_lines(text: &str, hunk: &Hunk) -> Vec<String> {
+    lines_in(text, hunk.new_lines.clone())
+}
+
+fn lines_in(text: &str, range: Range<usize>) -> Vec<String> {
+    split_lines(text.as_bytes())
+        .get(range)
+        .unwrap_or_default()
+        .iter()
+        .map(|line| {
+            String::from_utf8_lossy(line)
+                .trim_end_matches(['\n', '\r'])
+                .to_string()
+        })
+        .collect()
+}
+
+/// `into` with the lines `into_range` swapped for `from_range` of `from`.
+/// Works on bytes so content that isn't UTF-8 comes through unchanged.
+fn splice_lines(
+    into: &[u8],
+    into_range: Range<usize>,
+    from: &[u8],
+    from_range: Range<usize>,
+) -> Vec<u8> {
+    let into_lines = split_lines(into);
+    let from_lines = split_lines(from);
+    let (Some(before), Some(middle), Some(after)) = (
+        into_lines.get(..into_range.start),
+        from_lines.get(from_range.clone()),
+        into_lines.get(into_range.end..),
+    ) else {
+        return into.to_vec();
+    };
+    let mut result = Vec::with_capacity(into.len());
+    for part in [before, middle, after] {
+        for line in part {
+            // The last line of either side may lack a newline
+            if result.last().is_some_and(|&b| b != b'\n') {
+                result.push(b'\n');
+            }
+            result.extend_from_slice(line);
+        }
+    }
+    // Splicing in the end of the file also brings whether it ends in a newline
+    if into_range.end == into_lines.len() && from_range.end == from_lines.len() {
+        let ends_with_newline = from.is_empty() || from.ends_with(b"\n");
+        if ends_with_newline && result.last().is_some_and(|&b| b != b'\n') {
+            result.push(b'\n');
+        } else if !ends_with_newline && result.ends_with(b"\n") {
+            result.pop();
+        }
+    }
+    result
+}
+
+/// `text` with `hunk` put back the way it is in `base`.
+pub fn revert_hunk(base: &str, text: &str, hunk: &Hunk) -> String {
+    let bytes = splice_lines(
+        text.as_bytes(),
+        hunk.new_lines.clone(),
+        base.as_bytes(),
+        hunk.old_lines.clone(),
+    );
+    // Whole lines of two strings make a string
+    String::from_utf8(bytes).unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned())
+}
+
+/// The hunk of the change from `old` to `new` with the same lines that
+/// `hunk` has in `listed`, nearest to where it was. An error if there is
+/// none, because the file changed in a way that touched the hunk.
+fn find_hunk(listed: &FileDiff, hunk: &Hunk, old: &[u8], new: &[u8]) -> Result<Hunk, git2::Error> {
+    let removed = original_lines(&listed.old, hunk);
+    let added = changed_lines(&listed.new, hunk);
+    let (old_text, new_text) = (String::from_utf8_lossy(old), String::from_utf8_lossy(new));
+    diff_bytes(old, new)
+        .into_iter()
+        .filter(|h| original_lines(&old_text, h) == removed && changed_lines(&new_text, h) == added)
+        .min_by_key(|h| h.old_lines.start.abs_diff(hunk.old_lines.start))
+        .ok_or_else(|| {
+            git2::Error::from_str("The file changed since the diff was shown, try again")
+        })
+}
+
+/// What happened to a file, as listed in the source control panel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+    Untracked,
+    Conflicted,
+}
+
+impl ChangeKind {
+    pub fn letter(&self) -> &'static str {
+        match self {
+            ChangeKind::Added => "A",
+            ChangeKind::Modified => "M",
+            ChangeKind::Deleted => "D",
+            ChangeKind::Renamed => "R",
+            ChangeKind::Untracked => "U",
+            ChangeKind::Conflicted => "C",
+        }
+    }
+
+    pub fn color(&self) -> u32 {
+        match self {
+            ChangeKind::Added | ChangeKind::Renamed => GitStatus::Added.color(),
+            ChangeKind::Modified => GitStatus::Modified.color(),
+            ChangeKind::Deleted | ChangeKind::Conflicted => GitStatus::Conflicted.color(),
+            ChangeKind::Untracked => GitStatus::Untracked.color(),
+        }
+    }
+}
+
+/// A changed file, either in the index (staged) or only in the work tree.
+/// A file staged and then edited again shows up once on each side.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change {
+    /// Relative to the repository, with `/` separators as git spells it
+    pub path: String,
+    pub kind: ChangeKind,
+    pub staged: bool,
+    /// Where a staged rename came from
+    pub old_path: Option<String>,
+}
+
+/// Before and after texts of one file's change, with the hunks between them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FileDiff {
+    pub old: String,
+    pub new: String,
+    pub hunks: Vec<Hunk>,
+}
+
+/// The local repository a workspace folder is in, for staging and
+/// committing. Nothing here talks to remotes.
+pub struct SourceControl {
+    repo: Repository,
+    workdir: PathBuf,
+}
+
+impl SourceControl {
+    /// Opens the repository `path` is in.
+    pub fn open(path: &Path) -> Result<Self, git2::Error> {
+        let repo = Repository::discover(path)?;
+        let workdir = repo
+            .workdir()
+            .ok_or_else(|| git2::Error::from_str("Bare repositories have no files to stage"))?
+            .to_path_buf();
+        Ok(Self { repo, workdir })
+    }
+
+    pub fn workdir(&self) -> &Path {
+        &self.workdir
+    }
+
+    /// Name of the checked out branch, or a short commit id when detached.
+    pub fn branch(&self) -> Option<String> {
+        let head = self.repo.head().ok()?;
+        if head.is_branch() {
+            return head.shorthand().map(str::to_string);
+        }
+        let id = head.target()?.to_string();
+        Some(id[..7.min(id.len())].to_string())
+    }
+
+    /// Staged changes first, then the rest, each sorted by path.
+    pub fn changes(&self) -> Result<Vec<Change>, git2::Error> {
+        let mut options = StatusOptions::new();
+        options
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .renames_head_to_index(true)
+            .exclude_submodules(true);
+        let mut staged = Vec::new();
+        let mut unstaged = Vec::new();
+        // git names a renamed entry by its old path, each side wants its own
+        let new_path = |delta: Option<git2::DiffDelta>| {
+            delta.and_then(|delta| Some(delta.new_file().path()?.to_str()?.to_string()))
+        };
+        for entry in self.repo.statuses(Some(&mut options))?.iter() {
+            let Some(path) = entry.path() else {
+                continue;
+            };
+            let status = entry.status();
+            if status.is_conflicted() {
+                unstaged.push(Change {
+                    path: path.to_string(),
+                    kind: ChangeKind::Conflicted,
+                    staged: false,
+                    old_path: None,
+                });
+                continue;
+            }
+            let index_kind = if status.is_index_new() {
+                Some(ChangeKind::Added)
+            } else if status.is_index_deleted() {
+                Some(ChangeKind::Deleted)
+            } else if status.is_index_renamed() {
+                Some(ChangeKind::Renamed)
+            } else if status.intersects(Status::INDEX_MODIFIED | Status::INDEX_TYPECHANGE) {
+                Some(ChangeKind::Modified)
+            } else {
+                None
+            };
+            let worktree_kind = if status.is_wt_new() {
+                Some(ChangeKind::Untracked)
+            } else if status.is_wt_deleted() {
+                Some(ChangeKind::Deleted)
+            } else if status
+                .intersects(Status::WT_MODIFIED | Status::WT_TYPECHANGE | Status::WT_RENAMED)
+            {
+                Some(ChangeKind::Modified)
+            } else {
+                None
+            };
+            staged.extend(index_kind.map(|kind| Change {
+                path: new_path(entry.head_to_index()).unwrap_or_else(|| path.to_string()),
+                kind,
+                staged: true,
+                old_path: (kind == ChangeKind::Renamed).then(|| path.to_string()),
+            }));
+            unstaged.extend(worktree_kind.map(|kind| Change {
+                path: new_path(entry.index_to_workdir()).unwrap_or_else(|| path.to_string()),
+                kind,
+                staged: false,
+                old_path: None,
+            }));
+        }
+        staged.sort_by(|a, b| a.path.cmp(&b.path));
+        unstaged.sort_by(|a, b| a.path.cmp(&b.path));
+        staged.extend(unstaged);
+        Ok(staged)
+    }
+
+    fn head_tree(&self) -> Option<git2::Tree<'_>> {
+        self.repo.head().ok()?.peel_to_tree().ok()
+    }
+
+    fn blob_bytes(&self, id: git2::Oid) -> Result<Vec<u8>, git2::Error> {
+        Ok(self.repo.find_blob(id)?.content().to_vec())
+    }
+
+    /// Contents at HEAD, empty for files that aren't committed.
+    fn head_bytes(&self, path: &str) -> Result<Vec<u8>, git2::Error> {
+        match self
+            .head_tree()
+            .and_then(|tree| tree.get_path(Path::new(path)).ok())
+        {
+            Some(entry) => self.blob_bytes(entry.id()),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Staged contents, empty for files that aren't in the index.
+    fn index_bytes(&self, path: &str) -> Result<Vec<u8>, git2::Error> {
+        let index = self.repo.index()?;
+        match index.get_path(Path::new(path), 0) {
+            Some(entry) => self.blob_bytes(entry.id),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn worktree_bytes(&self, path: &str) -> Vec<u8> {
+        fs::read(self.workdir.join(path)).unwrap_or_default()
+    }
+
+    /// HEAD against the index for staged changes, the index against the
+    /// work tree otherwise. The texts are for display: invalid UTF-8 is
+    /// replaced, which keeps the lines the hunks refer to.
+    pub fn diff(&self, change: &Change) -> Result<FileDiff, git2::Error> {
+        let (old, new) = if change.staged {
+            (
+                self.head_bytes(&change.path)?,
+                self.index_bytes(&change.path)?,
+            )
+        } else {
+            (
+                self.index_bytes(&change.path)?,
+                self.worktree_bytes(&change.path),
+            )
+        };
+        Ok(FileDiff {
+            hunks: diff_bytes(&old, &new),
+            old: String::from_utf8_lossy(&old).into_owned(),
+            new: String::from_utf8_lossy(&new).into_owned(),
+        })
+    }
+
+    /// Stages the whole file, or its removal if it was deleted.
+    pub fn stage(&self, path: &str) -> Result<(), git2::Error> {
+        let mut index = self.repo.index()?;
+        if self.workdir.join(path).exists() {
+            index.add_path(Path::new(path))?;
+        } else {
+            index.remove_path(Path::new(path))?;
+        }
+        index.write()
+    }
+
+    /// Puts the file's index entry back to HEAD, and for a rename the
+    /// entry it was renamed from too.
+    pub fn unstage(&self, change: &Change) -> Result<(), git2::Error> {
+        let paths: Vec<&str> = std::iter::once(change.path.as_str())
+            .chain(change.old_path.as_deref())
+            .collect();
+        match self
+            .repo
+            .head()
+            .ok()
+            .and_then(|head| head.peel_to_commit().ok())
+        {
+            Some(commit) => self.repo.reset_default(Some(commit.as_object()), paths),
+            // Nothing committed yet, so staged files just leave the index
+            None => {
+                let mut index = self.repo.index()?;
+                for path in paths {
+                    index.remove_path(Path::new(path))?;
+                }
+                index.write()
+            }
+        }
+    }
+
+    /// Writes `contents` as the staged contents of `path`.
+    fn set_index_bytes(&self, path: &str, contents: &[u8]) -> Result<(), git2::Error> {
+        let mut index = self.repo.index()?;
+        let entry = match index.get_path(Path::new(path), 0) {
+            Some(entry) => entry,
+            None => git2::IndexEntry {
+                ctime: git2::IndexTime::new(0, 0),
+                mtime: git2::IndexTime::new(0, 0),
+                dev: 0,
+                ino: 0,
+                mode: 0o100644,
+                uid: 0,
+                gid: 0,
+                file_size: 0,
+                id: git2::Oid::zero(),
+                flags: 0,
+                flags_extended: 0,
+                path: path.as_bytes().to_vec(),
+            },
+        };
+        index.add_frombuffer(&entry, contents)?;
+        index.write()
+    }
+
+    /// Stages one hunk of a work tree change, as listed by `diff` in
+    /// `listed`. The file may have changed since, so the hunk is looked up
+    /// again by its lines.
+    pub fn stage_hunk(
+        &self,
+        path: &str,
+        listed: &FileDiff,
+        hunk: &Hunk,
+    ) -> Result<(), git2::Error> {
+        let staged = self.index_bytes(path)?;
+        let text = self.worktree_bytes(path);
+        let hunk = find_hunk(listed, hunk, &staged, &text)?;
+        let spliced = splice_lines(
+            &staged,
+            hunk.old_lines.clone(),
+            &text,
+            hunk.new_lines.clone(),
+        );
+        self.set_index_bytes(path, &spliced)
+    }
+
+    /// Takes one hunk of a staged change back out of the index, looked up
+    /// again like `stage_hunk` does.
+    pub fn unstage_hunk(
+        &self,
+        path: &str,
+        listed: &FileDiff,
+        hunk: &Hunk,
+    ) -> Result<(), git2::Error> {
+        let head = self.head_bytes(path)?;
+        let staged = self.index_bytes(path)?;
+        let hunk = find_hunk(listed, hunk, &head, &staged)?;
+        let spliced = splice_lines(
+            &staged,
+            hunk.new_lines.clone(),
+            &head,
+            hunk.old_lines.clone(),
+        );
+        self.set_index_bytes(path, &spliced)
+    }
+
+    /// Commits what is staged on top of HEAD, as the user git is
+    /// configured with.
+    pub fn commit(&self, message: &str) -> Result<git2::Oid, git2::Error> {
+        if message.trim().is_empty() {
+            return Err(git2::Error::from_str("A commit message is required"));
+        }
+        let mut index = self.repo.index()?;
+        let tree = self.repo.find_tree(index.write_tree()?)?;
+        let parent = self
+            .repo
+            .head()
+            .ok()
+            .and_then(|head| head.peel_to_commit().ok());
+        let unchanged = match &parent {
+            Some(parent) => parent.tree_id() == tree.id(),
+            None => tree.is_empty(),
+        };
+        if unchanged {
+            return Err(git2::Error::from_str("Nothing is staged"));
+        }
+        let signature = self.repo.signature()?;
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        self.repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message.trim(),
+            &tree,
+            &parents,
+        )
+    }
+}
@@ -0,0 +1,225 @@
+use gpui::{
+    Context, FocusHandle, InteractiveElement, IntoElement, MouseButton, ObjectFit, ParentElement,
+    Render, ScrollWheelEvent, StatefulInteractiveElement, Styled, StyledImage, Window, actions,
+    div, img, prelude::FluentBuilder, px, rgb,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::{IMAGE_MAX_ZOOM, IMAGE_MIN_ZOOM, IMAGE_ZOOM_STEP};
+use crate::utils::format_bytes;
+
+/// File extensions that open in an `ImageViewer` instead of a `TextEditor`.
+pub const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "svg"];
+
+actions!(ImageViewer, [ZoomIn, ZoomOut, ResetZoom, ToggleFitToWindow]);
+
+pub struct ImageViewer {
+    pub path: PathBuf,
+    pub focus_handle: FocusHandle,
+    dimensions: Option<(u32, u32)>,
+    file_size: Option<u64>,
+    zoom: f32,
+    fit_to_window: bool,
+}
+
+impl ImageViewer {
+    pub fn new(path: PathBuf, cx: &mut Context<Self>) -> Self {
+        let dimensions = read_dimensions(&path);
+        let file_size = fs::metadata(&path).ok().map(|m| m.len());
+
+        Self {
+            path,
+            focus_handle: cx.focus_handle(),
+            dimensions,
+            file_size,
+            zoom: 1.0,
+            fit_to_window: true,
+        }
+    }
+
+    /// Returns true if the path has an extension the viewer knows how to render.
+    pub fn is_supported(path: &Path) -> bool {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase())
+            .is_some_and(|e| IMAGE_EXTENSIONS.contains(&e.as_str()))
+    }
+
+    fn set_zoom(&mut self, zoom: f32, cx: &mut Context<Self>) {
+        self.zoom = zoom.clamp(IMAGE_MIN_ZOOM, IMAGE_MAX_ZOOM);
+        self.fit_to_window = false;
+        cx.notify();
+    }
+
+    pub fn zoom_in(&mut self, _: &ZoomIn, _window: &mut Window, cx: &mut Context<Self>) {
+        self.set_zoom(self.zoom * IMAGE_ZOOM_STEP, cx);
+    }
+
+    pub fn zoom_out(&mut self, _: &ZoomOut, _window: &mut Window, cx: &mut Context<Self>) {
+        self.set_zoom(self.zoom / IMAGE_ZOOM_STEP, cx);
+    }
+
+    pub fn reset_zoom(&mut self, _: &ResetZoom, _window: &mut Window, cx: &mut Context<Self>) {
+        self.set_zoom(1.0, cx);
+    }
+
+    pub fn toggle_fit_to_window(
+        &mut self,
+        _: &ToggleFitToWindow,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.fit_to_window = !self.fit_to_window;
+        cx.notify();
+    }
+
+    fn handle_scroll_wheel(
+        &mut self,
+        event: &ScrollWheelEvent,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        // Ctrl + wheel zooms, plain wheel is left to the scroll container
+        if !event.modifiers.control {
+            return;
+        }
+        let delta = event.delta.pixel_delta(px(20.0));
+        if f32::from(delta.y) > 0.0 {
+            self.set_zoom(self.zoom * IMAGE_ZOOM_STEP, cx);
+        } else if f32::from(delta.y) < 0.0 {
+            self.set_zoom(self.zoom / IMAGE_ZOOM_STEP, cx);
+        }
+        cx.stop_propagation();
+    }
+
+    fn info_text(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some((w, h)) = self.dimensions {
+            parts.push(format!("{} × {} px", w, h));
+        }
+        if let Some(size) = self.file_size {
+            parts.push(format_bytes(size));
+        }
+        if self.fit_to_window {
+            parts.push("Fit".to_string());
+        } else {
+            parts.push(format!("{:.0}%", self.zoom * 100.0));
+        }
+        parts.join("  ·  ")
+    }
+}
+
+/// Reads pixel dimensions without decoding the whole image.
+/// Raster formats only need their header; SVGs are parsed for their intrinsic size.
+fn read_dimensions(path: &Path) -> Option<(u32, u32)> {
+    let is_svg = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("svg"));
+
+    if is_svg {
+        let data = fs::read(path).ok()?;
+        let tree = usvg::Tree::from_data(&data, &usvg::Options::default()).ok()?;
+        let size = tree.size();
+        Some((size.width().round() as u32, size.height().round() as u32))
+    } else {
+        let size = imagesize::size(path).ok()?;
+        Some((size.width as u32, size.height as u32))
+    }
+}
+
+fn toolbar_button(label: &'static str) -> gpui::Div {
+    div()
+        .px_2()
+        .rounded_md()
+        .cursor_pointer()
+        .hover(|s| s.bg(rgb(0x3a3a3a)).text_color(rgb(0xffffff)))
+        .child(label)
+}
+
+impl Render for ImageViewer {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let image = img(self.path.clone()).with_fallback(|| {
+            div()
+                .text_sm()
+                .text_color(rgb(0x888888))
+                .child("Unable to display image")
+                .into_any_element()
+        });
+
+        let image = match (self.fit_to_window, self.dimensions) {
+            (false, Some((w, h))) => image
+                .flex_none()
+                .w(px(w as f32 * self.zoom))
+                .h(px(h as f32 * self.zoom)),
+            _ => image.size_full().object_fit(ObjectFit::ScaleDown),
+        };
+
+        div()
+            .track_focus(&self.focus_handle)
+            .key_context("ImageViewer")
+            .on_action(cx.listener(ImageViewer::zoom_in))
+            .on_action(cx.listener(ImageViewer::zoom_out))
+            .on_action(cx.listener(ImageViewer::reset_zoom))
+            .on_action(cx.listener(ImageViewer::toggle_fit_to_window))
+            .on_mouse_down(
+                MouseButton::Left,
+                cx.listener(|this, _, window, _| window.focus(&this.focus_handle)),
+            )
+            .size_full()
+            .flex()
+            .flex_col()
+            .bg(rgb(0x1e1e1e))
+            .child(
+                // Toolbar
+                div()
+                    .h_8()
+                    .px_2()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap_1()
+                    .border_b_1()
+                    .border_color(rgb(0x404040))
+                    .text_xs()
+                    .text_color(rgb(0xcccccc))
+                    .child(toolbar_button("−").on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(|this, _, window, cx| this.zoom_out(&ZoomOut, window, cx)),
+                    ))
+                    .child(toolbar_button("+").on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(|this, _, window, cx| this.zoom_in(&ZoomIn, window, cx)),
+                    ))
+                    .child(toolbar_button("1:1").on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(|this, _, window, cx| this.reset_zoom(&ResetZoom, window, cx)),
+                    ))
+                    .child(
+                        toolbar_button("Fit")
+                            .when(self.fit_to_window, |this| this.bg(rgb(0x04395e)))
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(|this, _, window, cx| {
+                                    this.toggle_fit_to_window(&ToggleFitToWindow, window, cx)
+                                }),
+                            ),
+                    )
+                    .child(div().flex_1())
+                    .child(self.info_text()),
+            )
+            .child(
+                div()
+                    .id("image-viewer-canvas")
+                    .flex_1()
+                    .flex()
+                    .items_center()
+                    .justify_center()
+                    .overflow_scroll()
+                    .on_scroll_wheel(cx.listener(ImageViewer::handle_scroll_wheel))
+                    .p_4()
+                    .child(image),
+            )
+    }
+}
@@ -0,0 +1,244 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use rayon::prelude::*;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use crate::config::SEARCH_INDEX_DIR_NAME;
+use crate::editor::search::{SearchQuery, SearchScope, project_files, searchable_text};
+use crate::persist::write_atomic;
+use crate::pools::Pools;
+use crate::structs::trigram::{FileStamp, TrigramIndex};
+use crate::utils::data_dir;
+
+/// Snapshot of indexing progress for the status bar.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IndexProgress {
+    pub indexed: usize,
+    pub total: usize,
+    pub building: bool,
+}
+
+/// On-disk trigram index of one workspace root. Built on the thread pool,
+/// kept current from file-change events, and used by project search to skip
+/// files that can't contain the query.
+pub struct SearchIndex {
+    root: PathBuf,
+    file: PathBuf,
+    index: RwLock<TrigramIndex>,
+    /// Root `.gitignore`/`.ignore` rules, for filtering change events
+    ignore: Gitignore,
+    indexed: AtomicUsize,
+    total: AtomicUsize,
+    building: AtomicBool,
+    /// Changed since last written to disk
+    dirty: AtomicBool,
+}
+
+impl SearchIndex {
+    /// Opens the index for `root`, starting from what was saved last session.
+    /// Call `refresh` to bring it up to date.
+    pub fn load(root: PathBuf) -> Self {
+        let mut hasher = DefaultHasher::new();
+        root.hash(&mut hasher);
+        let file = data_dir()
+            .join(SEARCH_INDEX_DIR_NAME)
+            .join(format!("{:016x}.idx", hasher.finish()));
+        Self::with_file(root, file)
+    }
+
+    pub fn with_file(root: PathBuf, file: PathBuf) -> Self {
+        let index = TrigramIndex::load(&file).unwrap_or_default();
+
+        let mut ignore = GitignoreBuilder::new(&root);
+        ignore.add(root.join(".gitignore"));
+        ignore.add(root.join(".ignore"));
+
+        Self {
+            indexed: AtomicUsize::new(0),
+            total: AtomicUsize::new(index.len()),
+            index: RwLock::new(index),
+            ignore: ignore.build().unwrap_or_else(|_| Gitignore::empty()),
+            building: AtomicBool::new(false),
+            dirty: AtomicBool::new(false),
+            root,
+            file,
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn progress(&self) -> IndexProgress {
+        let building = self.building.load(Ordering::Relaxed);
+        let indexed = if building {
+            self.indexed.load(Ordering::Relaxed)
+        } else {
+            self.index.read().unwrap().len()
+        };
+        IndexProgress {
+            indexed,
+            total: self.total.load(Ordering::Relaxed),
+            building,
+        }
+    }
+
+    /// Re-indexes files that are new or changed since they were last seen
+    /// and drops ones that are gone. Blocking; run it on the thread pool.
+    pub fn refresh(&self, pools: &Pools) {
+        self.building.store(true, Ordering::Relaxed);
+        let files = project_files(&self.root, &SearchScope::default()).unwrap_or_default();
+        self.total.store(files.len(), Ordering::Relaxed);
+        self.indexed.store(0, Ordering::Relaxed);
+
+        let removed: Vec<PathBuf> = {
+            let index = self.index.read().unwrap();
+            let current: std::collections::HashSet<&PathBuf> = files.iter().collect();
+            index
+                .paths()
+                .filter(|p| !current.contains(p))
+                .cloned()
+                .collect()
+        };
+        if !removed.is_empty() {
+            let mut index = self.index.write().unwrap();
+            for path in &removed {
+                index.remove(path);
+            }
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+
+        pools.threads.install(|| {
+            files.par_iter().for_each(|path| {
+                self.index_file(path);
+                self.indexed.fetch_add(1, Ordering::Relaxed);
+            })
+        });
+
+        self.building.store(false, Ordering::Relaxed);
+        self.save();
+    }
+
+    /// Applies file-change events. Paths outside the root or ignored are skipped.
+    pub fn update(&self, paths: &[PathBuf]) {
+        for path in paths {
+            let Some(path) = self.index_path(path) else {
+                continue;
+            };
+            let path = path.as_path();
+            let Ok(relative) = path.strip_prefix(&self.root) else {
+                continue;
+            };
+            let hidden = relative
+                .components()
+                .any(|c| c.as_os_str().to_string_lossy().starts_with('.'));
+            if hidden
+                || self
+                    .ignore
+                    .matched_path_or_any_parents(path, path.is_dir())
+                    .is_ignore()
+            {
+                continue;
+            }
+
+            if path.is_file() {
+                self.index_file(path);
+            } else if !path.exists() {
+                self.remove_under(path);
+            }
+        }
+    }
+
+    /// `path` as the index spells it, under `root` the way `project_files`
+    /// lists it, or `None` if it is outside the root. Some platforms report
+    /// absolute, symlink-resolved paths for events even when the root is
+    /// relative.
+    fn index_path(&self, path: &Path) -> Option<PathBuf> {
+        if path.starts_with(&self.root) {
+            return Some(path.to_path_buf());
+        }
+        if !path.is_absolute() {
+            return None;
+        }
+        [std::path::absolute(&self.root).ok(), self.root.canonicalize().ok()]
+            .into_iter()
+            .flatten()
+            .find_map(|root| Some(self.root.join(path.strip_prefix(root).ok()?)))
+    }
+
+    /// Indexes one file if its stamp changed. Binary and unreadable files are dropped.
+    fn index_file(&self, path: &Path) {
+        let stamp = FileStamp::of(path);
+        if stamp.is_some() && self.index.read().unwrap().stamp(path) == stamp {
+            return;
+        }
+
+        // Straight from disk, the stamp has to match what was indexed
+        let text = stamp.and_then(|_| searchable_text(fs::read(path).ok()?));
+        let mut index = self.index.write().unwrap();
+        match (stamp, text) {
+            (Some(stamp), Some(text)) => index.insert(path.to_path_buf(), stamp, &text),
+            _ => index.remove(path),
+        }
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// Drops a deleted file, or everything under a deleted directory.
+    fn remove_under(&self, path: &Path) {
+        let mut index = self.index.write().unwrap();
+        let removed: Vec<PathBuf> = index
+            .paths()
+            .filter(|p| p.starts_with(path))
+            .cloned()
+            .collect();
+        for p in &removed {
+            index.remove(p);
+        }
+        if !removed.is_empty() {
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Drops files the index knows can't match `query`. Files it hasn't
+    /// indexed, or that changed since, are kept, as is everything while a
+    /// refresh is running or when the query has no usable literal.
+    pub fn narrow(&self, files: Vec<PathBuf>, query: &SearchQuery) -> Vec<PathBuf> {
+        if self.building.load(Ordering::Relaxed) {
+            return files;
+        }
+        let Some(literal) = query.literal() else {
+            return files;
+        };
+
+        let index = self.index.read().unwrap();
+        let Some(candidates) = index.candidates(literal) else {
+            return files;
+        };
+        files
+            .into_iter()
+            .filter(|path| {
+                candidates.contains(path)
+                    || index
+                        .stamp(path)
+                        .is_none_or(|stamp| FileStamp::of(path) != Some(stamp))
+            })
+            .collect()
+    }
+
+    /// Writes the index to disk if it changed since the last save.
+    pub fn save(&self) {
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return;
+        }
+        // Snapshot first so searches and updates aren't held up by the write
+        let data = self.index.read().unwrap().encode();
+        if let Err(e) = write_atomic(&self.file, &data) {
+            eprintln!("Failed to save search index: {}", e);
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+    }
+}
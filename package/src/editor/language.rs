@@ -0,0 +1,63 @@
+use std::path::Path;
+
+/// Language of a buffer, detected from its file name.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Language {
+    Rust,
+    TypeScript,
+    JavaScript,
+    Svelte,
+    Python,
+    Json,
+    Toml,
+    Yaml,
+    Markdown,
+    Html,
+    Css,
+    Shell,
+    #[default]
+    PlainText,
+}
+
+impl Language {
+    pub fn from_path(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("rs") => Language::Rust,
+            Some("ts" | "tsx" | "mts" | "cts") => Language::TypeScript,
+            Some("js" | "jsx" | "mjs" | "cjs") => Language::JavaScript,
+            Some("svelte") => Language::Svelte,
+            Some("py") => Language::Python,
+            Some("json") => Language::Json,
+            Some("toml") => Language::Toml,
+            Some("yml" | "yaml") => Language::Yaml,
+            Some("md" | "markdown") => Language::Markdown,
+            Some("html" | "htm") => Language::Html,
+            Some("css") => Language::Css,
+            Some("sh" | "bash" | "zsh") => Language::Shell,
+            _ => Language::PlainText,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::Rust => "Rust",
+            Language::TypeScript => "TypeScript",
+            Language::JavaScript => "JavaScript",
+            Language::Svelte => "Svelte",
+            Language::Python => "Python",
+            Language::Json => "JSON",
+            Language::Toml => "TOML",
+            Language::Yaml => "YAML",
+            Language::Markdown => "Markdown",
+            Language::Html => "HTML",
+            Language::Css => "CSS",
+            Language::Shell => "Shell",
+            Language::PlainText => "Plain Text",
+        }
+    }
+}
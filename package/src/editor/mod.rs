@@ -1,3 +1,11 @@
 pub mod filebrowser;
+pub mod fileops;
+pub mod filetree;
+pub mod git;
+pub mod imageviewer;
+pub mod index;
+pub mod language;
+pub mod outline;
 pub mod texteditor;
+pub mod replace;
 pub mod search;
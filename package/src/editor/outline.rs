@@ -0,0 +1,578 @@
+use proc_macro2::Span;
+use regex::Regex;
+use std::sync::LazyLock;
+use syn::spanned::Spanned;
+use syn::{ForeignItem, ImplItem, Item, Stmt, TraitItem};
+
+use crate::editor::language::Language;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SymbolKind {
+    Function,
+    Struct,
+    Enum,
+    Trait,
+    Impl,
+    Module,
+    Class,
+    Interface,
+    Type,
+    Constant,
+    Macro,
+    Table,
+    Heading,
+}
+
+impl SymbolKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SymbolKind::Function => "fn",
+            SymbolKind::Struct => "struct",
+            SymbolKind::Enum => "enum",
+            SymbolKind::Trait => "trait",
+            SymbolKind::Impl => "impl",
+            SymbolKind::Module => "mod",
+            SymbolKind::Class => "class",
+            SymbolKind::Interface => "interface",
+            SymbolKind::Type => "type",
+            SymbolKind::Constant => "const",
+            SymbolKind::Macro => "macro",
+            SymbolKind::Table => "table",
+            SymbolKind::Heading => "#",
+        }
+    }
+}
+
+/// A named item in a buffer's outline.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// 1-based line of the declaration
+    pub line: usize,
+    /// 1-based char column of the name
+    pub column: usize,
+    /// Nesting level, from indentation or heading level
+    pub depth: usize,
+}
+
+/// A declaration pattern. The `name` group is the symbol's name.
+struct Rule {
+    kind: SymbolKind,
+    regex: Regex,
+}
+
+fn rules(rules: &[(SymbolKind, &str)]) -> Vec<Rule> {
+    rules
+        .iter()
+        .map(|(kind, pattern)| Rule {
+            kind: *kind,
+            regex: Regex::new(pattern).unwrap(),
+        })
+        .collect()
+}
+
+const RUST_VISIBILITY: &str = r"(?:pub(?:\([^)]*\))?\s+)?";
+
+static RUST_RULES: LazyLock<Vec<Rule>> = LazyLock::new(|| {
+    let v = RUST_VISIBILITY;
+    rules(&[
+        (
+            SymbolKind::Function,
+            &format!(
+                r#"^\s*{v}(?:default\s+)?(?:const\s+)?(?:async\s+)?(?:unsafe\s+)?(?:extern\s+"[^"]*"\s+)?fn\s+(?P<name>\w+)"#
+            ),
+        ),
+        (
+            SymbolKind::Struct,
+            &format!(r"^\s*{v}(?:struct|union)\s+(?P<name>\w+)"),
+        ),
+        (SymbolKind::Enum, &format!(r"^\s*{v}enum\s+(?P<name>\w+)")),
+        (
+            SymbolKind::Trait,
+            &format!(r"^\s*{v}(?:unsafe\s+)?trait\s+(?P<name>\w+)"),
+        ),
+        (SymbolKind::Module, &format!(r"^\s*{v}mod\s+(?P<name>\w+)")),
+        (SymbolKind::Type, &format!(r"^\s*{v}type\s+(?P<name>\w+)")),
+        (
+            SymbolKind::Constant,
+            &format!(r"^\s*{v}(?:const|static)\s+(?:mut\s+)?(?P<name>[A-Z_][A-Z0-9_]*)\s*:"),
+        ),
+        (SymbolKind::Macro, r"^\s*macro_rules!\s*(?P<name>\w+)"),
+        (
+            SymbolKind::Impl,
+            r"^\s*(?:unsafe\s+)?impl(?:<[^{]*?>)?\s+(?P<name>[^{]+?)\s*(?:where\b.*)?\{?\s*$",
+        ),
+    ])
+});
+
+static PYTHON_RULES: LazyLock<Vec<Rule>> = LazyLock::new(|| {
+    rules(&[
+        (
+            SymbolKind::Function,
+            r"^\s*(?:async\s+)?def\s+(?P<name>\w+)",
+        ),
+        (SymbolKind::Class, r"^\s*class\s+(?P<name>\w+)"),
+    ])
+});
+
+static SCRIPT_RULES: LazyLock<Vec<Rule>> = LazyLock::new(|| {
+    let export = r"(?:export\s+(?:default\s+)?)?";
+    rules(&[
+        (
+            SymbolKind::Function,
+            &format!(r"^\s*{export}(?:async\s+)?function\s*\*?\s*(?P<name>[\w$]+)"),
+        ),
+        (
+            SymbolKind::Class,
+            &format!(r"^\s*{export}(?:abstract\s+)?class\s+(?P<name>[\w$]+)"),
+        ),
+        (
+            SymbolKind::Interface,
+            &format!(r"^\s*{export}interface\s+(?P<name>[\w$]+)"),
+        ),
+        (
+            SymbolKind::Type,
+            &format!(r"^\s*{export}type\s+(?P<name>[\w$]+)\s*(?:<[^=]*>)?\s*="),
+        ),
+        (
+            SymbolKind::Enum,
+            &format!(r"^\s*{export}(?:const\s+)?enum\s+(?P<name>[\w$]+)"),
+        ),
+        (
+            SymbolKind::Function,
+            &format!(
+                r"^\s*{export}(?:const|let|var)\s+(?P<name>[\w$]+)\s*(?::[^=]+)?=\s*(?:async\s+)?(?:function\b|\([^)]*\)\s*(?::[^=]+)?=>|[\w$]+\s*=>)"
+            ),
+        ),
+        (
+            SymbolKind::Function,
+            r"^\s+(?:(?:public|private|protected|static|async|readonly|override)\s+)*(?P<name>[\w$]+)\s*\([^)]*\)\s*(?::[^{]+)?\{\s*$",
+        ),
+    ])
+});
+
+static SHELL_RULES: LazyLock<Vec<Rule>> = LazyLock::new(|| {
+    rules(&[(
+        SymbolKind::Function,
+        r"^\s*(?:function\s+(?P<name>[\w-]+)|(?P<name2>[\w-]+)\s*\(\s*\))",
+    )])
+});
+
+static TOML_RULES: LazyLock<Vec<Rule>> =
+    LazyLock::new(|| rules(&[(SymbolKind::Table, r"^\s*\[\[?\s*(?P<name>[^\]]+?)\s*\]\]?")]));
+
+static MARKDOWN_HEADING: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?P<level>#{1,6})\s+(?P<name>.+?)\s*#*\s*$").unwrap());
+
+/// Keywords the method pattern would otherwise take for names (`if (x) {`).
+const NOT_NAMES: &[&str] = &[
+    "if", "for", "while", "switch", "catch", "return", "function", "else", "with",
+];
+
+/// A string literal delimiter.
+struct Quote {
+    delim: &'static str,
+    /// Backslash escapes the next char
+    escapes: bool,
+    /// Can span lines; otherwise an unterminated string ends at the newline
+    multiline: bool,
+}
+
+const fn quote(delim: &'static str, escapes: bool, multiline: bool) -> Quote {
+    Quote {
+        delim,
+        escapes,
+        multiline,
+    }
+}
+
+/// Where comments and strings are, so declarations inside them are ignored.
+struct Syntax {
+    line_comment: &'static str,
+    block_comment: Option<(&'static str, &'static str)>,
+    /// Longest first, so `"""` wins over `"`
+    quotes: &'static [Quote],
+    /// The line comment only starts at the start of a word (`$#` in shell)
+    comment_at_word_start: bool,
+    /// Rust raw strings (`r#"..."#`) and char literals, told apart from
+    /// lifetimes
+    rust_literals: bool,
+}
+
+const RUST_SYNTAX: Syntax = Syntax {
+    line_comment: "//",
+    block_comment: Some(("/*", "*/")),
+    quotes: &[quote("\"", true, true)],
+    comment_at_word_start: false,
+    rust_literals: true,
+};
+
+const PYTHON_SYNTAX: Syntax = Syntax {
+    line_comment: "#",
+    block_comment: None,
+    quotes: &[
+        quote("\"\"\"", true, true),
+        quote("'''", true, true),
+        quote("\"", true, false),
+        quote("'", true, false),
+    ],
+    comment_at_word_start: false,
+    rust_literals: false,
+};
+
+const SCRIPT_SYNTAX: Syntax = Syntax {
+    line_comment: "//",
+    block_comment: Some(("/*", "*/")),
+    quotes: &[
+        quote("`", true, true),
+        quote("\"", true, false),
+        quote("'", true, false),
+    ],
+    comment_at_word_start: false,
+    rust_literals: false,
+};
+
+const SHELL_SYNTAX: Syntax = Syntax {
+    line_comment: "#",
+    block_comment: None,
+    quotes: &[quote("\"", true, true), quote("'", false, true)],
+    comment_at_word_start: true,
+    rust_literals: false,
+};
+
+/// Table headers sit at the start of a line, so only multi-line strings can
+/// hide one.
+const TOML_SYNTAX: Syntax = Syntax {
+    line_comment: "#",
+    block_comment: None,
+    quotes: &[quote("\"\"\"", true, true), quote("'''", false, true)],
+    comment_at_word_start: false,
+    rust_literals: false,
+};
+
+/// How an outline was found.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutlineSource {
+    /// From a syntax tree, so it has every item
+    Parser,
+    /// Lines matched against declaration patterns. Declarations split or
+    /// written in ways the patterns don't expect are missed.
+    Patterns,
+}
+
+/// The functions, types, headings etc. declared in `text`, in order.
+pub fn outline(text: &str, language: Language) -> Vec<Symbol> {
+    outline_with_source(text, language).0
+}
+
+/// `outline`, and how it was found. Rust is read from its syntax tree. Other
+/// languages, and Rust that doesn't parse yet, are matched line by line
+/// against declaration patterns after blanking out comments and strings, so
+/// half-written code still gets an outline.
+pub fn outline_with_source(text: &str, language: Language) -> (Vec<Symbol>, OutlineSource) {
+    let (rules, syntax): (&[Rule], &Syntax) = match language {
+        Language::Rust => match rust_outline(text) {
+            Some(symbols) => return (symbols, OutlineSource::Parser),
+            None => (&RUST_RULES, &RUST_SYNTAX),
+        },
+        Language::Python => (&PYTHON_RULES, &PYTHON_SYNTAX),
+        Language::TypeScript | Language::JavaScript | Language::Svelte => {
+            (&SCRIPT_RULES, &SCRIPT_SYNTAX)
+        }
+        Language::Shell => (&SHELL_RULES, &SHELL_SYNTAX),
+        Language::Toml => (&TOML_RULES, &TOML_SYNTAX),
+        Language::Markdown => return (markdown_outline(text), OutlineSource::Patterns),
+        _ => return (Vec::new(), OutlineSource::Patterns),
+    };
+
+    let code = blank_comments_and_strings(text, syntax);
+    let mut symbols = Vec::new();
+    for (i, (line, code)) in text.lines().zip(code.lines()).enumerate() {
+        let trimmed = code.trim_start();
+        // Attributes in Rust
+        if trimmed.starts_with('#') {
+            continue;
+        }
+
+        let found = rules.iter().find_map(|rule| {
+            let captures = rule.regex.captures(code)?;
+            let name = captures.name("name").or_else(|| captures.name("name2"))?;
+            (!NOT_NAMES.contains(&name.as_str())).then_some((rule.kind, name.range()))
+        });
+        if let Some((kind, name)) = found {
+            let indent = &code[..code.len() - trimmed.len()];
+            symbols.push(Symbol {
+                name: line[name.clone()].to_string(),
+                kind,
+                line: i + 1,
+                column: line[..name.start].chars().count() + 1,
+                depth: indent.replace('\t', "    ").len() / 4,
+            });
+        }
+    }
+    (symbols, OutlineSource::Patterns)
+}
+
+/// `text` with every comment and string literal replaced by spaces. Newlines
+/// and byte offsets are kept, so positions in the result are positions in
+/// `text`.
+fn blank_comments_and_strings(text: &str, syntax: &Syntax) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut word_start = true;
+    let mut in_word = false;
+    while let Some(c) = rest.chars().next() {
+        let len = if rest.starts_with(syntax.line_comment)
+            && (word_start || !syntax.comment_at_word_start)
+        {
+            rest.find('\n').unwrap_or(rest.len())
+        } else if let Some((open, close)) = syntax
+            .block_comment
+            .filter(|(open, _)| rest.starts_with(open))
+        {
+            rest[open.len()..]
+                .find(close)
+                .map_or(rest.len(), |i| open.len() + i + close.len())
+        } else if let Some(quote) = syntax.quotes.iter().find(|q| rest.starts_with(q.delim)) {
+            string_len(rest, quote)
+        } else if syntax.rust_literals && !in_word {
+            rust_literal_len(rest)
+        } else {
+            0
+        };
+
+        if len == 0 {
+            out.push(c);
+            word_start = c.is_whitespace() || c == ';';
+            in_word = c.is_alphanumeric() || c == '_';
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        for c in rest[..len].chars() {
+            match c {
+                '\n' | '\r' => out.push(c),
+                _ => out.extend(std::iter::repeat_n(' ', c.len_utf8())),
+            }
+        }
+        word_start = false;
+        in_word = false;
+        rest = &rest[len..];
+    }
+    out
+}
+
+/// Length in bytes of the string literal `text` starts with.
+fn string_len(text: &str, quote: &Quote) -> usize {
+    let mut chars = text.char_indices().skip(quote.delim.chars().count());
+    while let Some((i, c)) = chars.next() {
+        if quote.escapes && c == '\\' {
+            chars.next();
+        } else if c == '\n' && !quote.multiline {
+            return i;
+        } else if text[i..].starts_with(quote.delim) {
+            return i + quote.delim.len();
+        }
+    }
+    text.len()
+}
+
+/// Length in bytes of the raw string (`r"..."`, `br#"..."#`) or char
+/// literal (`'"'`, `'\''`) `text` starts with, or 0 if it starts with
+/// neither, as for a lifetime (`'a`).
+fn rust_literal_len(text: &str) -> usize {
+    if let Some(after) = text.strip_prefix('\'') {
+        let mut chars = after.char_indices();
+        let end = match chars.next() {
+            // `'\n'`, `'\''`, `'\u{1F600}'`
+            Some((_, '\\')) => {
+                chars.next();
+                chars.find(|(_, c)| *c == '\'' || *c == '\n')
+            }
+            Some((_, c)) if c != '\n' => chars.next(),
+            _ => None,
+        };
+        return match end {
+            Some((i, '\'')) => 1 + i + 1,
+            _ => 0,
+        };
+    }
+
+    let raw = text
+        .strip_prefix('r')
+        .or_else(|| text.strip_prefix("br"))
+        .or_else(|| text.strip_prefix("cr"));
+    let Some(raw) = raw else {
+        return 0;
+    };
+    let hashes = raw.len() - raw.trim_start_matches('#').len();
+    // `r#ident` is a raw identifier
+    if !raw[hashes..].starts_with('"') {
+        return 0;
+    }
+    let close = format!("\"{}", "#".repeat(hashes));
+    let start = text.len() - raw.len() + hashes + 1;
+    text[start..]
+        .find(&close)
+        .map_or(text.len(), |i| start + i + close.len())
+}
+
+/// The items of a Rust buffer, or `None` if it doesn't parse.
+fn rust_outline(text: &str) -> Option<Vec<Symbol>> {
+    let file = syn::parse_file(text).ok();
+    let mut symbols = Vec::new();
+    if let Some(file) = &file {
+        for item in &file.items {
+            rust_item(text, item, 0, &mut symbols);
+        }
+    }
+    // The parser keeps the text of everything it has parsed on this thread
+    // to resolve spans; the symbols are collected, so let it go.
+    proc_macro2::extra::invalidate_current_thread_spans();
+    file.map(|_| symbols)
+}
+
+fn rust_symbol(name: String, kind: SymbolKind, span: Span, depth: usize) -> Symbol {
+    let start = span.start();
+    Symbol {
+        name,
+        kind,
+        line: start.line,
+        column: start.column + 1,
+        depth,
+    }
+}
+
+fn rust_named(ident: &syn::Ident, kind: SymbolKind, depth: usize, symbols: &mut Vec<Symbol>) {
+    let name = ident.to_string();
+    // `const _: () = ...;`
+    if name != "_" {
+        symbols.push(rust_symbol(name, kind, ident.span(), depth));
+    }
+}
+
+/// Functions nested in a function body.
+fn rust_block(text: &str, block: &syn::Block, depth: usize, symbols: &mut Vec<Symbol>) {
+    for stmt in &block.stmts {
+        if let Stmt::Item(item) = stmt {
+            rust_item(text, item, depth, symbols);
+        }
+    }
+}
+
+fn rust_item(text: &str, item: &Item, depth: usize, symbols: &mut Vec<Symbol>) {
+    match item {
+        Item::Fn(f) => {
+            rust_named(&f.sig.ident, SymbolKind::Function, depth, symbols);
+            rust_block(text, &f.block, depth + 1, symbols);
+        }
+        Item::Struct(s) => rust_named(&s.ident, SymbolKind::Struct, depth, symbols),
+        Item::Union(u) => rust_named(&u.ident, SymbolKind::Struct, depth, symbols),
+        Item::Enum(e) => rust_named(&e.ident, SymbolKind::Enum, depth, symbols),
+        Item::Type(t) => rust_named(&t.ident, SymbolKind::Type, depth, symbols),
+        Item::Const(c) => rust_named(&c.ident, SymbolKind::Constant, depth, symbols),
+        Item::Static(s) => rust_named(&s.ident, SymbolKind::Constant, depth, symbols),
+        Item::TraitAlias(t) => rust_named(&t.ident, SymbolKind::Trait, depth, symbols),
+        Item::Macro(m) => {
+            if let Some(ident) = &m.ident {
+                rust_named(ident, SymbolKind::Macro, depth, symbols);
+            }
+        }
+        Item::Mod(m) => {
+            rust_named(&m.ident, SymbolKind::Module, depth, symbols);
+            for item in m.content.iter().flat_map(|(_, items)| items) {
+                rust_item(text, item, depth + 1, symbols);
+            }
+        }
+        Item::Trait(t) => {
+            rust_named(&t.ident, SymbolKind::Trait, depth, symbols);
+            for item in &t.items {
+                match item {
+                    TraitItem::Fn(f) => {
+                        rust_named(&f.sig.ident, SymbolKind::Function, depth + 1, symbols);
+                        if let Some(block) = &f.default {
+                            rust_block(text, block, depth + 2, symbols);
+                        }
+                    }
+                    TraitItem::Const(c) => {
+                        rust_named(&c.ident, SymbolKind::Constant, depth + 1, symbols)
+                    }
+                    TraitItem::Type(t) => {
+                        rust_named(&t.ident, SymbolKind::Type, depth + 1, symbols)
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Item::Impl(i) => {
+            // Named as written, from the trait (if any) through the type
+            let first = match &i.trait_ {
+                Some((_, path, _)) => path.span(),
+                None => i.self_ty.span(),
+            };
+            let range = first.byte_range().start..i.self_ty.span().byte_range().end;
+            let name = text
+                .get(range)
+                .unwrap_or_default()
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ");
+            symbols.push(rust_symbol(name, SymbolKind::Impl, first, depth));
+            for item in &i.items {
+                match item {
+                    ImplItem::Fn(f) => {
+                        rust_named(&f.sig.ident, SymbolKind::Function, depth + 1, symbols);
+                        rust_block(text, &f.block, depth + 2, symbols);
+                    }
+                    ImplItem::Const(c) => {
+                        rust_named(&c.ident, SymbolKind::Constant, depth + 1, symbols)
+                    }
+                    ImplItem::Type(t) => rust_named(&t.ident, SymbolKind::Type, depth + 1, symbols),
+                    _ => {}
+                }
+            }
+        }
+        Item::ForeignMod(f) => {
+            for item in &f.items {
+                match item {
+                    ForeignItem::Fn(f) => {
+                        rust_named(&f.sig.ident, SymbolKind::Function, depth, symbols)
+                    }
+                    ForeignItem::Static(s) => {
+                        rust_named(&s.ident, SymbolKind::Constant, depth, symbols)
+                    }
+                    ForeignItem::Type(t) => rust_named(&t.ident, SymbolKind::Type, depth, symbols),
+                    _ => {}
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// ATX headings, skipping anything inside fenced code blocks.
+fn markdown_outline(text: &str) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    let mut in_fence = false;
+    for (i, line) in text.lines().enumerate() {
+        if line.trim_start().starts_with("```") || line.trim_start().starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        if let Some(captures) = MARKDOWN_HEADING.captures(line) {
+            let level = captures["level"].len();
+            let name = captures.name("name").unwrap();
+            symbols.push(Symbol {
+                name: name.as_str().to_string(),
+                kind: SymbolKind::Heading,
+                line: i + 1,
+                column: line[..name.start()].chars().count() + 1,
+                depth: level - 1,
+            });
+        }
+    }
+    symbols
+}
@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use rayon::prelude::*;
+
+use crate::editor::search::{SearchQuery, SearchScope, project_files, read_searchable};
+use crate::pools::Pools;
+use crate::roots::WorkspaceRoots;
+use crate::utils::canonical_path;
+
+/// One occurrence a project replace would change.
+#[derive(Clone, Debug)]
+pub struct ReplaceMatch {
+    /// Byte range in the file's text
+    pub range: Range<usize>,
+    /// 1-based line the match starts on
+    pub line: usize,
+    pub line_content: String,
+    /// Byte range of the match within `line_content`, cut off at the end of the line
+    pub line_range: Range<usize>,
+    pub replacement: String,
+    pub included: bool,
+}
+
+/// All matches in one file, together with the text they were found in.
+#[derive(Clone, Debug)]
+pub struct FileReplace {
+    pub path: PathBuf,
+    pub matches: Vec<ReplaceMatch>,
+    /// Text the matches were computed against
+    original: String,
+}
+
+impl FileReplace {
+    pub fn new(path: PathBuf, text: String, query: &SearchQuery, replacement: &str) -> Self {
+        let mut matches = Vec::new();
+        let mut pos = 0;
+        while let Some(range) = query.find_at(&text, pos) {
+            pos = range.end;
+
+            let line_start = text[..range.start].rfind('\n').map_or(0, |i| i + 1);
+            let line_end = text[range.start..]
+                .find('\n')
+                .map_or(text.len(), |i| range.start + i);
+            matches.push(ReplaceMatch {
+                line: text[..range.start].matches('\n').count() + 1,
+                line_content: text[line_start..line_end].to_string(),
+                line_range: range.start - line_start..range.end.min(line_end) - line_start,
+                replacement: query.replacement_for(&text, range.clone(), replacement),
+                range,
+                included: true,
+            });
+        }
+
+        Self {
+            path,
+            matches,
+            original: text,
+        }
+    }
+
+    pub fn included_count(&self) -> usize {
+        self.matches.iter().filter(|m| m.included).count()
+    }
+
+    /// The change to this file with only the included matches applied,
+    /// or `None` if every match was unchecked.
+    pub fn edit(&self) -> Option<FileEdit> {
+        if self.included_count() == 0 {
+            return None;
+        }
+
+        let text = &self.original;
+        let mut after = String::with_capacity(text.len());
+        let mut last = 0;
+        for m in self.matches.iter().filter(|m| m.included) {
+            after.push_str(&text[last..m.range.start]);
+            after.push_str(&m.replacement);
+            last = m.range.end;
+        }
+        after.push_str(&text[last..]);
+
+        Some(FileEdit {
+            path: self.path.clone(),
+            before: text.clone(),
+            after,
+        })
+    }
+}
+
+/// A previewed project-wide replace.
+#[derive(Clone, Debug)]
+pub struct ProjectReplace {
+    pub files: Vec<FileReplace>,
+}
+
+impl ProjectReplace {
+    /// Finds every match in the files under `roots` that a search would
+    /// look at. Files open in an editor are searched through `open_buffers`,
+    /// keyed by canonical path, so unsaved changes are what gets replaced.
+    pub fn plan(
+        query: &SearchQuery,
+        replacement: &str,
+        roots: &WorkspaceRoots,
+        scope: &SearchScope,
+        open_buffers: &HashMap<PathBuf, String>,
+        pools: Arc<Pools>,
+    ) -> Self {
+        // Nested folders are walked once, on their own
+        let paths = match roots
+            .walk_roots()
+            .into_iter()
+            .map(|(root, nested)| project_files(&root, &scope.skipping(nested)))
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(paths) => paths.concat(),
+            Err(e) => {
+                eprintln!("Invalid search filter: {}", e);
+                Vec::new()
+            }
+        };
+
+        let mut files: Vec<FileReplace> = pools.threads.install(|| {
+            paths
+                .into_par_iter()
+                .filter_map(|path| {
+                    let text = match open_buffers.get(&canonical_path(&path)) {
+                        Some(text) => text.clone(),
+                        None => read_searchable(&pools.resources, &path)?,
+                    };
+                    let file = FileReplace::new(path, text, query, replacement);
+                    (!file.matches.is_empty()).then_some(file)
+                })
+                .collect()
+        });
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Self { files }
+    }
+
+    pub fn match_count(&self) -> usize {
+        self.files.iter().map(|f| f.matches.len()).sum()
+    }
+
+    pub fn included_count(&self) -> usize {
+        self.files.iter().map(|f| f.included_count()).sum()
+    }
+
+    pub fn edits(&self) -> Vec<FileEdit> {
+        self.files.iter().filter_map(FileReplace::edit).collect()
+    }
+}
+
+/// The before/after text of one file changed by a project replace, kept so
+/// the whole operation can be undone. A file whose text no longer matches
+/// `before` changed since the preview and is skipped rather than clobbered.
+#[derive(Clone, Debug)]
+pub struct FileEdit {
+    pub path: PathBuf,
+    pub before: String,
+    pub after: String,
+}
+
+impl FileEdit {
+    pub fn reversed(&self) -> Self {
+        Self {
+            path: self.path.clone(),
+            before: self.after.clone(),
+            after: self.before.clone(),
+        }
+    }
+}
+
+/// Outcome of applying (or undoing) a project replace.
+#[derive(Clone, Debug, Default)]
+pub struct ReplaceSummary {
+    pub replacements: usize,
+    pub edits: Vec<FileEdit>,
+    /// Files that changed since the preview, or failed to write
+    pub skipped: Vec<PathBuf>,
+}
+
+impl std::fmt::Display for ReplaceSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Replaced {} occurrence{} in {} file{}",
+            self.replacements,
+            if self.replacements == 1 { "" } else { "s" },
+            self.edits.len(),
+            if self.edits.len() == 1 { "" } else { "s" },
+        )?;
+        if !self.skipped.is_empty() {
+            write!(
+                f,
+                ", skipped {} that changed since the preview or couldn't be written",
+                self.skipped.len()
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -1,51 +1,415 @@
-use gpui::*;
-use std::path::PathBuf;
+use futures::channel::mpsc::UnboundedSender;
+use ignore::{WalkBuilder, WalkState};
+use ignore::overrides::OverrideBuilder;
+use rayon::prelude::*;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use crate::pools::Pools;
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::config::{SEARCH_BINARY_SNIFF_BYTES, SEARCH_CONTEXT_LINES};
+use crate::editor::index::SearchIndex;
+use crate::pools::{Pools, ResourcePool};
+
+/// Matching options shared by the find bar and project search.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchOptions {
+    pub regex: bool,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    /// When not explicitly case sensitive, match case only if the query has uppercase letters
+    pub smart_case: bool,
+    /// Replacements copy the casing of the text they replace (foo/Foo/FOO)
+    pub preserve_case: bool,
+}
+
+/// A query compiled with its options. Literal queries are escaped and go
+/// through the same regex engine, so all modes share one matching path.
+#[derive(Clone, Debug)]
+pub struct SearchQuery {
+    regex: Regex,
+    /// Only regex queries expand `$1` / `${name}` in replacements
+    expand_captures: bool,
+    preserve_case: bool,
+    /// The text every match contains, for narrowing a search with the trigram index
+    literal: Option<String>,
+}
+
+impl SearchQuery {
+    pub fn new(query: &str, options: SearchOptions) -> Result<Self, regex::Error> {
+        let mut pattern = if options.regex {
+            query.to_string()
+        } else {
+            regex::escape(query)
+        };
+        if options.whole_word {
+            pattern = format!(r"\b(?:{})\b", pattern);
+        }
+
+        let case_sensitive =
+            options.case_sensitive || (options.smart_case && has_uppercase(query, options.regex));
+
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(!case_sensitive)
+            .multi_line(true)
+            // So `$` also matches at the end of `\r\n` lines
+            .crlf(true)
+            .build()?;
+        Ok(Self {
+            regex,
+            expand_captures: options.regex,
+            preserve_case: options.preserve_case,
+            literal: (!options.regex).then(|| query.to_string()),
+        })
+    }
+
+    /// Text every match is guaranteed to contain (ignoring case), if known.
+    /// Regex queries don't have one, so they can't use the index.
+    pub fn literal(&self) -> Option<&str> {
+        self.literal.as_deref()
+    }
+
+    /// First non-empty match starting at or after byte offset `start`.
+    /// Empty matches (e.g. from `a*`) are skipped so callers always make progress.
+    pub fn find_at(&self, text: &str, start: usize) -> Option<Range<usize>> {
+        let mut pos = start;
+        while pos <= text.len() {
+            let found = self.regex.find_at(text, pos)?;
+            if !found.is_empty() {
+                return Some(found.range());
+            }
+            pos = found.end() + text[found.end()..].chars().next().map_or(1, char::len_utf8);
+        }
+        None
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.find_at(text, 0).is_some()
+    }
+
+    /// The text that replaces the match at `range`, with capture groups
+    /// expanded and casing adjusted according to the query's options.
+    pub fn replacement_for(&self, text: &str, range: Range<usize>, replacement: &str) -> String {
+        let mut expanded = String::new();
+        match self.regex.captures_at(text, range.start) {
+            Some(caps) if self.expand_captures && caps.get(0).map(|m| m.range()) == Some(range.clone()) => {
+                caps.expand(replacement, &mut expanded);
+            }
+            _ => expanded.push_str(replacement),
+        }
+
+        if self.preserve_case {
+            match_case(&text[range], &expanded)
+        } else {
+            expanded
+        }
+    }
+
+    /// Replaces every match in `text`, returning the new text and the number of replacements.
+    pub fn replace_all(&self, text: &str, replacement: &str) -> (String, usize) {
+        let mut result = String::with_capacity(text.len());
+        let mut last = 0;
+        let mut count = 0;
+        while let Some(range) = self.find_at(text, last) {
+            result.push_str(&text[last..range.start]);
+            result.push_str(&self.replacement_for(text, range.clone(), replacement));
+            last = range.end;
+            count += 1;
+        }
+        result.push_str(&text[last..]);
+        (result, count)
+    }
+
+    pub fn count(&self, text: &str) -> usize {
+        let mut pos = 0;
+        let mut count = 0;
+        while let Some(range) = self.find_at(text, pos) {
+            pos = range.end;
+            count += 1;
+        }
+        count
+    }
+}
+
+/// Whether `query` has an uppercase letter, for smart case. In a regex the
+/// letter after a backslash is an escape like `\S` or `\W`, not text, and
+/// so is a `\p{...}` class name.
+fn has_uppercase(query: &str, regex: bool) -> bool {
+    let mut chars = query.chars();
+    while let Some(c) = chars.next() {
+        if regex && c == '\\' {
+            if let Some('p' | 'P') = chars.next()
+                && chars.next() == Some('{')
+            {
+                chars.by_ref().find(|&c| c == '}');
+            }
+        } else if c.is_uppercase() {
+            return true;
+        }
+    }
+    false
+}
+
+/// Applies the casing style of `source` to `replacement`: all caps, all
+/// lowercase, or capitalized. Mixed case leaves the replacement untouched.
+fn match_case(source: &str, replacement: &str) -> String {
+    let has_letters = source.chars().any(|c| c.is_alphabetic());
+    if !has_letters {
+        return replacement.to_string();
+    }
+
+    if !source.chars().any(|c| c.is_lowercase()) {
+        return replacement.to_uppercase();
+    }
+    if !source.chars().any(|c| c.is_uppercase()) {
+        return replacement.to_lowercase();
+    }
+
+    let mut chars = source.chars().filter(|c| c.is_alphabetic());
+    let capitalized = chars.next().is_some_and(|c| c.is_uppercase()) && chars.all(|c| c.is_lowercase());
+    if capitalized {
+        let mut out = replacement.chars();
+        match out.next() {
+            Some(first) => first.to_uppercase().chain(out.flat_map(|c| c.to_lowercase())).collect(),
+            None => String::new(),
+        }
+    } else {
+        replacement.to_string()
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct SearchResult {
     pub path: PathBuf,
+    /// 1-based
     pub line: usize,
     pub line_content: String,
+    /// Byte range of the match within `line_content`
+    pub column: Range<usize>,
+    pub context_before: Vec<String>,
+    pub context_after: Vec<String>,
 }
 
-// Simplified: just a helper to be called in a background task
-pub fn perform_search(
-    query: String,
-    root_path: PathBuf,
-    pools: Arc<Pools>,
-) -> Vec<SearchResult> {
-    // Simulate heavy work / actual search
+/// Every match in `text`, one result per line a match starts on, with up
+/// to `SEARCH_CONTEXT_LINES` of surrounding lines. The whole text is
+/// matched at once so patterns can span lines; a match that does is
+/// highlighted to the end of its first line.
+pub fn search_text(query: &SearchQuery, path: &Path, text: &str) -> Vec<SearchResult> {
+    let lines: Vec<&str> = text.lines().collect();
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
     let mut results = Vec::new();
-    if query.is_empty() {
-        return results;
+
+    let mut pos = 0;
+    while let Some(found) = query.find_at(text, pos) {
+        let i = line_starts.partition_point(|&start| start <= found.start) - 1;
+        let line = lines[i];
+        let line_start = line_starts[i];
+        // `lines` leave out a trailing `\r`, which a match may start on
+        let column = (found.start - line_start).min(line.len())
+            ..(found.end - line_start).min(line.len());
+        // On to the next line, or past the end of a match spanning lines
+        pos = line_starts.get(i + 1).map_or(text.len(), |&next| next.max(found.end));
+
+        let context_start = i.saturating_sub(SEARCH_CONTEXT_LINES);
+        let context_end = (i + 1 + SEARCH_CONTEXT_LINES).min(lines.len());
+        results.push(SearchResult {
+            path: path.to_path_buf(),
+            line: i + 1,
+            line_content: line.to_string(),
+            column,
+            context_before: lines[context_start..i].iter().map(|l| l.to_string()).collect(),
+            context_after: lines[i + 1..context_end].iter().map(|l| l.to_string()).collect(),
+        });
     }
 
-    let mut stack = vec![root_path];
+    results
+}
+
+/// Which files a project search looks at, on top of `.gitignore`/`.ignore` rules.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct SearchScope {
+    /// Globs a file has to match one of; empty means every file
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    /// Folders left out entirely, such as workspace folders nested in the
+    /// one being walked that are searched on their own
+    #[serde(skip)]
+    pub skip: Vec<PathBuf>,
+}
 
-    while let Some(path) = stack.pop() {
-        if path.is_dir() {
-            if let Ok(entries) = pools.resources.list_dir(&path) {
-                for entry in entries.flatten() {
-                    stack.push(entry.path());
-                }
+impl SearchScope {
+    /// Parses a comma separated filter such as `*.rs, !tests/**`,
+    /// where a leading `!` marks an exclude.
+    pub fn parse(filter: &str) -> Self {
+        let mut scope = Self::default();
+        for glob in filter.split(',').map(str::trim).filter(|g| !g.is_empty()) {
+            match glob.strip_prefix('!') {
+                Some(exclude) => scope.exclude.push(exclude.trim().to_string()),
+                None => scope.include.push(glob.to_string()),
             }
-        } else if path.is_file() {
-            if let Ok(content) = pools.resources.open_file(&path) {
-                for (i, line) in content.lines().enumerate() {
-                    if line.contains(&query) {
-                        results.push(SearchResult {
-                            path: path.clone(),
-                            line: i + 1,
-                            line_content: line.trim().to_string(),
-                        });
-                        if results.len() > 50 { return results; }
-                    }
-                }
+        }
+        scope
+    }
+
+    /// This scope, also leaving out the folders in `skip`.
+    pub fn skipping(&self, skip: Vec<PathBuf>) -> Self {
+        Self {
+            skip,
+            ..self.clone()
+        }
+    }
+}
+
+/// Shared flag that stops a running search.
+#[derive(Clone, Debug, Default)]
+pub struct CancelFlag(Arc<AtomicBool>);
+
+impl CancelFlag {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Files under `root` that a search should look at: anything not excluded
+/// by ignore files (even outside a git repository), hidden files, or `scope`.
+/// For the whole workspace, walk each of `WorkspaceRoots::walk_roots`.
+pub fn project_files(root: &Path, scope: &SearchScope) -> Result<Vec<PathBuf>, ignore::Error> {
+    project_files_until(root, scope, &CancelFlag::default())
+}
+
+/// `project_files`, walking directories in parallel and giving up with
+/// whatever was found so far once `cancel` is set. Sorted by path.
+pub fn project_files_until(
+    root: &Path,
+    scope: &SearchScope,
+    cancel: &CancelFlag,
+) -> Result<Vec<PathBuf>, ignore::Error> {
+    let mut overrides = OverrideBuilder::new(root);
+    for glob in &scope.include {
+        overrides.add(glob)?;
+    }
+    for glob in &scope.exclude {
+        overrides.add(&format!("!{}", glob))?;
+    }
+
+    let skip = scope.skip.clone();
+    let walker = WalkBuilder::new(root)
+        .require_git(false)
+        .overrides(overrides.build()?)
+        .filter_entry(move |entry| !skip.iter().any(|dir| entry.path() == dir))
+        .build_parallel();
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    walker.run(|| {
+        let sender = sender.clone();
+        Box::new(move |entry| {
+            if cancel.is_cancelled() {
+                return WalkState::Quit;
+            }
+            if let Ok(entry) = entry
+                && entry.file_type().is_some_and(|t| t.is_file())
+            {
+                let _ = sender.send(entry.into_path());
             }
+            WalkState::Continue
+        })
+    });
+    drop(sender);
+
+    let mut files: Vec<PathBuf> = receiver.into_iter().collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Reads a file for searching, or `None` if it can't be read or looks binary.
+/// Bypasses the file cache so a search doesn't churn it, though files that
+/// are already cached are served from memory.
+pub fn read_searchable(resources: &ResourcePool, path: &Path) -> Option<String> {
+    searchable_text(resources.read_uncached(path).ok()?)
+}
+
+/// File contents as text, or `None` if they look binary (a NUL byte near
+/// the start). Invalid UTF-8 is replaced rather than skipped.
+pub fn searchable_text(bytes: Vec<u8>) -> Option<String> {
+    let sniff = &bytes[..bytes.len().min(SEARCH_BINARY_SNIFF_BYTES)];
+    if sniff.contains(&0) {
+        return None;
+    }
+    Some(match String::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(e) => String::from_utf8_lossy(e.as_bytes()).into_owned(),
+    })
+}
+
+/// Searches files under `root_path` in parallel on the thread pool, sending
+/// each file's results as soon as it has been searched. Stops early once
+/// `cancel` is set or the receiver is dropped. An `index` of the same root
+/// skips files that can't contain the query.
+pub fn perform_search_streaming(
+    query: SearchQuery,
+    root_path: PathBuf,
+    scope: SearchScope,
+    pools: Arc<Pools>,
+    index: Option<Arc<SearchIndex>>,
+    cancel: CancelFlag,
+    results: UnboundedSender<Vec<SearchResult>>,
+) {
+    let mut files = match project_files_until(&root_path, &scope, &cancel) {
+        Ok(files) => files,
+        Err(e) => {
+            eprintln!("Invalid search filter: {}", e);
+            return;
         }
+    };
+    if let Some(index) = index.filter(|index| index.root() == root_path) {
+        files = index.narrow(files, &query);
     }
 
-    results
+    pools.threads.install(|| {
+        files.par_iter().try_for_each(|path| {
+            if cancel.is_cancelled() || results.is_closed() {
+                return Err(());
+            }
+            let Some(content) = read_searchable(&pools.resources, path) else {
+                return Ok(());
+            };
+            let file_results = search_text(&query, path, &content);
+            if file_results.is_empty() {
+                return Ok(());
+            }
+            results.unbounded_send(file_results).map_err(|_| ())
+        })
+    })
+    .ok();
+}
+
+/// Searches the whole project and returns every result, ordered by path.
+pub fn perform_search(
+    query: SearchQuery,
+    root_path: PathBuf,
+    scope: SearchScope,
+    pools: Arc<Pools>,
+) -> Vec<SearchResult> {
+    let Ok(files) = project_files(&root_path, &scope) else {
+        return Vec::new();
+    };
+
+    pools.threads.install(|| {
+        files
+            .par_iter()
+            .flat_map_iter(|path| match read_searchable(&pools.resources, path) {
+                Some(content) => search_text(&query, path, &content),
+                None => Vec::new(),
+            })
+            .collect()
+    })
 }
@@ -1,38 +1,74 @@
+use gpui::prelude::FluentBuilder;
 use gpui::{InteractiveElement, ParentElement, Render, Styled, actions, div, rgb};
 
 // use crate::config::{CHUNK_LOAD_LINES, INITIAL_LOAD_LINES};
 use crate::state::appstate::AppState;
-use gpui::Entity;
+use gpui::{Entity, EventEmitter};
 use gpui::{
-    ClipboardItem, Context, FocusHandle, IntoElement, KeyDownEvent, MouseButton, MouseDownEvent,
-    MouseMoveEvent, MouseUpEvent, ScrollWheelEvent, Window, px,
+    App, ClipboardItem, Context, FocusHandle, IntoElement, KeyDownEvent, MouseButton,
+    MouseDownEvent, MouseMoveEvent, MouseUpEvent, PathPromptOptions, ScrollWheelEvent, Task,
+    Window, px,
 };
+use futures::channel::oneshot;
 use std::ops::Range;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::editor::git::{self, Hunk, HunkKind};
+use crate::editor::language::Language;
+use crate::editor::search::SearchQuery;
+use crate::settings::{AutosaveMode, Settings};
+use crate::ui::workspace::OpenPath;
+use crate::utils::position_to_offset;
 
 use crate::structs::tree::UndoTree;
 
-#[derive(Clone)]
+/// Height the editor assumes for its viewport until it is actually measured
+const EDITOR_VIEWPORT_HEIGHT: f32 = 800.0;
+
+/// How long typing has to pause before the gutter markers are recomputed
+const DIFF_DEBOUNCE: Duration = Duration::from_millis(50);
+
 pub struct TextEditor {
     model: Entity<AppState>,
     cursor_position: usize,
     selection: Option<Range<usize>>,
     pub focus_handle: FocusHandle,
     pub file_path: Option<PathBuf>,
+    pub language: Language,
     undo_tree: UndoTree,
     scroll_x: f32,
     scroll_y: f32,
     line_height: f32,
     is_selecting: bool,
+    /// Undo tree node that matches what is on disk; the buffer is dirty when
+    /// the current node differs from it.
+    saved_version: usize,
+    /// Pending "after delay" autosave, replaced (and so cancelled) on every edit
+    autosave_task: Option<Task<()>>,
+    /// The file as committed at HEAD, what the gutter markers compare against
+    diff_base: Option<Arc<String>>,
+    /// Changed line runs since HEAD, recomputed shortly after each edit
+    hunks: Vec<Hunk>,
+    diff_task: Option<Task<()>>,
+    /// Hunk whose HEAD lines are shown under it
+    previewed_hunk: Option<usize>,
 }
 
 actions!(
     TextEditor,
     [
-        NewFile, OpenFile, SaveFile, SaveFileAs, Undo, Redo, Cut, Copy, Paste
+        NewFile, OpenFile, SaveFile, SaveFileAs, Undo, Redo, Cut, Copy, Paste,
+        NextHunk, PreviousHunk, PreviewHunk, RevertHunk
     ]
 );
 
+/// Emitted after the buffer was written to its file.
+pub struct FileSaved;
+
+impl EventEmitter<FileSaved> for TextEditor {}
+
 impl Render for TextEditor {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let text = self.get_text(cx);
@@ -41,7 +77,7 @@ impl Render for TextEditor {
             lines.push("");
         }
         let line_count = lines.len();
-        let viewport_height = 800.0;
+        let viewport_height = EDITOR_VIEWPORT_HEIGHT;
         let start_line = (self.scroll_y / self.line_height).floor() as usize;
         let visible_lines = (viewport_height / self.line_height).ceil() as usize;
         let end_line = std::cmp::min(line_count, start_line + visible_lines);
@@ -56,6 +92,35 @@ impl Render for TextEditor {
         let content_y = -(self.scroll_y % self.line_height);
         let line_height = self.line_height;
 
+        // HEAD lines of the previewed hunk, shown in a box under it
+        let preview = self
+            .previewed_hunk
+            .and_then(|ix| Some((self.hunks.get(ix)?, self.diff_base.as_ref()?)))
+            .map(|(hunk, base)| {
+                let below = hunk.new_lines.end.max(hunk.marker_line(line_count) + 1);
+                let top = (below as f32 - start_line as f32) * line_height + content_y + 8.0;
+                let original = git::original_lines(base, hunk);
+                div()
+                    .absolute()
+                    .top(px(top))
+                    .left(px(44.0))
+                    .right(px(8.0))
+                    .p_1()
+                    .bg(rgb(0x2d2d2d))
+                    .border_1()
+                    .border_color(rgb(0x454545))
+                    .text_sm()
+                    .when(original.is_empty(), |this| {
+                        this.text_color(rgb(0x888888)).child("Added since HEAD")
+                    })
+                    .children(original.into_iter().map(|line| {
+                        div()
+                            .h(px(line_height))
+                            .text_color(rgb(HunkKind::Deleted.color()))
+                            .child(if line.is_empty() { " ".to_string() } else { line })
+                    }))
+            });
+
         div()
             .track_focus(&self.focus_handle)
             .on_action(cx.listener(TextEditor::new_file))
@@ -67,6 +132,10 @@ impl Render for TextEditor {
             .on_action(cx.listener(TextEditor::cut))
             .on_action(cx.listener(TextEditor::copy))
             .on_action(cx.listener(TextEditor::paste))
+            .on_action(cx.listener(TextEditor::next_hunk))
+            .on_action(cx.listener(TextEditor::previous_hunk))
+            .on_action(cx.listener(TextEditor::preview_hunk))
+            .on_action(cx.listener(TextEditor::revert_hunk))
             .on_key_down(cx.listener(TextEditor::handle_key_down))
             .on_scroll_wheel(cx.listener(TextEditor::handle_scroll_wheel))
             .on_mouse_down(
@@ -77,11 +146,12 @@ impl Render for TextEditor {
             .on_mouse_move(cx.listener(TextEditor::handle_mouse_move))
             .key_context("Editor")
             .size_full()
+            .relative()
             .flex()
             .flex_row()
             .child(
                 div()
-                    .w_8()
+                    .w_10()
                     .h_full()
                     // .bg(rgb(0x1e1e1e))
                     // .border_r_1()
@@ -99,10 +169,33 @@ impl Render for TextEditor {
                             .flex_col()
                             // Offset gutter to match content
                             .mt(px(content_y))
-                            .children(
-                                (start_line..end_line)
-                                    .map(|i| div().text_right().child((i + 1).to_string())),
-                            ),
+                            .children((start_line..end_line).map(|i| {
+                                let marker = self
+                                    .hunks
+                                    .iter()
+                                    .find(|hunk| hunk.contains_line(i, line_count))
+                                    .map(|hunk| hunk.kind);
+                                div()
+                                    .h(px(line_height))
+                                    .flex()
+                                    .flex_row()
+                                    .child(
+                                        // Deletions get a short bar at the top of the next line
+                                        div()
+                                            .w(px(3.0))
+                                            .mr_1()
+                                            .when_some(marker, |this, kind| {
+                                                this.bg(rgb(kind.color())).h(
+                                                    if kind == HunkKind::Deleted {
+                                                        px(4.0)
+                                                    } else {
+                                                        px(line_height)
+                                                    },
+                                                )
+                                            }),
+                                    )
+                                    .child(div().flex_1().text_right().child((i + 1).to_string()))
+                            })),
                     ),
             )
             .child(
@@ -163,6 +256,7 @@ impl Render for TextEditor {
                             )),
                     ),
             )
+            .children(preview)
     }
 }
 
@@ -170,6 +264,7 @@ impl TextEditor {
     pub fn new(cx: &mut Context<Self>, model: Entity<AppState>) -> Self {
         // Observe the model to trigger updates when state changes
         cx.observe(&model, |_, _window, cx| cx.notify()).detach();
+        let initial_text = model.read(cx).text.clone();
 
         Self {
             model,
@@ -177,26 +272,216 @@ impl TextEditor {
             selection: None,
             focus_handle: cx.focus_handle(),
             file_path: None,
+            language: Language::PlainText,
             scroll_x: 0.0,
             scroll_y: 0.0,
-            undo_tree: UndoTree::new(""),
+            undo_tree: UndoTree::new(&initial_text),
             line_height: 20.0, // Approximation, should ideally be measured
             is_selecting: false,
+            saved_version: 0,
+            autosave_task: None,
+            diff_base: None,
+            hunks: Vec::new(),
+            diff_task: None,
+            previewed_hunk: None,
         }
     }
 
-    pub fn get_text(&self, cx: &Context<Self>) -> String {
+    /// Identifies the buffer contents; changes whenever the text is edited.
+    pub fn version(&self) -> usize {
+        self.undo_tree.current_id()
+    }
+
+    /// True if the buffer differs from the last saved (or loaded) version.
+    pub fn is_dirty(&self) -> bool {
+        self.undo_tree.current_id() != self.saved_version
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.saved_version = self.undo_tree.current_id();
+    }
+
+    /// Points the buffer at a new file, e.g. after Save As.
+    pub fn set_file_path(&mut self, path: PathBuf, cx: &mut Context<Self>) {
+        self.language = Language::from_path(&path);
+        self.file_path = Some(path);
+        // Markers for the old path don't apply
+        self.diff_base = None;
+        self.hunks.clear();
+        self.reload_diff_base(cx);
+        cx.notify();
+    }
+
+    /// Reads the file's HEAD contents again on the thread pool, such as
+    /// after a commit, and recomputes the gutter markers if they changed.
+    pub fn reload_diff_base(&mut self, cx: &mut Context<Self>) {
+        let Some(path) = self.file_path.clone() else {
+            self.diff_base = None;
+            self.schedule_diff(cx);
+            return;
+        };
+        let (sender, receiver) = oneshot::channel();
+        let read_path = path.clone();
+        self.model.read(cx).pools.threads.spawn(move || {
+            sender.send(git::head_text(&read_path)).ok();
+        });
+        cx.spawn(async move |this, cx| {
+            let Ok(base) = receiver.await else {
+                return;
+            };
+            this.update(cx, |this, cx| {
+                // Saved under another name in the meantime
+                if this.file_path.as_ref() != Some(&path)
+                    || this.diff_base.as_deref() == base.as_ref()
+                {
+                    return;
+                }
+                this.diff_base = base.map(Arc::new);
+                this.schedule_diff(cx);
+                cx.notify();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Recomputes the hunks once typing pauses. Comparing whole files is
+    /// cheap next to a keystroke, but not free for big ones.
+    fn schedule_diff(&mut self, cx: &mut Context<Self>) {
+        self.previewed_hunk = None;
+        let Some(base) = self.diff_base.clone() else {
+            self.hunks.clear();
+            self.diff_task = None;
+            return;
+        };
+        self.diff_task = Some(cx.spawn(async move |this, cx| {
+            cx.background_executor().timer(DIFF_DEBOUNCE).await;
+            let Ok((text, version)) = this.update(cx, |this, cx| (this.get_text(cx), this.version()))
+            else {
+                return;
+            };
+            let hunks = cx
+                .background_executor()
+                .spawn(async move { git::diff_lines(&base, &text) })
+                .await;
+            this.update(cx, |this, cx| {
+                if this.version() == version {
+                    this.hunks = hunks;
+                    cx.notify();
+                }
+            })
+            .ok();
+        }));
+    }
+
+    /// Changed line runs since HEAD, as of the last pause in typing.
+    pub fn hunks(&self) -> &[Hunk] {
+        &self.hunks
+    }
+
+    /// Name shown to the user, e.g. in tabs and save prompts.
+    pub fn display_name(&self) -> String {
+        self.file_path
+            .as_ref()
+            .and_then(|p| p.file_name())
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "Untitled".to_string())
+    }
+
+    /// Records the current text as a new undo step.
+    fn commit_edit(&mut self, cx: &mut Context<Self>) {
+        let text = self.get_text(cx);
+        self.undo_tree.commit(text);
+        self.schedule_autosave(cx);
+        self.schedule_diff(cx);
+    }
+
+    fn schedule_autosave(&mut self, cx: &mut Context<Self>) {
+        let settings = Settings::get(cx);
+        if settings.autosave != AutosaveMode::AfterDelay {
+            self.autosave_task = None;
+            return;
+        }
+        let delay = Duration::from_millis(settings.autosave_delay_ms);
+        self.autosave_task = Some(cx.spawn(async move |this, cx| {
+            cx.background_executor().timer(delay).await;
+            this.update(cx, |this, cx| this.autosave(cx)).ok();
+        }));
+    }
+
+    /// Saves the buffer if it is dirty and has somewhere to go.
+    /// Untitled buffers are left alone, they are covered by the recovery journal.
+    pub fn autosave(&mut self, cx: &mut Context<Self>) {
+        if self.is_dirty()
+            && self.file_path.is_some()
+            && let Err(e) = self.save(cx)
+        {
+            eprintln!("Autosave failed: {}", e);
+        }
+    }
+
+    /// Autosaves only when the configured mode matches `trigger`.
+    pub fn autosave_on(&mut self, trigger: AutosaveMode, cx: &mut Context<Self>) {
+        if Settings::get(cx).autosave == trigger {
+            self.autosave(cx);
+        }
+    }
+
+    /// Reopens a buffer from the recovery journal. The restored text is
+    /// left dirty so the user decides whether to keep it.
+    pub fn restore(&mut self, path: Option<PathBuf>, text: String, cx: &mut Context<Self>) {
+        if let Some(path) = path {
+            if path.exists() {
+                self.open_file_from_path(path.clone(), cx);
+            }
+            self.set_file_path(path, cx);
+        }
+        // The journal can lag behind a save, don't flag an identical buffer as dirty
+        if self.get_text(cx) != text {
+            self.set_text(text, cx);
+        }
+        cx.notify();
+    }
+
+    /// Replaces the buffer with the undo tree's current state.
+    fn restore_undo_state(&mut self, cx: &mut Context<Self>) {
+        let state = self.undo_tree.current_state();
+        let mut cursor = self.cursor_position.min(state.len());
+        while !state.is_char_boundary(cursor) {
+            cursor -= 1;
+        }
+        self.model.update(cx, |model, _| model.text = state);
+        self.cursor_position = cursor;
+        self.selection = None;
+        self.schedule_diff(cx);
+    }
+
+    pub fn get_text(&self, cx: &App) -> String {
         self.model.read(cx).text.clone()
     }
 
+    /// Replaces the buffer as an edit that can be undone.
     pub fn set_text(&mut self, text: String, cx: &mut Context<Self>) {
-        let text_clone = text.clone();
+        self.replace_buffer(text.clone(), cx);
+        self.undo_tree.commit(text);
+        self.schedule_diff(cx);
+    }
+
+    /// Replaces the buffer with freshly loaded contents, starting a new
+    /// undo history so undo can't go back past them.
+    fn load_text(&mut self, text: String, cx: &mut Context<Self>) {
+        self.undo_tree.reset(&text);
+        self.replace_buffer(text, cx);
+        self.mark_saved();
+        self.schedule_diff(cx);
+    }
+
+    fn replace_buffer(&mut self, text: String, cx: &mut Context<Self>) {
+        self.cursor_position = text.len();
         self.model.update(cx, |model, _| model.text = text);
-        self.cursor_position = text_clone.len();
         self.selection = None;
         self.scroll_x = 0.0;
         self.scroll_y = 0.0;
-        self.undo_tree.commit(text_clone);
     }
 
     pub fn insert_text_at_cursor(&mut self, text: &str, cx: &mut Context<Self>) {
@@ -219,6 +504,7 @@ impl TextEditor {
 
         self.cursor_position = new_cursor_pos;
         self.selection = None;
+        self.commit_edit(cx);
         cx.notify();
     }
 
@@ -226,8 +512,8 @@ impl TextEditor {
         let pools = self.model.read(cx).pools.clone();
         match pools.resources.open_file(&path) {
             Ok(content) => {
-                self.set_text(content, cx);
-                self.file_path = Some(path);
+                self.load_text(content, cx);
+                self.set_file_path(path, cx);
                 cx.notify();
             }
             Err(e) => {
@@ -366,6 +652,48 @@ impl TextEditor {
         }
     }
 
+    /// Moves the cursor to a 1-based line and column and scrolls it into view.
+    pub fn go_to_position(&mut self, line: usize, column: usize, cx: &mut Context<Self>) {
+        self.cursor_position = position_to_offset(&self.model.read(cx).text, line, column);
+        self.selection = None;
+        self.scroll_to_cursor(cx);
+        cx.notify();
+    }
+
+    /// Cursor offset and scroll position, for returning to after a preview.
+    pub fn view_position(&self) -> (usize, f32) {
+        (self.cursor_position, self.scroll_y)
+    }
+
+    pub fn restore_view_position(
+        &mut self,
+        (cursor, scroll_y): (usize, f32),
+        cx: &mut Context<Self>,
+    ) {
+        self.cursor_position = cursor.min(self.model.read(cx).text.len());
+        self.scroll_y = scroll_y;
+        self.selection = None;
+        cx.notify();
+    }
+
+    /// 1-based line the cursor is on, and the number of lines in the buffer.
+    pub fn cursor_line(&self, cx: &App) -> (usize, usize) {
+        let text = &self.model.read(cx).text;
+        let line = text[..self.cursor_position].matches('\n').count() + 1;
+        (line, text.matches('\n').count() + 1)
+    }
+
+    /// Scrolls vertically so the cursor's line is on screen, leaving some
+    /// lines above it when a jump lands off screen.
+    fn scroll_to_cursor(&mut self, cx: &Context<Self>) {
+        let row = self.cursor_row(cx) as f32;
+        let first_visible = self.scroll_y / self.line_height;
+        let visible_lines = EDITOR_VIEWPORT_HEIGHT / self.line_height;
+        if row < first_visible || row >= first_visible + visible_lines - 1.0 {
+            self.scroll_y = ((row - visible_lines / 3.0).max(0.0) * self.line_height).floor();
+        }
+    }
+
     // Helper methods for cursor rendering
     fn cursor_row(&self, cx: &Context<Self>) -> usize {
         let text = self.model.read(cx).text.clone();
@@ -394,103 +722,300 @@ impl TextEditor {
                 start
             });
             self.cursor_position = new_pos;
+            self.commit_edit(cx);
         }
     }
 
     fn handle_delete(&mut self, cx: &mut Context<Self>) {
         let cursor_pos = self.cursor_position;
-        self.model.update(cx, move |model, _| {
+        let deleted = self.model.update(cx, move |model, _| {
             let content = &mut model.text;
             if cursor_pos < content.len() {
                 content.remove(cursor_pos);
+                true
+            } else {
+                false
             }
         });
+        if deleted {
+            self.commit_edit(cx);
+        }
     }
 
     fn handle_enter(&mut self, cx: &mut Context<Self>) {
         self.insert_text_at_cursor("\n", cx);
     }
 
-    pub fn find_next(&mut self, query: &str, cx: &mut Context<Self>) {
-        if query.is_empty() {
-            return;
-        }
+    pub fn find_next(&mut self, query: &SearchQuery, cx: &mut Context<Self>) {
         let text = self.model.read(cx).text.clone();
 
-        // Simple search from cursor position
-        if let Some(idx) = text[self.cursor_position..].find(query) {
-            let start = self.cursor_position + idx;
-            let end = start + query.len();
-            self.selection = Some(start..end);
-            self.cursor_position = end;
+        // Search from cursor position, wrapping around to the start
+        if let Some(range) = query
+            .find_at(&text, self.cursor_position)
+            .or_else(|| query.find_at(&text, 0))
+        {
+            self.cursor_position = range.end;
+            self.selection = Some(range);
             cx.notify();
-        } else {
-            // Wrap around
-            if let Some(idx) = text.find(query) {
-                let start = idx;
-                let end = start + query.len();
-                self.selection = Some(start..end);
-                self.cursor_position = end;
-                cx.notify();
-            }
         }
     }
+
+    /// The selection, if it is exactly a match of `query`.
+    fn selected_match(&self, query: &SearchQuery, cx: &App) -> Option<Range<usize>> {
+        let selection = self.selection.clone()?;
+        let text = &self.model.read(cx).text;
+        (query.find_at(text, selection.start) == Some(selection.clone())).then_some(selection)
+    }
+
+    /// What replacing the selected match would produce, for previewing in the search bar.
+    pub fn replace_preview(
+        &self,
+        query: &SearchQuery,
+        replacement: &str,
+        cx: &App,
+    ) -> Option<(String, String)> {
+        let range = self.selected_match(query, cx)?;
+        let text = &self.model.read(cx).text;
+        Some((
+            text[range.clone()].to_string(),
+            query.replacement_for(text, range, replacement),
+        ))
+    }
+
+    /// Replaces the selected match and moves on to the next one.
+    /// If nothing is selected yet, the first call only finds.
+    pub fn replace_next(&mut self, query: &SearchQuery, replacement: &str, cx: &mut Context<Self>) {
+        if let Some(range) = self.selected_match(query, cx) {
+            let text = self.get_text(cx);
+            let replaced = query.replacement_for(&text, range.clone(), replacement);
+            let end = range.start + replaced.len();
+            self.model
+                .update(cx, |model, _| model.text.replace_range(range, &replaced));
+            self.cursor_position = end;
+            self.selection = None;
+            self.commit_edit(cx);
+        }
+        self.find_next(query, cx);
+        cx.notify();
+    }
+
+    /// Replaces every match in the buffer as a single undo step.
+    pub fn replace_all(
+        &mut self,
+        query: &SearchQuery,
+        replacement: &str,
+        cx: &mut Context<Self>,
+    ) -> usize {
+        let (text, count) = query.replace_all(&self.get_text(cx), replacement);
+        if count > 0 {
+            self.replace_contents(text, cx);
+        }
+        count
+    }
+
+    /// Swaps in new text as one undoable edit, keeping the cursor roughly in place.
+    /// Unlike `set_text` this marks the buffer dirty and doesn't reset the view.
+    pub fn replace_contents(&mut self, text: String, cx: &mut Context<Self>) {
+        let mut cursor = self.cursor_position.min(text.len());
+        while !text.is_char_boundary(cursor) {
+            cursor -= 1;
+        }
+        self.model.update(cx, |model, _| model.text = text);
+        self.cursor_position = cursor;
+        self.selection = None;
+        self.commit_edit(cx);
+        cx.notify();
+    }
 }
 
 impl TextEditor {
     pub fn new_file(&mut self, _: &NewFile, _window: &mut Window, cx: &mut Context<Self>) {
-        self.set_text(String::new(), cx);
+        self.load_text(String::new(), cx);
         self.file_path = None;
+        self.language = Language::PlainText;
+        self.reload_diff_base(cx);
         cx.notify();
     }
 
-    pub fn open_file(&mut self, _: &OpenFile, _window: &mut Window, cx: &mut Context<Self>) {
-        // For now, open a sample file - in real implementation would show file dialog
-        let sample_path = PathBuf::from(".");
-        if sample_path.exists() {
-            // Try to open a test file if it exists
-            let test_file = sample_path.join("test.txt");
-            if test_file.exists() {
-                self.open_file_from_path(test_file, cx);
-            } else {
-                self.set_text(
-                    "// No file selected. Use file browser to open files.\n".to_string(),
-                    cx,
-                );
-                cx.notify();
-            }
-        }
+    /// Shows the platform file picker and opens each chosen file in its own tab.
+    pub fn open_file(&mut self, _: &OpenFile, window: &mut Window, cx: &mut Context<Self>) {
+        let paths = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: true,
+            prompt: None,
+        });
+
+        cx.spawn_in(window, async move |_, cx| {
+            let paths = match paths.await {
+                Ok(Ok(Some(paths))) => paths,
+                Ok(Err(e)) => {
+                    eprintln!("Failed to open file picker: {}", e);
+                    return;
+                }
+                _ => return,
+            };
+            cx.update(|window, cx| {
+                for path in paths {
+                    window.dispatch_action(Box::new(OpenPath::new(path)), cx);
+                }
+            })
+            .ok();
+        })
+        .detach();
     }
 
-    pub fn save_file(&mut self, _: &SaveFile, _window: &mut Window, cx: &mut Context<Self>) {
-        if let Some(ref path) = self.file_path {
-            let content = self.get_text(cx);
-            let app_state = self.model.read(cx);
-            if let Err(e) = app_state.pools.resources.save_file(path, content) {
+    /// Writes the buffer to its file and clears the dirty flag.
+    pub fn save(&mut self, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let Some(path) = self.file_path.clone() else {
+            anyhow::bail!("{} has no file path", self.display_name());
+        };
+        let content = self.get_text(cx);
+        let app_state = self.model.read(cx);
+        app_state.pools.resources.save_file(&path, content)?;
+        self.mark_saved();
+        cx.emit(FileSaved);
+        cx.notify();
+        Ok(())
+    }
+
+    /// Asks for a new location, then saves there. Resolves to whether the
+    /// buffer was written.
+    pub fn save_as(&mut self, cx: &mut Context<Self>) -> Task<bool> {
+        let directory = self
+            .file_path
+            .as_ref()
+            .and_then(|p| p.parent())
+            .filter(|p| !p.as_os_str().is_empty())
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from("."));
+        // The platform pickers want an absolute starting directory
+        let directory = directory.canonicalize().unwrap_or(directory);
+        let suggested_name = self
+            .file_path
+            .as_ref()
+            .and_then(|p| p.file_name())
+            .map(|n| n.to_string_lossy().to_string());
+        let new_path = cx.prompt_for_new_path(&directory, suggested_name.as_deref());
+
+        cx.spawn(async move |this, cx| {
+            let path = match new_path.await {
+                Ok(Ok(Some(path))) => path,
+                Ok(Err(e)) => {
+                    eprintln!("Failed to open save dialog: {}", e);
+                    return false;
+                }
+                _ => return false,
+            };
+            this.update(cx, |this, cx| {
+                this.set_file_path(path, cx);
+                match this.save(cx) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        eprintln!("Failed to save file: {}", e);
+                        false
+                    }
+                }
+            })
+            .unwrap_or(false)
+        })
+    }
+
+    /// Saves in place, or goes through Save As for untitled buffers.
+    pub fn save_or_prompt(&mut self, cx: &mut Context<Self>) -> Task<bool> {
+        if self.file_path.is_none() {
+            return self.save_as(cx);
+        }
+        match self.save(cx) {
+            Ok(()) => Task::ready(true),
+            Err(e) => {
                 eprintln!("Failed to save file: {}", e);
-            } else {
-                cx.notify();
+                Task::ready(false)
             }
         }
     }
 
+    pub fn save_file(&mut self, _: &SaveFile, _window: &mut Window, cx: &mut Context<Self>) {
+        self.save_or_prompt(cx).detach();
+    }
+
     pub fn save_file_as(&mut self, _: &SaveFileAs, _window: &mut Window, cx: &mut Context<Self>) {
-        // Placeholder - would show save dialog
+        self.save_as(cx).detach();
+    }
+
+    /// Index of the hunk on the cursor's line.
+    fn hunk_at_cursor(&self, hunks: &[Hunk], cx: &App) -> Option<usize> {
+        let text = &self.model.read(cx).text;
+        let line_count = text.lines().count().max(1);
+        let row = text[..self.cursor_position].matches('\n').count();
+        hunks
+            .iter()
+            .position(|hunk| hunk.contains_line(row, line_count))
+    }
+
+    fn go_to_hunk(&mut self, forward: bool, cx: &mut Context<Self>) {
+        let text = &self.model.read(cx).text;
+        let line_count = text.lines().count().max(1);
+        let row = text[..self.cursor_position].matches('\n').count();
+        let lines: Vec<usize> = self
+            .hunks
+            .iter()
+            .map(|hunk| hunk.marker_line(line_count))
+            .collect();
+        // Wraps around at either end
+        let target = if forward {
+            lines.iter().find(|line| **line > row).or(lines.first())
+        } else {
+            lines.iter().rev().find(|line| **line < row).or(lines.last())
+        };
+        if let Some(line) = target.copied() {
+            self.go_to_position(line + 1, 1, cx);
+        }
+    }
+
+    pub fn next_hunk(&mut self, _: &NextHunk, _window: &mut Window, cx: &mut Context<Self>) {
+        self.go_to_hunk(true, cx);
+    }
+
+    pub fn previous_hunk(&mut self, _: &PreviousHunk, _window: &mut Window, cx: &mut Context<Self>) {
+        self.go_to_hunk(false, cx);
+    }
+
+    /// Shows or hides the HEAD lines of the hunk on the cursor's line.
+    pub fn preview_hunk(&mut self, _: &PreviewHunk, _window: &mut Window, cx: &mut Context<Self>) {
+        let hunk = self.hunk_at_cursor(&self.hunks, cx);
+        self.previewed_hunk = if self.previewed_hunk == hunk { None } else { hunk };
         cx.notify();
     }
 
+    /// Puts the hunk on the cursor's line back the way it is at HEAD.
+    pub fn revert_hunk(&mut self, _: &RevertHunk, _window: &mut Window, cx: &mut Context<Self>) {
+        let Some(base) = self.diff_base.clone() else {
+            return;
+        };
+        // The markers may lag behind the last keystroke
+        let text = self.get_text(cx);
+        let hunks = git::diff_lines(&base, &text);
+        let Some(ix) = self.hunk_at_cursor(&hunks, cx) else {
+            return;
+        };
+        self.replace_contents(git::revert_hunk(&base, &text, &hunks[ix]), cx);
+    }
+
     pub fn undo(&mut self, _: &Undo, _window: &mut Window, cx: &mut Context<Self>) {
         self.undo_tree.undo();
+        self.restore_undo_state(cx);
         cx.notify();
     }
 
     pub fn redo(&mut self, _: &Redo, _window: &mut Window, cx: &mut Context<Self>) {
         self.undo_tree.redo();
+        self.restore_undo_state(cx);
         cx.notify();
     }
 
     pub fn cut(&mut self, _: &Cut, _window: &mut Window, cx: &mut Context<Self>) {
-        if let Some(selection) = &self.selection {
+        if let Some(selection) = self.selection.clone() {
             let text = self.model.read(cx).text.clone();
             if selection.end <= text.len() {
                 let selected_text = text[selection.clone()].to_string();
@@ -502,6 +1027,7 @@ impl TextEditor {
                 });
                 self.cursor_position = selection.start;
                 self.selection = None;
+                self.commit_edit(cx);
                 cx.notify();
             }
         }
@@ -518,11 +1044,11 @@ impl TextEditor {
     }
 
     pub fn paste(&mut self, _: &Paste, _window: &mut Window, cx: &mut Context<Self>) {
-        if let Some(item) = cx.read_from_clipboard() {
-            if let Some(s) = item.text() {
-                self.insert_text_at_cursor(&s, cx);
-                cx.notify();
-            }
+        if let Some(item) = cx.read_from_clipboard()
+            && let Some(s) = item.text()
+        {
+            self.insert_text_at_cursor(&s, cx);
+            cx.notify();
         }
     }
 
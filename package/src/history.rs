@@ -0,0 +1,84 @@
+use gpui::Global;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::config::{SEARCH_HISTORY_FILE_NAME, SEARCH_HISTORY_LIMIT};
+use crate::editor::search::SearchOptions;
+use crate::persist::JsonFile;
+use crate::utils::data_dir;
+
+/// A pinned search that can be re-run from the command palette.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub name: String,
+    pub query: String,
+    /// Project search rather than find in the active buffer
+    pub global: bool,
+    #[serde(default)]
+    pub options: SearchOptions,
+    /// Include/exclude globs, as typed in the search bar
+    #[serde(default)]
+    pub filter: String,
+}
+
+/// Previous search queries and pinned searches, persisted as JSON in the
+/// data directory so they survive restarts.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchHistory {
+    /// Oldest first
+    pub queries: Vec<String>,
+    pub saved: Vec<SavedSearch>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl Global for SearchHistory {}
+
+impl SearchHistory {
+    /// Loads history from disk, starting empty if missing or invalid.
+    pub fn load() -> Self {
+        Self::load_from(data_dir().join(SEARCH_HISTORY_FILE_NAME))
+    }
+
+    /// Records a query as the most recent, dropping an older copy and the
+    /// oldest entries past the limit. Returns false if nothing changed.
+    pub fn push(&mut self, query: &str) -> bool {
+        if query.is_empty() || self.queries.last().is_some_and(|q| q == query) {
+            return false;
+        }
+        self.queries.retain(|q| q != query);
+        self.queries.push(query.to_string());
+        let excess = self.queries.len().saturating_sub(SEARCH_HISTORY_LIMIT);
+        self.queries.drain(..excess);
+        true
+    }
+
+    /// Saves a search, replacing one with the same name.
+    pub fn pin(&mut self, search: SavedSearch) {
+        match self.saved.iter_mut().find(|s| s.name == search.name) {
+            Some(existing) => *existing = search,
+            None => self.saved.push(search),
+        }
+    }
+
+    pub fn unpin(&mut self, name: &str) {
+        self.saved.retain(|s| s.name != name);
+    }
+
+    pub fn saved(&self, name: &str) -> Option<&SavedSearch> {
+        self.saved.iter().find(|s| s.name == name)
+    }
+}
+
+impl JsonFile for SearchHistory {
+    const NAME: &'static str = "search history";
+
+    fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    fn set_path(&mut self, path: PathBuf) {
+        self.path = Some(path);
+    }
+}
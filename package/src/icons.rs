@@ -0,0 +1,165 @@
+use gpui::{App, Global};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use crate::config::{DIR_ICON, DIR_OPEN_ICON, FILE_ICON, ICON_THEME_FILE_NAME};
+use crate::utils::data_dir;
+
+/// Icons shown when no theme is registered with the app.
+static BUILT_IN: LazyLock<IconTheme> = LazyLock::new(IconTheme::default);
+
+/// Well-known file names, matched before extensions.
+const FILE_NAME_ICONS: &[(&str, &str)] = &[
+    ("Cargo.toml", "📦"),
+    ("Cargo.lock", "🔒"),
+    ("package.json", "📦"),
+    ("package-lock.json", "🔒"),
+    ("yarn.lock", "🔒"),
+    (".gitignore", "🙈"),
+    (".gitattributes", "🙈"),
+    ("Dockerfile", "🐳"),
+    ("Makefile", "🛠"),
+    ("LICENSE", "📜"),
+    ("README.md", "📖"),
+];
+
+const EXTENSION_ICONS: &[(&str, &str)] = &[
+    ("rs", "🦀"),
+    ("toml", "⚙"),
+    ("json", "🧾"),
+    ("yaml", "🧾"),
+    ("yml", "🧾"),
+    ("md", "📝"),
+    ("txt", "📝"),
+    ("js", "🟨"),
+    ("ts", "🟦"),
+    ("tsx", "🟦"),
+    ("jsx", "🟨"),
+    ("py", "🐍"),
+    ("go", "🐹"),
+    ("c", "🇨"),
+    ("h", "🇨"),
+    ("cpp", "➕"),
+    ("html", "🌐"),
+    ("css", "🎨"),
+    ("sh", "💲"),
+    ("png", "🖼"),
+    ("jpg", "🖼"),
+    ("jpeg", "🖼"),
+    ("gif", "🖼"),
+    ("svg", "🖼"),
+    ("lock", "🔒"),
+];
+
+/// On-disk form of an icon theme. Everything is optional, what's left out
+/// keeps the built-in icon.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct IconThemeFile {
+    file: Option<String>,
+    folder: Option<String>,
+    folder_open: Option<String>,
+    file_names: HashMap<String, String>,
+    extensions: HashMap<String, String>,
+}
+
+/// Icons for the file browser, picked by file name first, then extension.
+/// Loaded from a JSON theme file in the data directory on top of the
+/// built-in icons.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IconTheme {
+    pub file: String,
+    pub folder: String,
+    pub folder_open: String,
+    pub file_names: HashMap<String, String>,
+    /// Keyed by lowercase extension, without the dot
+    pub extensions: HashMap<String, String>,
+}
+
+impl Default for IconTheme {
+    fn default() -> Self {
+        let map = |icons: &[(&str, &str)]| {
+            icons
+                .iter()
+                .map(|(key, icon)| (key.to_string(), icon.to_string()))
+                .collect()
+        };
+        Self {
+            file: FILE_ICON.to_string(),
+            folder: DIR_ICON.to_string(),
+            folder_open: DIR_OPEN_ICON.to_string(),
+            file_names: map(FILE_NAME_ICONS),
+            extensions: map(EXTENSION_ICONS),
+        }
+    }
+}
+
+impl Global for IconTheme {}
+
+impl IconTheme {
+    /// Loads the theme from disk, using the built-in icons if it's missing
+    /// or invalid.
+    pub fn load() -> Self {
+        Self::load_from(&data_dir().join(ICON_THEME_FILE_NAME))
+    }
+
+    pub fn load_from(path: &Path) -> Self {
+        let Ok(text) = fs::read_to_string(path) else {
+            return Self::default();
+        };
+        match serde_json::from_str::<IconThemeFile>(&text) {
+            Ok(file) => Self::default().merged(file),
+            Err(e) => {
+                eprintln!("Invalid icon theme {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    fn merged(mut self, file: IconThemeFile) -> Self {
+        self.file = file.file.unwrap_or(self.file);
+        self.folder = file.folder.unwrap_or(self.folder);
+        self.folder_open = file.folder_open.unwrap_or(self.folder_open);
+        self.file_names.extend(file.file_names);
+        self.extensions.extend(
+            file.extensions
+                .into_iter()
+                .map(|(ext, icon)| (ext.trim_start_matches('.').to_lowercase(), icon)),
+        );
+        self
+    }
+
+    /// The theme registered with the app, or the built-in one.
+    pub fn global(cx: &App) -> &IconTheme {
+        cx.try_global::<IconTheme>().unwrap_or(&BUILT_IN)
+    }
+
+    pub fn folder_icon(&self, expanded: bool) -> &str {
+        if expanded {
+            &self.folder_open
+        } else {
+            &self.folder
+        }
+    }
+
+    pub fn file_icon(&self, path: &Path) -> &str {
+        let name = path.file_name().map(|name| name.to_string_lossy());
+        if let Some(icon) = name.as_deref().and_then(|name| self.file_names.get(name)) {
+            return icon;
+        }
+        path.extension()
+            .and_then(|ext| self.extensions.get(&ext.to_string_lossy().to_lowercase()))
+            .unwrap_or(&self.file)
+    }
+
+    pub fn icon(&self, path: &Path, is_dir: bool, expanded: bool) -> &str {
+        if is_dir {
+            self.folder_icon(expanded)
+        } else {
+            self.file_icon(path)
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::config::{
+    BROWSER_DEFAULT_WIDTH, BROWSER_MAX_WIDTH, BROWSER_MIN_WIDTH, LAYOUT_FILE_NAME,
+};
+use crate::persist::JsonFile;
+use crate::utils::data_dir;
+
+/// Which side of the window the file browser sits on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Dock {
+    #[default]
+    Left,
+    Right,
+}
+
+impl Dock {
+    pub fn other(&self) -> Self {
+        match self {
+            Dock::Left => Dock::Right,
+            Dock::Right => Dock::Left,
+        }
+    }
+}
+
+/// Panel sizes and visibility, persisted as JSON in the data directory so
+/// the window comes back the way it was left.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PanelLayout {
+    pub show_browser: bool,
+    pub show_info_panel: bool,
+    pub browser_width: f32,
+    pub browser_dock: Dock,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl Default for PanelLayout {
+    fn default() -> Self {
+        Self {
+            show_browser: true,
+            show_info_panel: true,
+            browser_width: BROWSER_DEFAULT_WIDTH,
+            browser_dock: Dock::Left,
+            path: None,
+        }
+    }
+}
+
+impl PanelLayout {
+    /// Loads the layout from disk, falling back to defaults if missing or invalid.
+    pub fn load() -> Self {
+        Self::load_from(data_dir().join(LAYOUT_FILE_NAME))
+    }
+
+    /// Sets the browser width, kept within the allowed range.
+    pub fn set_browser_width(&mut self, width: f32) {
+        self.browser_width = if width.is_finite() {
+            width.clamp(BROWSER_MIN_WIDTH, BROWSER_MAX_WIDTH)
+        } else {
+            BROWSER_DEFAULT_WIDTH
+        };
+    }
+}
+
+impl JsonFile for PanelLayout {
+    const NAME: &'static str = "panel layout";
+
+    fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    fn set_path(&mut self, path: PathBuf) {
+        self.path = Some(path);
+    }
+
+    fn sanitize(&mut self) {
+        // A hand-edited file shouldn't be able to hide the browser off-screen
+        self.set_browser_width(self.browser_width);
+    }
+}
@@ -43,7 +43,7 @@ macro_rules! warn {
 macro_rules! debug {
     ($($expr:expr),*) => {
 {
-    use crate::config::DEBUG;
+    use $crate::config::DEBUG;
         if DEBUG {
             use colored::Colorize;
 
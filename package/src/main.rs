@@ -1,21 +1,41 @@
 use crate::config::APP_NAME;
+use crate::editor::filebrowser;
+use crate::editor::imageviewer::{ResetZoom, ToggleFitToWindow, ZoomIn, ZoomOut};
 use crate::editor::texteditor::{
-    Copy, Cut, NewFile, OpenFile, Paste, Redo, SaveFile, SaveFileAs, TextEditor, Undo,
+    Copy, Cut, NewFile, NextHunk, OpenFile, Paste, PreviewHunk, PreviousHunk, Redo, RevertHunk,
+    SaveFile, SaveFileAs, TextEditor, Undo,
 };
+use crate::history::SearchHistory;
+use crate::icons::IconTheme;
+use crate::recent::RecentWorkspaces;
+use crate::roots::WorkspaceRoots;
+use crate::settings::Settings;
 use crate::state::appstate::AppState;
+use crate::ui::search_results::{NextResult, PreviousResult};
+use crate::ui::text_input;
 use crate::ui::workspace::{
-    CloseTab, MainScreen, OpenSettings, Quit, ToggleBrowser, ToggleFileSwitcher, ToggleInfoPanel,
+    AddFolderToWorkspace, CloseTab, FlushCache, FocusBrowser, GoToLine, GoToSymbol, MainScreen, OpenFolder, OpenPath,
+    OpenSettings, OpenWorkspaceFile, Quit, RevealActiveFile, ShowCacheStats, ShowSourceControl, ToggleBrowser, ToggleCommandPalette, ToggleFileSwitcher, ToggleInfoPanel, ToggleProjectReplace, ToggleReplace,
 };
-use crate::utils::{bind_editor_action, bind_global_action};
+use crate::utils::{bind_editor_action, bind_global_action, parse_launch_path};
 use gpui::{
     App, Application, Bounds, Menu, MenuItem, WindowBounds, WindowOptions, prelude::*, px, size, KeyBinding,
 };
 use std::env;
+use std::path::PathBuf;
 
 pub mod config;
 pub mod editor;
+pub mod history;
+pub mod icons;
+pub mod layout;
 pub mod log;
+pub mod persist;
 pub mod pools;
+pub mod recent;
+pub mod recovery;
+pub mod roots;
+pub mod settings;
 pub mod state;
 pub mod structs;
 pub mod tests;
@@ -23,8 +43,28 @@ pub mod ui;
 pub mod utils;
 pub mod workspace;
 fn main() {
-    let _env: Vec<String> = env::args().collect();
-    Application::new().run(|cx: &mut App| {
+    // `byok-1 [folder | workspace file | file[:line[:col]]]`
+    let launch = env::args().nth(1).map(|arg| parse_launch_path(&arg));
+    let current_dir = || WorkspaceRoots::folder(PathBuf::from("."));
+    let (workspace, open_file) = match launch {
+        Some((path, _)) if path.is_dir() || WorkspaceRoots::is_workspace_file(&path) => {
+            match WorkspaceRoots::open(&path) {
+                Ok(workspace) => (workspace, None),
+                Err(e) => {
+                    eprintln!("{:#}", e);
+                    (current_dir(), None)
+                }
+            }
+        }
+        Some((path, position)) => (current_dir(), Some((path, position))),
+        None => (current_dir(), None),
+    };
+
+    Application::new().run(move |cx: &mut App| {
+        cx.set_global(Settings::load());
+        cx.set_global(SearchHistory::load());
+        cx.set_global(RecentWorkspaces::load());
+        cx.set_global(IconTheme::load());
         let bounds = Bounds::centered(None, size(px(1000.), px(800.0)), cx);
 
         let menu = Menu {
@@ -35,8 +75,13 @@ fn main() {
                     items: vec![
                         MenuItem::action("New", NewFile),
                         MenuItem::action("Open...", OpenFile),
+                        MenuItem::action("Open Folder...", OpenFolder),
+                        MenuItem::action("Open Workspace...", OpenWorkspaceFile),
+                        MenuItem::action("Add Folder to Workspace...", AddFolderToWorkspace),
+                        MenuItem::action("Go to File...", ToggleFileSwitcher),
                         MenuItem::action("Save", SaveFile),
                         MenuItem::action("Save As...", SaveFileAs),
+                        MenuItem::action("Close Tab", CloseTab),
                         MenuItem::Separator,
                         MenuItem::action("Quit", Quit),
                     ],
@@ -50,6 +95,14 @@ fn main() {
                         MenuItem::action("Cut", Cut),
                         MenuItem::action("Copy", Copy),
                         MenuItem::action("Paste", Paste),
+                        MenuItem::Separator,
+                        MenuItem::action("Replace", ToggleReplace),
+                        MenuItem::action("Replace in Project", ToggleProjectReplace),
+                        MenuItem::action("Next Search Result", NextResult),
+                        MenuItem::action("Previous Search Result", PreviousResult),
+                        MenuItem::Separator,
+                        MenuItem::action("Go to Line...", GoToLine),
+                        MenuItem::action("Go to Symbol...", GoToSymbol),
                     ],
                 }),
                 MenuItem::Submenu(Menu {
@@ -57,6 +110,11 @@ fn main() {
                     items: vec![
                         MenuItem::action("Toggle Browser", ToggleBrowser),
                         MenuItem::action("Toggle Info Panel", ToggleInfoPanel),
+                        MenuItem::action("Command Palette", ToggleCommandPalette),
+                        MenuItem::action("Source Control", ShowSourceControl),
+                        MenuItem::Separator,
+                        MenuItem::action("File Cache Stats", ShowCacheStats),
+                        MenuItem::action("Flush File Cache", FlushCache),
                     ],
                 }),
             ],
@@ -72,10 +130,22 @@ fn main() {
 
                 // keybindings
                 bind_global_action(cx, "ctrl-b", ToggleBrowser);
+                bind_global_action(cx, "ctrl-k ctrl-o", OpenFolder);
+                bind_global_action(cx, "ctrl-shift-e", FocusBrowser);
+                bind_global_action(cx, "ctrl-alt-r", RevealActiveFile);
                 bind_global_action(cx, "ctrl-l", ToggleInfoPanel);
-                bind_global_action(cx, "ctrl-shift-p", OpenSettings);
+                bind_global_action(cx, "ctrl-shift-p", ToggleCommandPalette);
                 bind_global_action(cx, "ctrl-,", OpenSettings);
                 bind_global_action(cx, "ctrl-p", ToggleFileSwitcher);
+                bind_global_action(cx, "ctrl-g", GoToLine);
+                bind_global_action(cx, "ctrl-shift-o", GoToSymbol);
+                bind_global_action(cx, "ctrl-w", CloseTab);
+                bind_global_action(cx, "ctrl-q", Quit);
+                bind_global_action(cx, "ctrl-h", ToggleReplace);
+                bind_global_action(cx, "ctrl-shift-h", ToggleProjectReplace);
+                bind_global_action(cx, "ctrl-shift-g", ShowSourceControl);
+                bind_global_action(cx, "f4", NextResult);
+                bind_global_action(cx, "shift-f4", PreviousResult);
                 cx.bind_keys([
                     KeyBinding::new("ctrl-f", crate::ui::workspace::ToggleSearch { global: false }, None),
                     KeyBinding::new("ctrl-shift-f", crate::ui::workspace::ToggleSearch { global: true }, None),
@@ -90,13 +160,78 @@ fn main() {
                 bind_editor_action(cx, "ctrl-x", Cut);
                 bind_editor_action(cx, "ctrl-c", Copy);
                 bind_editor_action(cx, "ctrl-v", Paste);
+                bind_editor_action(cx, "alt-f5", NextHunk);
+                bind_editor_action(cx, "alt-shift-f5", PreviousHunk);
+                bind_editor_action(cx, "ctrl-k ctrl-d", PreviewHunk);
+                bind_editor_action(cx, "ctrl-alt-z", RevertHunk);
+
+                cx.bind_keys([
+                    KeyBinding::new("ctrl-=", ZoomIn, Some("ImageViewer")),
+                    KeyBinding::new("ctrl-+", ZoomIn, Some("ImageViewer")),
+                    KeyBinding::new("ctrl--", ZoomOut, Some("ImageViewer")),
+                    KeyBinding::new("ctrl-0", ResetZoom, Some("ImageViewer")),
+                    KeyBinding::new("ctrl-9", ToggleFitToWindow, Some("ImageViewer")),
+                ]);
+
+                cx.bind_keys([
+                    // Plain letters are left free for type-to-filter
+                    KeyBinding::new("alt-n", filebrowser::CreateFile, Some("FileBrowser && not_editing")),
+                    KeyBinding::new("alt-shift-n", filebrowser::CreateFolder, Some("FileBrowser && not_editing")),
+                    KeyBinding::new("f2", filebrowser::RenameEntry, Some("FileBrowser && not_editing")),
+                    KeyBinding::new("delete", filebrowser::TrashEntry, Some("FileBrowser && not_editing")),
+                    KeyBinding::new("ctrl-d", filebrowser::DuplicateEntry, Some("FileBrowser && not_editing")),
+                    KeyBinding::new("ctrl-alt-c", filebrowser::CopyPath, Some("FileBrowser && not_editing")),
+                    KeyBinding::new("ctrl-alt-shift-c", filebrowser::CopyRelativePath, Some("FileBrowser && not_editing")),
+                    KeyBinding::new("up", filebrowser::SelectPrevious, Some("FileBrowser && not_editing")),
+                    KeyBinding::new("down", filebrowser::SelectNext, Some("FileBrowser && not_editing")),
+                    KeyBinding::new("home", filebrowser::SelectFirst, Some("FileBrowser && not_editing")),
+                    KeyBinding::new("end", filebrowser::SelectLast, Some("FileBrowser && not_editing")),
+                    KeyBinding::new("left", filebrowser::CollapseEntry, Some("FileBrowser && not_editing")),
+                    KeyBinding::new("right", filebrowser::ExpandEntry, Some("FileBrowser && not_editing")),
+                    KeyBinding::new("enter", filebrowser::OpenEntry, Some("FileBrowser && not_editing")),
+                ]);
+
+                cx.bind_keys([
+                    KeyBinding::new("backspace", text_input::Backspace, Some("TextInput")),
+                    KeyBinding::new("delete", text_input::Delete, Some("TextInput")),
+                    KeyBinding::new("ctrl-backspace", text_input::DeleteWordLeft, Some("TextInput")),
+                    KeyBinding::new("ctrl-delete", text_input::DeleteWordRight, Some("TextInput")),
+                    KeyBinding::new("left", text_input::Left, Some("TextInput")),
+                    KeyBinding::new("right", text_input::Right, Some("TextInput")),
+                    KeyBinding::new("ctrl-left", text_input::WordLeft, Some("TextInput")),
+                    KeyBinding::new("ctrl-right", text_input::WordRight, Some("TextInput")),
+                    KeyBinding::new("shift-left", text_input::SelectLeft, Some("TextInput")),
+                    KeyBinding::new("shift-right", text_input::SelectRight, Some("TextInput")),
+                    KeyBinding::new("ctrl-shift-left", text_input::SelectWordLeft, Some("TextInput")),
+                    KeyBinding::new("ctrl-shift-right", text_input::SelectWordRight, Some("TextInput")),
+                    KeyBinding::new("ctrl-a", text_input::SelectAll, Some("TextInput")),
+                    KeyBinding::new("home", text_input::Home, Some("TextInput")),
+                    KeyBinding::new("end", text_input::End, Some("TextInput")),
+                    KeyBinding::new("shift-home", text_input::SelectToHome, Some("TextInput")),
+                    KeyBinding::new("shift-end", text_input::SelectToEnd, Some("TextInput")),
+                    KeyBinding::new("ctrl-v", text_input::Paste, Some("TextInput")),
+                    KeyBinding::new("ctrl-x", text_input::Cut, Some("TextInput")),
+                    KeyBinding::new("ctrl-c", text_input::Copy, Some("TextInput")),
+                ]);
 
                 let app_state = cx.new(|_cx| AppState::new());
                 let editor = cx.new(|cx| TextEditor::new(cx, app_state.clone()));
                 let editor_focus = editor.read(cx).focus_handle.clone();
                 window.focus(&editor_focus);
 
-                let main_screen = cx.new(|cx| MainScreen::new(editor, app_state, cx));
+                let main_screen =
+                    cx.new(|cx| MainScreen::new(editor, app_state, workspace, window, cx));
+                if let Some((path, position)) = open_file {
+                    let action = match position {
+                        Some((line, column)) => OpenPath::at(path, line, column),
+                        None => OpenPath::new(path),
+                    };
+                    main_screen.update(cx, |this, cx| this.open_path(&action, window, cx));
+                }
+                let handle = main_screen.clone();
+                window.on_window_should_close(cx, move |window, cx| {
+                    handle.update(cx, |this, cx| this.handle_window_should_close(window, cx))
+                });
                 main_screen
             },
         )
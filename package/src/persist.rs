@@ -0,0 +1,86 @@
+use gpui::{App, Global};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Reads a JSON file, falling back to the default if it's missing or invalid.
+pub fn read_json<T: DeserializeOwned + Default>(path: &Path) -> T {
+    fs::read(path)
+        .ok()
+        .and_then(|data| serde_json::from_slice(&data).ok())
+        .unwrap_or_default()
+}
+
+/// Writes `data` to a temp file beside `path` and renames it over, so a
+/// crash or a full disk mid-write leaves the old file rather than a
+/// truncated one.
+pub fn write_atomic(path: &Path, data: &[u8]) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    // Per process, in case two windows of the app save at once
+    tmp.push(format!(".{}.tmp", std::process::id()));
+    let tmp = PathBuf::from(tmp);
+    let result = fs::write(&tmp, data).and_then(|()| fs::rename(&tmp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    Ok(result?)
+}
+
+/// Writes `value` as JSON through `write_atomic`.
+pub fn write_json<T: Serialize>(path: &Path, value: &T) -> anyhow::Result<()> {
+    write_atomic(path, &serde_json::to_vec_pretty(value)?)
+}
+
+/// State kept as a JSON file in the data directory, such as the search
+/// history or the panel layout.
+pub trait JsonFile: Serialize + DeserializeOwned + Default {
+    /// What the file holds, for error messages
+    const NAME: &'static str;
+
+    /// Where it's saved. `None` for one that was never loaded, which isn't.
+    fn path(&self) -> Option<&Path>;
+
+    fn set_path(&mut self, path: PathBuf);
+
+    /// Fixes up values a hand-edited file may have got wrong.
+    fn sanitize(&mut self) {}
+
+    fn load_from(path: PathBuf) -> Self {
+        let mut value: Self = read_json(&path);
+        value.sanitize();
+        value.set_path(path);
+        value
+    }
+
+    fn save(&self) -> anyhow::Result<()> {
+        match self.path() {
+            Some(path) => write_json(path, self),
+            None => Ok(()),
+        }
+    }
+
+    /// The app's copy, or an empty one if none was registered.
+    fn get(cx: &App) -> Self
+    where
+        Self: Global + Clone,
+    {
+        cx.try_global::<Self>().cloned().unwrap_or_default()
+    }
+
+    /// Applies `f` to the app's copy and writes it to disk.
+    fn update(cx: &mut App, f: impl FnOnce(&mut Self))
+    where
+        Self: Global + Clone,
+    {
+        let mut value = Self::get(cx);
+        f(&mut value);
+        if let Err(e) = value.save() {
+            eprintln!("Failed to save {}: {}", Self::NAME, e);
+        }
+        cx.set_global(value);
+    }
+}
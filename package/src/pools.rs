@@ -1,8 +1,12 @@
-use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, RwLock};
-use std::thread;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use crate::config::RESOURCE_CACHE_BUDGET_BYTES;
+use crate::structs::lru::LruCache;
+use crate::utils::format_bytes;
 
 /// The main Pools struct containing all resource pools.
 #[derive(Clone, Debug)]
@@ -12,6 +16,12 @@ pub struct Pools {
     pub network: Arc<NetworkPool>,
 }
 
+impl Default for Pools {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Pools {
     pub fn new() -> Self {
         Self {
@@ -28,6 +38,12 @@ pub struct ThreadPool {
     pool: Arc<rayon::ThreadPool>,
 }
 
+impl Default for ThreadPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ThreadPool {
     pub fn new() -> Self {
         let pool = rayon::ThreadPoolBuilder::new()
@@ -57,52 +73,256 @@ impl ThreadPool {
     }
 }
 
+/// Snapshot of the resource cache counters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub entries: usize,
+    pub bytes: usize,
+    pub budget: usize,
+}
+
+impl fmt::Display for CacheStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lookups = self.hits + self.misses;
+        let hit_rate = if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 * 100.0 / lookups as f64
+        };
+        writeln!(
+            f,
+            "{} files, {} of {}",
+            self.entries,
+            format_bytes(self.bytes as u64),
+            format_bytes(self.budget as u64)
+        )?;
+        writeln!(
+            f,
+            "{} hits, {} misses ({:.0}% hit rate)",
+            self.hits, self.misses, hit_rate
+        )?;
+        write!(f, "{} evictions", self.evictions)
+    }
+}
+
+/// Size and modification time of a file when it was cached. A file whose
+/// stamp no longer matches was changed on disk and is read again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Stamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl Stamp {
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        Some(Self {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct CachedFile {
+    content: String,
+    stamp: Stamp,
+}
+
+#[derive(Debug)]
+struct FileCache {
+    lru: LruCache<PathBuf, CachedFile>,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
 /// Manages file resources and potentially memory buffers.
 #[derive(Clone, Debug)]
 pub struct ResourcePool {
-    // Simple in-memory cache for file contents: Path -> Content
-    cache: Arc<RwLock<HashMap<PathBuf, String>>>,
+    // In-memory LRU cache for file contents: Path -> Content, bounded by total bytes
+    cache: Arc<Mutex<FileCache>>,
+}
+
+impl Default for ResourcePool {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ResourcePool {
     pub fn new() -> Self {
+        Self::with_budget(RESOURCE_CACHE_BUDGET_BYTES)
+    }
+
+    /// Creates a pool whose cache holds at most `budget` bytes of file contents.
+    pub fn with_budget(budget: usize) -> Self {
         Self {
-            cache: Arc::new(RwLock::new(HashMap::new())),
+            cache: Arc::new(Mutex::new(FileCache {
+                lru: LruCache::new(budget),
+                hits: 0,
+                misses: 0,
+                evictions: 0,
+            })),
         }
     }
 
-    /// Reads a file, checking the cache first.
+    /// Reads a file, checking the cache first. Cached contents are only used
+    /// while the file's size and modification time are unchanged.
     /// Returns the content as a String.
     pub fn open_file<P: AsRef<Path>>(&self, path: P) -> std::io::Result<String> {
         let path = path.as_ref().to_path_buf();
+        // Taken before reading, so a change made meanwhile shows up next time
+        let stamp = Stamp::of(&path);
         {
-            let cache = self.cache.read().unwrap();
-            if let Some(content) = cache.get(&path) {
-                return Ok(content.clone());
+            let mut cache = self.cache.lock().unwrap();
+            let cached = cache
+                .lru
+                .get(&path)
+                .filter(|cached| Some(cached.stamp) == stamp)
+                .map(|cached| cached.content.clone());
+            if let Some(content) = cached {
+                cache.hits += 1;
+                return Ok(content);
             }
+            cache.misses += 1;
         }
 
         let content = fs::read_to_string(&path)?;
-
-        {
-            let mut cache = self.cache.write().unwrap();
-            cache.insert(path, content.clone());
+        if let Some(stamp) = stamp {
+            self.insert_stamped(path, content.clone(), stamp);
         }
 
         Ok(content)
     }
 
+    /// Reads a file's bytes without populating the cache or touching its
+    /// stats, for bulk operations like search that would otherwise evict
+    /// everything. Content that is already cached, and unchanged on disk, is
+    /// still served from memory.
+    pub fn read_uncached<P: AsRef<Path>>(&self, path: P) -> std::io::Result<Vec<u8>> {
+        let path = path.as_ref();
+        let stamp = Stamp::of(path);
+        if let Some(cached) = self.cache.lock().unwrap().lru.peek(&path.to_path_buf())
+            && Some(cached.stamp) == stamp
+        {
+            return Ok(cached.content.clone().into_bytes());
+        }
+        fs::read(path)
+    }
+
     /// Writes to a file and updates the cache.
     pub fn save_file<P: AsRef<Path>>(&self, path: P, content: String) -> std::io::Result<()> {
         let path = path.as_ref().to_path_buf();
         fs::write(&path, &content)?;
+        self.insert(path, content);
 
-        {
-            let mut cache = self.cache.write().unwrap();
-            cache.insert(path, content);
+        Ok(())
+    }
+
+    /// Writes several files as one operation, returning a result per file in
+    /// the order given. Every file is staged next to its target first; if
+    /// any staging write fails nothing is replaced. Each file is then swapped
+    /// in with a rename, so none is ever left half-written, and if a swap
+    /// fails the files already swapped are put back. A file whose original
+    /// couldn't be put back reports `Ok`, since its new contents are on disk.
+    ///
+    /// Symlinks are written through to the file they point at, and staged
+    /// files get the permissions of the file they replace.
+    pub fn save_files_atomic(&self, files: Vec<(PathBuf, String)>) -> Vec<std::io::Result<()>> {
+        let targets: Vec<PathBuf> = files
+            .iter()
+            .map(|(path, _)| fs::canonicalize(path).unwrap_or_else(|_| path.clone()))
+            .collect();
+
+        let mut staged = Vec::with_capacity(files.len());
+        for (ix, (target, (_, content))) in targets.iter().zip(&files).enumerate() {
+            let tmp = sibling(target, ".byok-tmp");
+            if let Err(e) = stage_file(&tmp, target, content) {
+                fs::remove_file(&tmp).ok();
+                for tmp in staged {
+                    fs::remove_file(tmp).ok();
+                }
+                return failed_with(files.len(), ix, e);
+            }
+            staged.push(tmp);
         }
 
-        Ok(())
+        // Originals are kept aside until every file is in place
+        let mut backups = Vec::with_capacity(files.len());
+        for (ix, (tmp, target)) in staged.iter().zip(&targets).enumerate() {
+            match swap_in(tmp, target) {
+                Ok(backup) => backups.push(backup),
+                Err(e) => {
+                    for tmp in &staged[ix..] {
+                        fs::remove_file(tmp).ok();
+                    }
+                    let mut results = failed_with(files.len(), ix, e);
+                    for (jx, backup) in backups.into_iter().enumerate() {
+                        if let Err(e) = restore(&targets[jx], backup) {
+                            eprintln!("Failed to restore {}: {}", targets[jx].display(), e);
+                            results[jx] = Ok(());
+                            self.insert(files[jx].0.clone(), files[jx].1.clone());
+                        }
+                    }
+                    return results;
+                }
+            }
+        }
+
+        for backup in backups.into_iter().flatten() {
+            fs::remove_file(backup).ok();
+        }
+        let count = files.len();
+        for (path, content) in files {
+            self.insert(path, content);
+        }
+        (0..count).map(|_| Ok(())).collect()
+    }
+
+    /// Caches `content` as what was just written to `path`.
+    fn insert(&self, path: PathBuf, content: String) {
+        match Stamp::of(&path) {
+            Some(stamp) => self.insert_stamped(path, content, stamp),
+            None => self.forget(&path),
+        }
+    }
+
+    fn insert_stamped(&self, path: PathBuf, content: String, stamp: Stamp) {
+        let mut cache = self.cache.lock().unwrap();
+        let size = content.len();
+        cache.evictions += cache.lru.insert(path, CachedFile { content, stamp }, size) as u64;
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let cache = self.cache.lock().unwrap();
+        CacheStats {
+            hits: cache.hits,
+            misses: cache.misses,
+            evictions: cache.evictions,
+            entries: cache.lru.len(),
+            bytes: cache.lru.bytes(),
+            budget: cache.lru.budget(),
+        }
+    }
+
+    /// Drops the cached contents of `path`, or of everything under it for a
+    /// directory, after it was moved or deleted on disk.
+    pub fn forget<P: AsRef<Path>>(&self, path: P) {
+        let path = path.as_ref();
+        self.cache
+            .lock()
+            .unwrap()
+            .lru
+            .remove_where(|p| p.starts_with(path));
+    }
+
+    /// Drops every cached file. Counters are kept.
+    pub fn flush(&self) {
+        self.cache.lock().unwrap().lru.clear();
     }
 
     /// Wraps fs::read_dir
@@ -111,12 +331,75 @@ impl ResourcePool {
     }
 }
 
+/// `path` with `suffix` appended to its file name.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Writes `content` to `tmp`, with the permissions of `target` if it exists.
+fn stage_file(tmp: &Path, target: &Path, content: &str) -> std::io::Result<()> {
+    fs::write(tmp, content)?;
+    if let Ok(metadata) = fs::metadata(target) {
+        fs::set_permissions(tmp, metadata.permissions())?;
+    }
+    Ok(())
+}
+
+/// Moves `tmp` over `target`, keeping a copy of the original to restore.
+/// The original is hard linked where possible so the swap stays a single
+/// rename.
+fn swap_in(tmp: &Path, target: &Path) -> std::io::Result<Option<PathBuf>> {
+    let backup = if target.exists() {
+        let backup = sibling(target, ".byok-bak");
+        fs::remove_file(&backup).ok();
+        fs::hard_link(target, &backup).or_else(|_| fs::copy(target, &backup).map(|_| ()))?;
+        Some(backup)
+    } else {
+        None
+    };
+    if let Err(e) = fs::rename(tmp, target) {
+        if let Some(backup) = backup {
+            fs::remove_file(backup).ok();
+        }
+        return Err(e);
+    }
+    Ok(backup)
+}
+
+/// Undoes `swap_in`: puts the original back, or removes a file that didn't
+/// exist before.
+fn restore(target: &Path, backup: Option<PathBuf>) -> std::io::Result<()> {
+    match backup {
+        Some(backup) => fs::rename(backup, target),
+        None => fs::remove_file(target),
+    }
+}
+
+/// Results for `count` files that were all left untouched because writing
+/// the one at `failed` ran into `error`.
+fn failed_with(count: usize, failed: usize, error: std::io::Error) -> Vec<std::io::Result<()>> {
+    let message = format!("not written, another file failed: {}", error);
+    let mut results: Vec<std::io::Result<()>> = (0..count)
+        .map(|_| Err(std::io::Error::other(message.clone())))
+        .collect();
+    results[failed] = Err(error);
+    results
+}
+
 /// Manages network connections.
 #[derive(Clone, Debug)]
 pub struct NetworkPool {
     client: reqwest::blocking::Client,
 }
 
+impl Default for NetworkPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl NetworkPool {
     pub fn new() -> Self {
         Self {
@@ -0,0 +1,46 @@
+use gpui::Global;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::config::{RECENT_WORKSPACES_FILE_NAME, RECENT_WORKSPACES_LIMIT};
+use crate::persist::JsonFile;
+use crate::utils::data_dir;
+
+/// Folders opened as the workspace root, persisted as JSON in the data
+/// directory and offered again from the command palette.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecentWorkspaces {
+    /// Most recent first
+    pub paths: Vec<PathBuf>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl Global for RecentWorkspaces {}
+
+impl RecentWorkspaces {
+    /// Loads the list from disk, starting empty if missing or invalid.
+    pub fn load() -> Self {
+        Self::load_from(data_dir().join(RECENT_WORKSPACES_FILE_NAME))
+    }
+
+    /// Moves `root` to the front, dropping the oldest past the limit.
+    pub fn push(&mut self, root: &Path) {
+        self.paths.retain(|p| p != root);
+        self.paths.insert(0, root.to_path_buf());
+        self.paths.truncate(RECENT_WORKSPACES_LIMIT);
+    }
+}
+
+impl JsonFile for RecentWorkspaces {
+    const NAME: &'static str = "recent workspaces";
+
+    fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    fn set_path(&mut self, path: PathBuf) {
+        self.path = Some(path);
+    }
+}
@@ -0,0 +1,111 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::config::RECOVERY_DIR_NAME;
+use crate::utils::data_dir;
+
+/// Snapshot of one unsaved buffer.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RecoveryEntry {
+    /// `None` for untitled buffers
+    pub path: Option<PathBuf>,
+    pub text: String,
+}
+
+/// Journal of unsaved buffers, rewritten periodically so work survives a crash.
+/// It is kept apart from the files themselves and cleared on a clean exit.
+///
+/// Writes usually run on the thread pool while clearing happens on the UI
+/// thread, so every write is queued first and only lands if nothing was
+/// queued or cleared after it. Clones share the queue.
+#[derive(Clone, Debug)]
+pub struct RecoveryJournal {
+    dir: PathBuf,
+    /// Number of the latest queued write or clear. Held while touching the
+    /// files, which also keeps writers from sharing the temp file.
+    latest: Arc<Mutex<u64>>,
+}
+
+impl Default for RecoveryJournal {
+    fn default() -> Self {
+        Self::new(data_dir().join(RECOVERY_DIR_NAME))
+    }
+}
+
+impl RecoveryJournal {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            latest: Arc::new(Mutex::new(0)),
+        }
+    }
+
+    fn journal_path(&self) -> PathBuf {
+        self.dir.join("journal.json")
+    }
+
+    /// Reserves a place for a write, to be made with `write_queued`.
+    /// Anything queued or cleared later supersedes it.
+    pub fn queue(&self) -> u64 {
+        let mut latest = self.latest.lock().unwrap();
+        *latest += 1;
+        *latest
+    }
+
+    /// Replaces the journal with `entries`, unless a newer write or a
+    /// clear came after `ticket`. Returns whether it was written.
+    pub fn write_queued(&self, ticket: u64, entries: &[RecoveryEntry]) -> anyhow::Result<bool> {
+        let latest = self.latest.lock().unwrap();
+        if *latest != ticket {
+            return Ok(false);
+        }
+        if entries.is_empty() {
+            self.remove_journal()?;
+        } else {
+            self.write_journal(entries)?;
+        }
+        drop(latest);
+        Ok(true)
+    }
+
+    /// Replaces the journal with `entries`. An empty list removes it.
+    pub fn write(&self, entries: &[RecoveryEntry]) -> anyhow::Result<()> {
+        let ticket = self.queue();
+        self.write_queued(ticket, entries).map(|_| ())
+    }
+
+    fn write_journal(&self, entries: &[RecoveryEntry]) -> anyhow::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+
+        // Write to a temp file first so a crash mid-write can't corrupt the old journal
+        let tmp = self.dir.join("journal.json.tmp");
+        fs::write(&tmp, serde_json::to_vec(entries)?)?;
+        fs::rename(tmp, self.journal_path())?;
+        Ok(())
+    }
+
+    pub fn load(&self) -> Vec<RecoveryEntry> {
+        fs::read(self.journal_path())
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default()
+    }
+
+    /// Removes the journal, waiting for a write in progress and dropping
+    /// the ones still queued.
+    pub fn clear(&self) -> anyhow::Result<()> {
+        let mut latest = self.latest.lock().unwrap();
+        *latest += 1;
+        self.remove_journal()
+    }
+
+    fn remove_journal(&self) -> anyhow::Result<()> {
+        match fs::remove_file(self.journal_path()) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
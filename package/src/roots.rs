@@ -0,0 +1,208 @@
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::WORKSPACE_FILE_EXTENSION;
+use crate::persist::write_json;
+use crate::utils::canonical_path;
+
+/// On-disk form of a workspace file. Folders inside the file's own folder
+/// are stored relative to it so the file can be checked in.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct WorkspaceFile {
+    folders: Vec<PathBuf>,
+}
+
+/// The folders open in the window, in display order, and the workspace
+/// file they were loaded from or saved to, if any. The first folder is the
+/// primary one: new files and the search index live there.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorkspaceRoots {
+    pub folders: Vec<PathBuf>,
+    pub file: Option<PathBuf>,
+}
+
+impl WorkspaceRoots {
+    pub fn folder(path: PathBuf) -> Self {
+        Self {
+            folders: vec![path],
+            file: None,
+        }
+    }
+
+    pub fn is_workspace_file(path: &Path) -> bool {
+        path.extension()
+            .is_some_and(|ext| ext == WORKSPACE_FILE_EXTENSION)
+    }
+
+    /// Opens a folder, or the folders listed in a workspace file.
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let path = path
+            .canonicalize()
+            .with_context(|| format!("Can't open {}", path.display()))?;
+        if path.is_dir() {
+            return Ok(Self::folder(path));
+        }
+        if !Self::is_workspace_file(&path) {
+            anyhow::bail!("{} is not a folder or workspace file", path.display());
+        }
+
+        let text = fs::read_to_string(&path)?;
+        let file: WorkspaceFile = serde_json::from_str(&text)
+            .with_context(|| format!("Invalid workspace file {}", path.display()))?;
+        let base = path.parent().unwrap_or(Path::new("."));
+        let mut folders: Vec<PathBuf> = Vec::new();
+        for folder in file.folders {
+            match base.join(&folder).canonicalize() {
+                Ok(folder) if folder.is_dir() && !folders.contains(&folder) => folders.push(folder),
+                Ok(_) => {}
+                // One missing checkout shouldn't keep the rest from opening
+                Err(e) => eprintln!("Skipping workspace folder {}: {}", folder.display(), e),
+            }
+        }
+        if folders.is_empty() {
+            anyhow::bail!("{} has no folders that exist", path.display());
+        }
+        Ok(Self {
+            folders,
+            file: Some(path),
+        })
+    }
+
+    /// Writes the folders to the workspace file, if there is one.
+    pub fn save(&self) -> anyhow::Result<()> {
+        let Some(path) = &self.file else {
+            return Ok(());
+        };
+        let base = path
+            .parent()
+            .and_then(|base| base.canonicalize().ok())
+            .unwrap_or_default();
+        let folders = self
+            .folders
+            .iter()
+            .map(|folder| {
+                let absolute = folder.canonicalize().unwrap_or_else(|_| folder.clone());
+                match absolute.strip_prefix(&base) {
+                    Ok(relative) if relative.as_os_str().is_empty() => PathBuf::from("."),
+                    Ok(relative) => relative.to_path_buf(),
+                    Err(_) => absolute,
+                }
+            })
+            .collect();
+        // Hand-edited files are precious; never leave one half written
+        write_json(path, &WorkspaceFile { folders })
+    }
+
+    /// Where new files and the search index go.
+    pub fn primary(&self) -> &Path {
+        &self.folders[0]
+    }
+
+    /// What to list under recent workspaces: the workspace file, or the folder.
+    pub fn recent_entry(&self) -> PathBuf {
+        match &self.file {
+            Some(file) => file.clone(),
+            None => {
+                let folder = self.primary();
+                folder
+                    .canonicalize()
+                    .unwrap_or_else(|_| folder.to_path_buf())
+            }
+        }
+    }
+
+    /// Adds a folder after the others. Returns false if it was already open.
+    pub fn add(&mut self, folder: PathBuf) -> bool {
+        if self.folders.contains(&folder) {
+            return false;
+        }
+        self.folders.push(folder);
+        true
+    }
+
+    /// Removes a folder, keeping at least one. Returns false if nothing changed.
+    pub fn remove(&mut self, folder: &Path) -> bool {
+        if self.folders.len() < 2 || !self.folders.iter().any(|f| f == folder) {
+            return false;
+        }
+        self.folders.retain(|f| f != folder);
+        true
+    }
+
+    /// The folder `path` is in, preferring the deepest one when folders nest.
+    pub fn root_of(&self, path: &Path) -> Option<&Path> {
+        self.folders
+            .iter()
+            .filter(|folder| path.starts_with(folder))
+            .max_by_key(|folder| folder.components().count())
+            .map(PathBuf::as_path)
+    }
+
+    /// `path` spelled under the folder it is in, the way listings of that
+    /// folder spell it, so a path from the Open dialog compares equal to the
+    /// same file found by search. Paths outside every folder are returned
+    /// as they are.
+    pub fn normalize(&self, path: &Path) -> PathBuf {
+        let canonical = canonical_path(path);
+        self.folders
+            .iter()
+            .filter_map(|folder| {
+                let relative = canonical.strip_prefix(canonical_path(folder)).ok()?;
+                Some((folder, relative))
+            })
+            .max_by_key(|(folder, _)| folder.components().count())
+            .map(|(folder, relative)| folder.join(relative))
+            .unwrap_or_else(|| path.to_path_buf())
+    }
+
+    /// The folders a project-wide walk goes through, each with the open
+    /// folders nested in it to leave out. Every file is then listed once,
+    /// under the deepest folder holding it as `root_of` has it, and a folder
+    /// open twice under different spellings is walked once.
+    pub fn walk_roots(&self) -> Vec<(PathBuf, Vec<PathBuf>)> {
+        let canonical: Vec<PathBuf> = self.folders.iter().map(|f| canonical_path(f)).collect();
+        self.folders
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !canonical[..*i].contains(&canonical[*i]))
+            .map(|(i, folder)| {
+                // Spelled under `folder`, the way the walk will come across them
+                let nested = canonical
+                    .iter()
+                    .filter_map(|other| {
+                        let relative = other.strip_prefix(&canonical[i]).ok()?;
+                        (!relative.as_os_str().is_empty()).then(|| folder.join(relative))
+                    })
+                    .collect();
+                (folder.clone(), nested)
+            })
+            .collect()
+    }
+
+    /// `path` relative to its folder, prefixed with the folder's name when
+    /// several are open so results from different folders can be told apart.
+    pub fn display_path(&self, path: &Path) -> String {
+        let Some(root) = self.root_of(path) else {
+            return path.to_string_lossy().to_string();
+        };
+        let relative = path.strip_prefix(root).unwrap_or(path).to_string_lossy();
+        if self.folders.len() > 1 {
+            format!("{}/{}", root_name(root), relative)
+        } else {
+            relative.to_string()
+        }
+    }
+}
+
+/// Name shown for a workspace folder: its last component, resolving `.`.
+pub fn root_name(root: &Path) -> String {
+    root.canonicalize()
+        .ok()
+        .as_deref()
+        .unwrap_or(root)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| root.to_string_lossy().to_string())
+}
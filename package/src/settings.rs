@@ -0,0 +1,79 @@
+use gpui::{App, Global};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::config::SETTINGS_FILE_NAME;
+use crate::persist::{read_json, write_json};
+use crate::utils::data_dir;
+
+/// When dirty buffers with a file path get written back without an explicit save.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutosaveMode {
+    #[default]
+    Off,
+    /// Save when the editor loses focus or the window is deactivated
+    OnFocusChange,
+    /// Save once typing has paused for `autosave_delay_ms`
+    AfterDelay,
+}
+
+impl AutosaveMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            AutosaveMode::Off => "Off",
+            AutosaveMode::OnFocusChange => "On focus change",
+            AutosaveMode::AfterDelay => "After delay",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            AutosaveMode::Off => AutosaveMode::OnFocusChange,
+            AutosaveMode::OnFocusChange => AutosaveMode::AfterDelay,
+            AutosaveMode::AfterDelay => AutosaveMode::Off,
+        }
+    }
+}
+
+/// User settings persisted as JSON in the data directory.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub autosave: AutosaveMode,
+    pub autosave_delay_ms: u64,
+    /// Keep an on-disk trigram index of the project to speed up project search
+    pub search_index: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            autosave: AutosaveMode::Off,
+            autosave_delay_ms: 1000,
+            search_index: false,
+        }
+    }
+}
+
+impl Global for Settings {}
+
+impl Settings {
+    fn path() -> PathBuf {
+        data_dir().join(SETTINGS_FILE_NAME)
+    }
+
+    /// Loads settings from disk, falling back to defaults if missing or invalid.
+    pub fn load() -> Self {
+        read_json(&Self::path())
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        write_json(&Self::path(), self)
+    }
+
+    /// The current settings, or defaults if none were registered with the app.
+    pub fn get(cx: &App) -> Settings {
+        cx.try_global::<Settings>().cloned().unwrap_or_default()
+    }
+}
@@ -5,18 +5,32 @@ use std::sync::Arc;
 pub struct AppState {
     pub opened_file: Option<String>,
     pub text: String,
-    pub search_results: Vec<crate::editor::search::SearchResult>,
     pub pools: Arc<Pools>,
 }
 
+impl Default for AppState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl AppState {
     pub fn new() -> Self {
         AppState {
             opened_file: None,
             // later gets changed when file opens , but if say file doesnot open then default state
             text: "Could not open file".to_string(),
-            search_results: Vec::new(),
             pools: Arc::new(Pools::new()),
         }
     }
+
+    /// State for an additional buffer that shares the given pools, so every
+    /// editor draws from the same thread pool and file cache.
+    pub fn with_pools(pools: Arc<Pools>) -> Self {
+        AppState {
+            opened_file: None,
+            text: "Could not open file".to_string(),
+            pools,
+        }
+    }
 }
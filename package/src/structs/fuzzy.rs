@@ -0,0 +1,113 @@
+/// Where and how well a query matched a candidate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    /// Char indices of the matched characters in the candidate, ascending
+    pub positions: Vec<usize>,
+}
+
+const SCORE_MATCH: i64 = 16;
+const BONUS_CONSECUTIVE: i64 = 4;
+const BONUS_AFTER_SEPARATOR: i64 = 10;
+const BONUS_WORD_START: i64 = 8;
+const BONUS_EXACT_CASE: i64 = 1;
+const BONUS_IN_FILE_NAME: i64 = 24;
+const PENALTY_GAP_START: i64 = 3;
+const PENALTY_GAP: i64 = 1;
+
+/// Matches `query` as a case-insensitive subsequence of `candidate`.
+///
+/// Among the possible alignments the tightest window is kept, preferring
+/// one inside the last path component. Consecutive characters and ones at
+/// word or path boundaries score higher; gaps and long candidates score lower.
+pub fn fuzzy_match(query: &str, candidate: &str) -> Option<FuzzyMatch> {
+    let query: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).collect();
+    let chars: Vec<char> = candidate.chars().collect();
+    if query.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: Vec::new(),
+        });
+    }
+
+    let file_name_start = chars
+        .iter()
+        .rposition(|&c| c == '/' || c == '\\')
+        .map_or(0, |i| i + 1);
+
+    // Prefer a match entirely within the file name, then anywhere
+    [file_name_start, 0]
+        .into_iter()
+        .filter_map(|from| align(&query, &chars, from))
+        .map(|positions| FuzzyMatch {
+            score: score(&query, &chars, &positions, file_name_start),
+            positions,
+        })
+        .max_by_key(|m| m.score)
+}
+
+fn eq_ignore_case(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+/// Finds the earliest end of a match starting at `from`, then walks back to
+/// the latest start for that end, giving the shortest window.
+fn align(query: &[char], chars: &[char], from: usize) -> Option<Vec<usize>> {
+    let mut qi = 0;
+    let mut end = None;
+    for (i, &c) in chars.iter().enumerate().skip(from) {
+        if eq_ignore_case(c, query[qi]) {
+            qi += 1;
+            if qi == query.len() {
+                end = Some(i);
+                break;
+            }
+        }
+    }
+    let end = end?;
+
+    let mut positions = Vec::with_capacity(query.len());
+    let mut qi = query.len();
+    for i in (from..=end).rev() {
+        if qi > 0 && eq_ignore_case(chars[i], query[qi - 1]) {
+            qi -= 1;
+            positions.push(i);
+        }
+    }
+    positions.reverse();
+    Some(positions)
+}
+
+fn score(query: &[char], chars: &[char], positions: &[usize], file_name_start: usize) -> i64 {
+    let mut score = 0;
+    for (qi, &pos) in positions.iter().enumerate() {
+        score += SCORE_MATCH;
+        if chars[pos] == query[qi] {
+            score += BONUS_EXACT_CASE;
+        }
+
+        let prev = pos.checked_sub(1).map(|i| chars[i]);
+        score += match prev {
+            None | Some('/' | '\\') => BONUS_AFTER_SEPARATOR,
+            Some('_' | '-' | '.' | ' ') => BONUS_WORD_START,
+            Some(p) if p.is_lowercase() && chars[pos].is_uppercase() => BONUS_WORD_START,
+            _ => 0,
+        };
+
+        if qi > 0 {
+            let gap = pos - positions[qi - 1] - 1;
+            if gap == 0 {
+                score += BONUS_CONSECUTIVE;
+            } else {
+                score -= PENALTY_GAP_START + PENALTY_GAP * gap.min(32) as i64;
+            }
+        }
+    }
+
+    if positions.first().is_some_and(|&p| p >= file_name_start) {
+        score += BONUS_IN_FILE_NAME;
+    }
+    // Longer names and deeper paths are less likely what was meant
+    let name_len = chars.len() - file_name_start;
+    score - (name_len / 2 + file_name_start / 16) as i64
+}
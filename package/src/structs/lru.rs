@@ -0,0 +1,120 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+#[derive(Debug)]
+struct LruEntry<V> {
+    value: V,
+    size: usize,
+    tick: u64,
+}
+
+/// Least-recently-used cache bounded by the total size of its values
+/// rather than by entry count. Sizes are supplied by the caller.
+#[derive(Debug)]
+pub struct LruCache<K, V> {
+    entries: HashMap<K, LruEntry<V>>,
+    // tick -> key, oldest first
+    order: BTreeMap<u64, K>,
+    tick: u64,
+    bytes: usize,
+    budget: usize,
+}
+
+impl<K: Hash + Eq + Clone, V> LruCache<K, V> {
+    pub fn new(budget: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            tick: 0,
+            bytes: 0,
+            budget,
+        }
+    }
+
+    /// Returns the value and marks it as most recently used.
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        self.tick += 1;
+        let entry = self.entries.get_mut(key)?;
+        self.order.remove(&entry.tick);
+        entry.tick = self.tick;
+        self.order.insert(self.tick, key.clone());
+        Some(&entry.value)
+    }
+
+    /// Returns the value without affecting its recency.
+    pub fn peek(&self, key: &K) -> Option<&V> {
+        self.entries.get(key).map(|e| &e.value)
+    }
+
+    /// Inserts a value, evicting least recently used entries until it fits.
+    /// Values larger than the whole budget are not cached at all.
+    /// Returns how many entries were evicted.
+    pub fn insert(&mut self, key: K, value: V, size: usize) -> usize {
+        self.remove(&key);
+        if size > self.budget {
+            return 0;
+        }
+
+        let mut evicted = 0;
+        while self.bytes + size > self.budget {
+            let Some((_, oldest)) = self.order.pop_first() else {
+                break;
+            };
+            if let Some(entry) = self.entries.remove(&oldest) {
+                self.bytes -= entry.size;
+                evicted += 1;
+            }
+        }
+
+        self.tick += 1;
+        self.order.insert(self.tick, key.clone());
+        self.entries.insert(
+            key,
+            LruEntry {
+                value,
+                size,
+                tick: self.tick,
+            },
+        );
+        self.bytes += size;
+        evicted
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let entry = self.entries.remove(key)?;
+        self.order.remove(&entry.tick);
+        self.bytes -= entry.size;
+        Some(entry.value)
+    }
+
+    /// Removes every entry whose key matches `f`.
+    pub fn remove_where(&mut self, f: impl Fn(&K) -> bool) {
+        let keys: Vec<K> = self.entries.keys().filter(|k| f(k)).cloned().collect();
+        for key in keys {
+            self.remove(&key);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+        self.bytes = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Total size of all cached values.
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+}
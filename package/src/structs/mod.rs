@@ -1,2 +1,5 @@
 // CUSTOM Implementations of a few data structures , cuz why not
 pub mod tree;
+pub mod lru;
+pub mod fuzzy;
+pub mod trigram;
@@ -3,6 +3,7 @@ use std::rc::Rc;
 
 #[derive(Debug)]
 struct UndoTreeNode {
+    id: usize,
     state: String,
     parent: Option<Rc<RefCell<UndoTreeNode>>>,
     children: Vec<Rc<RefCell<UndoTreeNode>>>,
@@ -11,26 +12,45 @@ struct UndoTreeNode {
 #[derive(Debug, Clone)]
 pub struct UndoTree {
     current: Rc<RefCell<UndoTreeNode>>,
+    next_id: usize,
 }
 
 impl UndoTree {
     pub fn new(initial_state: &str) -> Self {
         let root = Rc::new(RefCell::new(UndoTreeNode {
+            id: 0,
             state: initial_state.to_string(),
             parent: None,
             children: vec![],
         }));
 
-        UndoTree { current: root }
+        UndoTree {
+            current: root,
+            next_id: 1,
+        }
+    }
+
+    /// Drops the whole history and starts again at `state`. Ids keep
+    /// counting up, so no id from before the reset comes back.
+    pub fn reset(&mut self, state: &str) {
+        self.current = Rc::new(RefCell::new(UndoTreeNode {
+            id: self.next_id,
+            state: state.to_string(),
+            parent: None,
+            children: vec![],
+        }));
+        self.next_id += 1;
     }
 
     /// Saves a new state into the history tree
     pub fn commit(&mut self, new_state: String) {
         let new_node = Rc::new(RefCell::new(UndoTreeNode {
+            id: self.next_id,
             state: new_state,
             parent: Some(Rc::clone(&self.current)),
             children: vec![],
         }));
+        self.next_id += 1;
 
         // Add this new state as a child of where we are currently
         self.current
@@ -66,4 +86,10 @@ impl UndoTree {
     pub fn current_state(&self) -> String {
         self.current.borrow().state.clone()
     }
+
+    /// Identifies the current node; every committed state gets a unique id,
+    /// so two ids are equal only if they point at the same point in history
+    pub fn current_id(&self) -> usize {
+        self.current.borrow().id
+    }
 }
@@ -0,0 +1,253 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Cheap fingerprint of a file's contents, used to tell whether it needs
+/// re-indexing without reading it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FileStamp {
+    pub modified_ns: u128,
+    pub len: u64,
+}
+
+impl FileStamp {
+    pub fn of(path: &Path) -> Option<Self> {
+        let meta = fs::metadata(path).ok()?;
+        let modified_ns = meta
+            .modified()
+            .ok()?
+            .duration_since(UNIX_EPOCH)
+            .ok()?
+            .as_nanos();
+        Some(Self {
+            modified_ns,
+            len: meta.len(),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct IndexedFile {
+    path: PathBuf,
+    stamp: FileStamp,
+    /// Sorted, for dropping the file from the postings again
+    trigrams: Vec<u32>,
+}
+
+/// Start of a saved index, bumped whenever the layout changes
+const MAGIC: &[u8; 8] = b"byoktri1";
+
+/// Every 3-byte window of the lowercased text, packed into a `u32`.
+pub fn trigrams(text: &str) -> HashSet<u32> {
+    text.to_lowercase()
+        .as_bytes()
+        .windows(3)
+        .map(|w| u32::from_le_bytes([w[0], w[1], w[2], 0]))
+        .collect()
+}
+
+/// Maps trigrams to the files containing them. A file can only contain a
+/// literal if it contains every trigram of it, which narrows a search down
+/// to a few candidates that then get verified for real.
+///
+/// Paths are stored once and referred to by a `u32` id everywhere else, so
+/// a posting costs four bytes rather than a path per trigram.
+#[derive(Debug, Default)]
+pub struct TrigramIndex {
+    /// Indexed by file id; `None` for ids freed by `remove`
+    files: Vec<Option<IndexedFile>>,
+    ids: HashMap<PathBuf, u32>,
+    free: Vec<u32>,
+    /// Ids of the files containing each trigram, ascending
+    postings: HashMap<u32, Vec<u32>>,
+}
+
+impl TrigramIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, path: PathBuf, stamp: FileStamp, text: &str) {
+        self.remove(&path);
+        let mut trigrams: Vec<u32> = trigrams(text).into_iter().collect();
+        trigrams.sort_unstable();
+        let id = match self.free.pop() {
+            Some(id) => id,
+            None => {
+                self.files.push(None);
+                (self.files.len() - 1) as u32
+            }
+        };
+        for trigram in &trigrams {
+            let ids = self.postings.entry(*trigram).or_default();
+            // New ids mostly go at the end, reused ones somewhere inside
+            if let Err(at) = ids.binary_search(&id) {
+                ids.insert(at, id);
+            }
+        }
+        self.ids.insert(path.clone(), id);
+        self.files[id as usize] = Some(IndexedFile {
+            path,
+            stamp,
+            trigrams,
+        });
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        let Some(id) = self.ids.remove(path) else {
+            return;
+        };
+        let Some(file) = self.files[id as usize].take() else {
+            return;
+        };
+        for trigram in file.trigrams {
+            if let Some(ids) = self.postings.get_mut(&trigram) {
+                if let Ok(at) = ids.binary_search(&id) {
+                    ids.remove(at);
+                }
+                if ids.is_empty() {
+                    self.postings.remove(&trigram);
+                }
+            }
+        }
+        self.free.push(id);
+    }
+
+    fn file(&self, path: &Path) -> Option<&IndexedFile> {
+        let id = *self.ids.get(path)?;
+        self.files[id as usize].as_ref()
+    }
+
+    pub fn stamp(&self, path: &Path) -> Option<FileStamp> {
+        self.file(path).map(|f| f.stamp)
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        self.ids.contains_key(path)
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &PathBuf> {
+        self.files.iter().flatten().map(|f| &f.path)
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Indexed files that may contain `literal` (case-insensitively), or
+    /// `None` when it is too short to narrow anything down.
+    pub fn candidates(&self, literal: &str) -> Option<HashSet<&PathBuf>> {
+        let trigrams = trigrams(literal);
+        if trigrams.is_empty() {
+            return None;
+        }
+        let mut postings = Vec::with_capacity(trigrams.len());
+        for trigram in &trigrams {
+            match self.postings.get(trigram) {
+                Some(ids) => postings.push(ids),
+                None => return Some(HashSet::new()),
+            }
+        }
+        // Walk the shortest list, looking each id up in the others
+        postings.sort_by_key(|ids| ids.len());
+        let (shortest, rest) = postings.split_first()?;
+        Some(
+            shortest
+                .iter()
+                .filter(|id| rest.iter().all(|ids| ids.binary_search(id).is_ok()))
+                .filter_map(|id| self.files[*id as usize].as_ref().map(|f| &f.path))
+                .collect(),
+        )
+    }
+
+    /// The index in its on-disk form. Only the per-file trigrams are
+    /// written, three bytes each; postings are rebuilt on load. Paths that
+    /// aren't valid UTF-8 are left out and get indexed again next session.
+    pub fn encode(&self) -> Vec<u8> {
+        let files: Vec<(&str, &IndexedFile)> = self
+            .files
+            .iter()
+            .flatten()
+            .filter_map(|f| Some((f.path.to_str()?, f)))
+            .collect();
+        let mut data = Vec::new();
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&(files.len() as u32).to_le_bytes());
+        for (path, file) in files {
+            data.extend_from_slice(&(path.len() as u32).to_le_bytes());
+            data.extend_from_slice(path.as_bytes());
+            data.extend_from_slice(&file.stamp.modified_ns.to_le_bytes());
+            data.extend_from_slice(&file.stamp.len.to_le_bytes());
+            data.extend_from_slice(&(file.trigrams.len() as u32).to_le_bytes());
+            for trigram in &file.trigrams {
+                data.extend_from_slice(&trigram.to_le_bytes()[..3]);
+            }
+        }
+        data
+    }
+
+    /// Reads an index written by `encode`, or `None` if it is from another
+    /// version or damaged.
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let mut reader = Reader(data);
+        if reader.take(MAGIC.len())? != MAGIC {
+            return None;
+        }
+        let count = reader.u32()?;
+        let mut index = Self::new();
+        for _ in 0..count {
+            let len = reader.u32()? as usize;
+            let path = PathBuf::from(std::str::from_utf8(reader.take(len)?).ok()?);
+            let modified_ns = u128::from_le_bytes(reader.take(16)?.try_into().ok()?);
+            let len = u64::from_le_bytes(reader.take(8)?.try_into().ok()?);
+            let trigram_count = reader.u32()? as usize;
+            let trigrams: Vec<u32> = reader
+                .take(trigram_count.checked_mul(3)?)?
+                .chunks_exact(3)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], 0]))
+                .collect();
+
+            // Ids are handed out in order, so every posting list stays sorted
+            let id = index.files.len() as u32;
+            for trigram in &trigrams {
+                index.postings.entry(*trigram).or_default().push(id);
+            }
+            if index.ids.insert(path.clone(), id).is_some() {
+                return None;
+            }
+            index.files.push(Some(IndexedFile {
+                path,
+                stamp: FileStamp { modified_ns, len },
+                trigrams,
+            }));
+        }
+        reader.0.is_empty().then_some(index)
+    }
+
+    pub fn load(path: &Path) -> Option<Self> {
+        Self::decode(&fs::read(path).ok()?)
+    }
+}
+
+/// Reads little-endian values off the front of a byte slice.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.0.len() < len {
+            return None;
+        }
+        let (head, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(head)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+}
@@ -1,9 +1,1383 @@
+/// A fresh folder under the system temp dir for one test, removed when
+/// dropped so a failing assertion doesn't leave it behind.
+#[cfg(test)]
+struct TempDir(std::path::PathBuf);
+
+#[cfg(test)]
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("byok-{}-{}", name, std::process::id()));
+        std::fs::remove_dir_all(&path).ok();
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+}
+
+#[cfg(test)]
+impl std::ops::Deref for TempDir {
+    type Target = std::path::PathBuf;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl AsRef<std::path::Path> for TempDir {
+    fn as_ref(&self) -> &std::path::Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        std::fs::remove_dir_all(&self.0).ok();
+    }
+}
+
 #[test]
 fn test_main() {
     let pools = crate::pools::Pools::new();
-    let _ = pools.threads.spawn(|| {
+    pools.threads.spawn(|| {
         crate::main();
     });
     std::thread::sleep(std::time::Duration::from_secs(1));
     assert_eq!(true, true);
 }
+
+#[test]
+fn test_image_viewer_supported_extensions() {
+    use crate::editor::imageviewer::ImageViewer;
+    use std::path::Path;
+
+    assert!(ImageViewer::is_supported(Path::new("images/byok-first-look.png")));
+    assert!(ImageViewer::is_supported(Path::new("images/arrow_circle.SVG")));
+    assert!(ImageViewer::is_supported(Path::new("photo.jpeg")));
+    assert!(!ImageViewer::is_supported(Path::new("src/main.rs")));
+    assert!(!ImageViewer::is_supported(Path::new("Makefile")));
+}
+
+#[test]
+fn test_undo_tree_ids_track_saved_version() {
+    use crate::structs::tree::UndoTree;
+
+    let mut tree = UndoTree::new("");
+    let saved = tree.current_id();
+    tree.commit("a".to_string());
+    tree.commit("ab".to_string());
+    assert_ne!(tree.current_id(), saved);
+
+    tree.undo();
+    tree.undo();
+    assert_eq!(tree.current_id(), saved);
+    assert_eq!(tree.current_state(), "");
+
+    // A new branch with identical text is still a different version
+    tree.commit(String::new());
+    assert_ne!(tree.current_id(), saved);
+}
+
+#[test]
+fn test_undo_after_load_is_noop() {
+    use crate::structs::tree::UndoTree;
+
+    // Opening a file starts a fresh history at its contents
+    let mut tree = UndoTree::new("");
+    tree.commit("draft".to_string());
+    let before = tree.current_id();
+    tree.reset("fn main() {}\n");
+    let saved = tree.current_id();
+    assert_ne!(saved, before);
+    tree.undo();
+    assert_eq!(tree.current_state(), "fn main() {}\n");
+    assert_eq!(tree.current_id(), saved);
+
+    tree.commit("fn main() {}\n\n".to_string());
+    tree.undo();
+    tree.undo();
+    assert_eq!(tree.current_state(), "fn main() {}\n");
+    assert_eq!(tree.current_id(), saved);
+}
+
+#[test]
+fn test_recovery_journal_roundtrip() {
+    use crate::recovery::{RecoveryEntry, RecoveryJournal};
+    use std::path::PathBuf;
+
+    let dir = TempDir::new("recovery");
+    let journal = RecoveryJournal::new(dir.clone());
+    let entries = vec![
+        RecoveryEntry {
+            path: Some(PathBuf::from("src/main.rs")),
+            text: "fn main() {}".to_string(),
+        },
+        RecoveryEntry {
+            path: None,
+            text: "untitled notes".to_string(),
+        },
+    ];
+
+    journal.write(&entries).unwrap();
+    assert_eq!(journal.load(), entries);
+
+    // Writing an empty set clears the journal
+    journal.write(&[]).unwrap();
+    assert!(journal.load().is_empty());
+
+    // A write queued before a clean exit's clear must not bring it back
+    let ticket = journal.queue();
+    journal.clone().clear().unwrap();
+    assert!(!journal.write_queued(ticket, &entries).unwrap());
+    assert!(journal.load().is_empty());
+
+    // Only the newest of several queued writes lands
+    let older = journal.queue();
+    let newer = journal.queue();
+    assert!(journal.write_queued(newer, &entries[..1]).unwrap());
+    assert!(!journal.write_queued(older, &entries).unwrap());
+    assert_eq!(journal.load(), entries[..1]);
+}
+
+#[test]
+fn test_language_detection() {
+    use crate::editor::language::Language;
+    use std::path::Path;
+
+    assert_eq!(Language::from_path(Path::new("src/main.rs")), Language::Rust);
+    assert_eq!(Language::from_path(Path::new("web/vite.config.ts")), Language::TypeScript);
+    assert_eq!(Language::from_path(Path::new("README.MD")), Language::Markdown);
+    assert_eq!(Language::from_path(Path::new("notes")), Language::PlainText);
+}
+
+#[test]
+fn test_lru_cache_evicts_least_recently_used() {
+    use crate::structs::lru::LruCache;
+
+    let mut cache = LruCache::new(10);
+    cache.insert("a", "aaaa", 4);
+    cache.insert("b", "bbbb", 4);
+    // Touch "a" so "b" becomes the eviction candidate
+    assert_eq!(cache.get(&"a"), Some(&"aaaa"));
+
+    assert_eq!(cache.insert("c", "cccc", 4), 1);
+    assert!(cache.peek(&"b").is_none());
+    assert!(cache.peek(&"a").is_some());
+    assert_eq!(cache.bytes(), 8);
+
+    // Larger than the whole budget: not cached
+    assert_eq!(cache.insert("d", "too big", 11), 0);
+    assert!(cache.peek(&"d").is_none());
+    assert_eq!(cache.len(), 2);
+}
+
+#[test]
+fn test_resource_pool_cache_stats_and_bypass() {
+    use crate::pools::ResourcePool;
+
+    let dir = TempDir::new("cache");
+    let file = dir.join("a.txt");
+    std::fs::write(&file, "hello").unwrap();
+
+    let pool = ResourcePool::with_budget(1024);
+    assert_eq!(pool.read_uncached(&file).unwrap(), b"hello");
+    assert_eq!(pool.stats().entries, 0);
+
+    pool.open_file(&file).unwrap();
+    pool.open_file(&file).unwrap();
+    let stats = pool.stats();
+    assert_eq!((stats.hits, stats.misses, stats.entries, stats.bytes), (1, 1, 1, 5));
+
+    // Cached content is served without counting as a hit
+    assert_eq!(pool.read_uncached(&file).unwrap(), b"hello");
+    assert_eq!(pool.stats().hits, 1);
+
+    // A file changed on disk is read again rather than served stale
+    std::fs::write(&file, "changed on disk").unwrap();
+    assert_eq!(pool.read_uncached(&file).unwrap(), b"changed on disk");
+    assert_eq!(pool.open_file(&file).unwrap(), "changed on disk");
+    let stats = pool.stats();
+    assert_eq!((stats.hits, stats.misses, stats.bytes), (1, 2, 15));
+
+    pool.flush();
+    assert_eq!(pool.stats().bytes, 0);
+}
+
+#[test]
+fn test_search_query_options() {
+    use crate::editor::search::{SearchOptions, SearchQuery};
+
+    let text = "Foo foo food f.o";
+
+    let plain = SearchQuery::new("foo", SearchOptions::default()).unwrap();
+    assert_eq!(plain.find_at(text, 0), Some(0..3));
+
+    let case = SearchOptions { case_sensitive: true, ..Default::default() };
+    assert_eq!(SearchQuery::new("foo", case).unwrap().find_at(text, 0), Some(4..7));
+
+    // Smart case: an uppercase letter in the query turns on case sensitivity
+    let smart = SearchOptions { smart_case: true, ..Default::default() };
+    assert_eq!(SearchQuery::new("foo", smart).unwrap().find_at(text, 1), Some(4..7));
+    assert!(!SearchQuery::new("FOO", smart).unwrap().is_match(text));
+    // Escapes in a regex aren't uppercase letters, but literal text is
+    let smart_regex = SearchOptions { regex: true, ..smart };
+    assert_eq!(SearchQuery::new(r"\Afoo\W", smart_regex).unwrap().find_at(text, 0), Some(0..4));
+    assert!(SearchQuery::new(r"\p{Lu}", smart_regex).unwrap().is_match("x"));
+    assert!(!SearchQuery::new(r"\SFOO", smart_regex).unwrap().is_match(text));
+    assert!(!SearchQuery::new(r"\S", smart).unwrap().is_match("\\s"));
+
+    let word = SearchOptions { whole_word: true, ..Default::default() };
+    assert_eq!(SearchQuery::new("foo", word).unwrap().find_at(text, 5), None);
+
+    // Literal mode escapes metacharacters, regex mode doesn't
+    assert_eq!(SearchQuery::new(".", SearchOptions::default()).unwrap().find_at(text, 0), Some(14..15));
+    let regex = SearchOptions { regex: true, ..Default::default() };
+    assert_eq!(SearchQuery::new("fo+d", regex).unwrap().find_at(text, 0), Some(8..12));
+    assert!(SearchQuery::new("(unclosed", regex).is_err());
+}
+
+#[test]
+fn test_search_query_replace() {
+    use crate::editor::search::{SearchOptions, SearchQuery};
+
+    let regex = SearchOptions { regex: true, ..Default::default() };
+    let query = SearchQuery::new(r"(\w+)@(\w+)", regex).unwrap();
+    assert_eq!(query.replace_all("a@b c@d", "$2@$1"), ("b@a d@c".to_string(), 2));
+
+    // Literal queries don't expand `$1`
+    let literal = SearchQuery::new("a", SearchOptions::default()).unwrap();
+    assert_eq!(literal.replace_all("ba", "$1").0, "b$1");
+
+    let preserve = SearchOptions { preserve_case: true, ..Default::default() };
+    let query = SearchQuery::new("foo", preserve).unwrap();
+    assert_eq!(query.replace_all("foo Foo FOO", "bar").0, "bar Bar BAR");
+}
+
+#[test]
+fn test_project_replace_edits() {
+    use crate::editor::replace::ProjectReplace;
+    use crate::editor::search::{SearchOptions, SearchQuery, SearchScope};
+    use crate::pools::Pools;
+    use crate::roots::WorkspaceRoots;
+    use crate::utils::canonical_path;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    let dir = TempDir::new("replace");
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    std::fs::write(dir.join("a.txt"), "foo bar\nfoo\n").unwrap();
+    std::fs::write(dir.join("sub/b.txt"), "no match").unwrap();
+    std::fs::write(dir.join("sub/c.txt"), "on disk foo").unwrap();
+
+    // Open buffers win over what's on disk, even when the search spells the path differently
+    let open = HashMap::from([(canonical_path(&dir.join("sub/c.txt")), "unsaved foo foo".to_string())]);
+    let query = SearchQuery::new("foo", SearchOptions::default()).unwrap();
+    let scope = SearchScope::default();
+    let roots = WorkspaceRoots::folder(dir.join("sub").join(".."));
+    let mut plan = ProjectReplace::plan(&query, "baz", &roots, &scope, &open, Arc::new(Pools::new()));
+    assert_eq!(plan.files.len(), 2);
+    assert_eq!(plan.match_count(), 4);
+    assert_eq!((plan.files[0].matches[1].line, plan.files[0].matches[1].line_range.clone()), (2, 0..3));
+
+    // Unchecked matches are left alone
+    plan.files[0].matches[0].included = false;
+    let edits = plan.edits();
+    assert_eq!(edits[0].after, "foo bar\nbaz\n");
+    assert_eq!(edits[1].after, "unsaved baz baz");
+
+    let pools = Pools::new();
+    let files = edits.iter().map(|e| (e.path.clone(), e.after.clone())).collect();
+    let results = pools.resources.save_files_atomic(files);
+    assert!(results.iter().all(|r| r.is_ok()));
+    assert_eq!(std::fs::read_to_string(dir.join("a.txt")).unwrap(), "foo bar\nbaz\n");
+    assert_eq!(edits[0].reversed().after, "foo bar\nfoo\n");
+
+    // A file that can't be swapped in puts back the ones already written
+    let blocked = dir.join("blocked");
+    std::fs::create_dir_all(&blocked).unwrap();
+    let results = pools.resources.save_files_atomic(vec![
+        (dir.join("a.txt"), "rolled back".to_string()),
+        (blocked.clone(), "not a file".to_string()),
+    ]);
+    assert!(results.iter().all(|r| r.is_err()));
+    assert_eq!(std::fs::read_to_string(dir.join("a.txt")).unwrap(), "foo bar\nbaz\n");
+    let leftovers: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap()
+        .flatten()
+        .filter(|e| e.file_name().to_string_lossy().contains(".byok-"))
+        .collect();
+    assert!(leftovers.is_empty());
+
+    // Symlinks are written through and permissions are kept
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let script = dir.join("run.sh");
+        std::fs::write(&script, "echo foo").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let link = dir.join("link.sh");
+        std::os::unix::fs::symlink(&script, &link).unwrap();
+        let results = pools.resources.save_files_atomic(vec![(link.clone(), "echo baz".to_string())]);
+        assert!(results[0].is_ok());
+        assert!(std::fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(std::fs::read_to_string(&script).unwrap(), "echo baz");
+        let mode = std::fs::metadata(&script).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+    }
+}
+
+#[test]
+fn test_search_text_context() {
+    use crate::editor::search::{SearchOptions, SearchQuery, search_text};
+    use std::path::Path;
+
+    let query = SearchQuery::new("needle", SearchOptions::default()).unwrap();
+    let results = search_text(&query, Path::new("a.txt"), "needle\none\ntwo needle\nthree");
+    assert_eq!(results.len(), 2);
+    assert!(results[0].context_before.is_empty());
+    assert_eq!(results[0].context_after, vec!["one"]);
+    assert_eq!((results[1].line, results[1].column.clone()), (3, 4..10));
+    assert_eq!(results[1].context_before, vec!["one"]);
+    assert_eq!(results[1].context_after, vec!["three"]);
+
+    // Patterns can span lines, highlighted to the end of the first one
+    let options = SearchOptions {
+        regex: true,
+        ..SearchOptions::default()
+    };
+    let query = SearchQuery::new(r"one\ntwo", options).unwrap();
+    let results = search_text(&query, Path::new("a.txt"), "needle\none\ntwo needle\nthree");
+    assert_eq!(results.len(), 1);
+    assert_eq!((results[0].line, results[0].column.clone()), (2, 0..3));
+    let query = SearchQuery::new(r"e$", options).unwrap();
+    let results = search_text(&query, Path::new("a.txt"), "one\r\ntwo\r\nthree");
+    let lines: Vec<usize> = results.iter().map(|r| r.line).collect();
+    assert_eq!(lines, vec![1, 3]);
+    assert_eq!(results[0].line_content, "one");
+}
+
+#[test]
+fn test_position_to_offset() {
+    use crate::utils::position_to_offset;
+
+    let text = "ab\nçd\n";
+    assert_eq!(position_to_offset(text, 1, 1), 0);
+    assert_eq!(position_to_offset(text, 2, 2), 5);
+    // Past the end of a line or the text clamps
+    assert_eq!(position_to_offset(text, 1, 99), 2);
+    assert_eq!(position_to_offset(text, 9, 1), text.len());
+}
+
+#[test]
+fn test_search_respects_ignore_files_scope_and_binaries() {
+    use crate::editor::search::{
+        CancelFlag, SearchOptions, SearchQuery, SearchScope, perform_search, project_files_until,
+    };
+    use crate::pools::Pools;
+    use std::sync::Arc;
+
+    let dir = TempDir::new("search");
+    std::fs::create_dir_all(dir.join("target")).unwrap();
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(dir.join(".gitignore"), "target/\n").unwrap();
+    std::fs::write(dir.join(".ignore"), "*.log\n").unwrap();
+    std::fs::write(dir.join("target/out.rs"), "needle").unwrap();
+    std::fs::write(dir.join("debug.log"), "needle").unwrap();
+    std::fs::write(dir.join("data.bin"), b"needle\0\x01").unwrap();
+    std::fs::write(dir.join("src/main.rs"), "needle").unwrap();
+    std::fs::write(dir.join("src/notes.txt"), "needle").unwrap();
+
+    let query = SearchQuery::new("needle", SearchOptions::default()).unwrap();
+    let search = |filter: &str| {
+        let results = perform_search(query.clone(), dir.clone(), SearchScope::parse(filter), Arc::new(Pools::new()));
+        let mut names: Vec<String> = results
+            .iter()
+            .map(|r| r.path.strip_prefix(&dir).unwrap().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    };
+
+    assert_eq!(search(""), vec!["src/main.rs", "src/notes.txt"]);
+    assert_eq!(search("*.rs"), vec!["src/main.rs"]);
+    assert_eq!(search("!*.txt"), vec!["src/main.rs"]);
+
+    // A cancelled walk stops straight away
+    let cancel = CancelFlag::default();
+    cancel.cancel();
+    let files = project_files_until(&dir, &SearchScope::default(), &cancel).unwrap();
+    assert!(files.is_empty());
+}
+
+#[test]
+fn test_trigram_index_candidates_and_persistence() {
+    use crate::structs::trigram::{FileStamp, TrigramIndex};
+    use std::path::{Path, PathBuf};
+
+    let stamp = FileStamp {
+        modified_ns: 1,
+        len: 1,
+    };
+    let mut index = TrigramIndex::new();
+    index.insert(PathBuf::from("a.rs"), stamp, "fn Needle() {}");
+    index.insert(PathBuf::from("b.rs"), stamp, "haystack");
+
+    let candidates = |index: &TrigramIndex, literal: &str| {
+        let mut paths: Vec<PathBuf> = index.candidates(literal).unwrap().into_iter().cloned().collect();
+        paths.sort();
+        paths
+    };
+    assert_eq!(candidates(&index, "needle"), vec![PathBuf::from("a.rs")]);
+    assert_eq!(candidates(&index, "stack"), vec![PathBuf::from("b.rs")]);
+    assert!(candidates(&index, "missing").is_empty());
+    // Too short to narrow anything down
+    assert!(index.candidates("ne").is_none());
+
+    let tmp = TempDir::new("index-data");
+    let file = tmp.join("index.idx");
+    let data = index.encode();
+    std::fs::write(&file, &data).unwrap();
+    let mut loaded = TrigramIndex::load(&file).unwrap();
+    assert_eq!(loaded.stamp(Path::new("a.rs")), Some(stamp));
+    assert_eq!(candidates(&loaded, "needle"), vec![PathBuf::from("a.rs")]);
+    // Cut short or from another version
+    assert!(TrigramIndex::decode(&data[..data.len() - 1]).is_none());
+    assert!(TrigramIndex::decode(b"{}").is_none());
+
+    loaded.remove(Path::new("a.rs"));
+    assert!(candidates(&loaded, "needle").is_empty());
+    assert_eq!(loaded.len(), 1);
+
+    // The freed id is reused without mixing up the other file's postings
+    loaded.insert(PathBuf::from("c.rs"), stamp, "needle in a haystack");
+    assert_eq!(candidates(&loaded, "needle"), vec![PathBuf::from("c.rs")]);
+    assert_eq!(
+        candidates(&loaded, "haystack"),
+        vec![PathBuf::from("b.rs"), PathBuf::from("c.rs")]
+    );
+    loaded.insert(PathBuf::from("b.rs"), stamp, "rewritten");
+    assert_eq!(candidates(&loaded, "haystack"), vec![PathBuf::from("c.rs")]);
+}
+
+#[test]
+fn test_search_index_narrow_and_update() {
+    use crate::editor::index::SearchIndex;
+    use crate::editor::search::{SearchOptions, SearchQuery, SearchScope, project_files};
+    use crate::pools::Pools;
+    use std::path::PathBuf;
+
+    let dir = TempDir::new("search-index");
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    std::fs::write(dir.join("a.rs"), "fn needle() {}").unwrap();
+    std::fs::write(dir.join("b.rs"), "haystack").unwrap();
+    std::fs::write(dir.join("sub/c.rs"), "needle again").unwrap();
+    let tmp = TempDir::new("search-index-data");
+    let file = tmp.join("search-index.idx");
+
+    // Spelled differently from the events, like a root opened as "."
+    let root = dir.join("sub").join("..");
+    let index = SearchIndex::with_file(root.clone(), file.clone());
+    index.refresh(&Pools::new());
+    assert_eq!(index.progress().indexed, 3);
+
+    let query = SearchQuery::new("needle", SearchOptions::default()).unwrap();
+    let files = project_files(&root, &SearchScope::default()).unwrap();
+    let narrowed = |index: &SearchIndex| {
+        let mut names: Vec<String> = index
+            .narrow(files.clone(), &query)
+            .iter()
+            .map(|p| p.strip_prefix(&root).unwrap().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    };
+    assert_eq!(narrowed(&index), vec!["a.rs", "sub/c.rs"]);
+
+    // A changed file is kept until its event is applied, then judged by its new text
+    std::fs::write(dir.join("a.rs"), "fn haystack_only() {}").unwrap();
+    assert_eq!(narrowed(&index), vec!["a.rs", "sub/c.rs"]);
+    let absolute = dir.canonicalize().unwrap();
+    index.update(&[absolute.join("a.rs"), PathBuf::from("/elsewhere/a.rs")]);
+    assert_eq!(narrowed(&index), vec!["sub/c.rs"]);
+
+    std::fs::remove_file(dir.join("sub/c.rs")).unwrap();
+    index.update(&[absolute.join("sub/c.rs")]);
+    assert_eq!(index.progress().indexed, 2);
+}
+
+#[test]
+fn test_fuzzy_match_ranking() {
+    use crate::structs::fuzzy::fuzzy_match;
+
+    let m = fuzzy_match("wrk", "src/ui/workspace.rs").unwrap();
+    assert_eq!(m.positions, vec![7, 9, 10]);
+    assert!(fuzzy_match("xyz", "src/ui/workspace.rs").is_none());
+
+    // File name matches beat directory matches, tight matches beat scattered ones
+    let score = |q: &str, c: &str| fuzzy_match(q, c).unwrap().score;
+    assert!(score("search", "src/editor/search.rs") > score("search", "src/ui/search_bar.rs"));
+    assert!(score("main", "src/main.rs") > score("main", "src/domain/mod.rs"));
+    assert!(score("sb", "src/ui/search_bar.rs") > score("sb", "src/ui/statusbar.rs"));
+}
+
+#[test]
+fn test_file_finder_parse_position() {
+    use crate::ui::file_finder::parse_position;
+
+    assert_eq!(parse_position("main.rs"), ("main.rs", None));
+    assert_eq!(parse_position("main.rs:12"), ("main.rs", Some((12, 1))));
+    assert_eq!(parse_position("src/main.rs:12:5"), ("src/main.rs", Some((12, 5))));
+    assert_eq!(parse_position("main.rs:12:"), ("main.rs", Some((12, 1))));
+    assert_eq!(parse_position("main.rs:"), ("main.rs", None));
+}
+
+#[test]
+fn test_outline_symbols() {
+    use crate::editor::language::Language;
+    use crate::editor::outline::{SymbolKind, outline};
+
+    let rust = "pub struct Foo {\n    x: u32,\n}\n\n#[derive(Debug)]\nenum Bar {}\n\nimpl Foo {\n    pub(crate) async fn run(&self) {}\n    // fn commented() {}\n}\n\nconst LIMIT: usize = 3;\n";
+    let symbols: Vec<(String, SymbolKind, usize, usize)> = outline(rust, Language::Rust)
+        .into_iter()
+        .map(|s| (s.name, s.kind, s.line, s.depth))
+        .collect();
+    assert_eq!(
+        symbols,
+        vec![
+            ("Foo".to_string(), SymbolKind::Struct, 1, 0),
+            ("Bar".to_string(), SymbolKind::Enum, 6, 0),
+            ("Foo".to_string(), SymbolKind::Impl, 8, 0),
+            ("run".to_string(), SymbolKind::Function, 9, 1),
+            ("LIMIT".to_string(), SymbolKind::Constant, 13, 0),
+        ]
+    );
+    assert_eq!(outline(rust, Language::Rust)[3].column, 25);
+
+    let markdown = "# Title\n\n```sh\n# not a heading\n```\n\n## Usage ##\n";
+    let headings: Vec<(String, usize)> = outline(markdown, Language::Markdown)
+        .into_iter()
+        .map(|s| (s.name, s.depth))
+        .collect();
+    assert_eq!(headings, vec![("Title".to_string(), 0), ("Usage".to_string(), 1)]);
+}
+
+#[test]
+fn test_outline_from_syntax() {
+    use crate::editor::language::Language;
+    use crate::editor::outline::{OutlineSource, SymbolKind, outline, outline_with_source};
+
+    let rust = "/// fn in_doc() {}\nmod inner {\n    pub fn\n    split<T>(\n        x: T,\n    ) {\n        fn helper() {}\n        let s = \"fn in_string() {}\";\n    }\n}\n\nimpl<T> fmt::Display\n    for Wrapper<T> {\n    const A: u8 = 1;\n}\n";
+    let symbols: Vec<(String, SymbolKind, usize, usize, usize)> = outline(rust, Language::Rust)
+        .into_iter()
+        .map(|s| (s.name, s.kind, s.line, s.column, s.depth))
+        .collect();
+    assert_eq!(
+        symbols,
+        vec![
+            ("inner".to_string(), SymbolKind::Module, 2, 5, 0),
+            ("split".to_string(), SymbolKind::Function, 4, 5, 1),
+            ("helper".to_string(), SymbolKind::Function, 7, 12, 2),
+            ("fmt::Display for Wrapper<T>".to_string(), SymbolKind::Impl, 12, 9, 0),
+            ("A".to_string(), SymbolKind::Constant, 14, 11, 1),
+        ]
+    );
+
+    // Buffers that don't parse yet fall back to the line rules
+    let broken = "fn done() {}\n/* fn commented() {} */\nfn typing(\n";
+    let names: Vec<String> = outline(broken, Language::Rust)
+        .into_iter()
+        .map(|s| s.name)
+        .collect();
+    assert_eq!(names, vec!["done", "typing"]);
+    assert_eq!(outline_with_source(broken, Language::Rust).1, OutlineSource::Patterns);
+    assert_eq!(outline_with_source(rust, Language::Rust).1, OutlineSource::Parser);
+
+    // Char literals and raw strings don't open a string that hides the rest
+    let literals = "fn quote() -> char { '\"' }\nfn escaped<'a>(_: &'a str) -> char { '\\'' }\nconst RAW: &str = r#\"fn in_raw() {} \"\"#;\nfn after() {\n";
+    let names: Vec<String> = outline(literals, Language::Rust)
+        .into_iter()
+        .map(|s| s.name)
+        .collect();
+    assert_eq!(names, vec!["quote", "escaped", "RAW", "after"]);
+
+    let python = "def real():\n    \"\"\"\n    def in_docstring():\n    \"\"\"\n    x = 'class Fake:'  # def in_comment():\n\nclass Real:\n    pass\n";
+    let names: Vec<(String, usize)> = outline(python, Language::Python)
+        .into_iter()
+        .map(|s| (s.name, s.line))
+        .collect();
+    assert_eq!(names, vec![("real".to_string(), 1), ("Real".to_string(), 7)]);
+
+    let script = "const s = `\nfunction fake() {}\n`;\n/*\nclass Hidden {}\n*/\nexport function shown() {}\n";
+    let names: Vec<String> = outline(script, Language::TypeScript)
+        .into_iter()
+        .map(|s| s.name)
+        .collect();
+    assert_eq!(names, vec!["shown"]);
+}
+
+#[test]
+fn test_parse_line_column() {
+    use crate::ui::go_to_line::parse_line_column;
+
+    assert_eq!(parse_line_column("42"), Some((42, 1)));
+    assert_eq!(parse_line_column("42:7"), Some((42, 7)));
+    assert_eq!(parse_line_column("42:"), Some((42, 1)));
+    assert_eq!(parse_line_column("0"), None);
+    assert_eq!(parse_line_column(""), None);
+    assert_eq!(parse_line_column("4x"), None);
+}
+
+#[test]
+fn test_search_history_recall_and_saved_searches() {
+    use crate::config::SEARCH_HISTORY_LIMIT;
+    use crate::editor::search::SearchOptions;
+    use crate::history::{SavedSearch, SearchHistory};
+    use crate::persist::JsonFile;
+
+    let tmp = TempDir::new("history-data");
+    let path = tmp.join("history.json");
+    let mut history = SearchHistory::load_from(path.clone());
+    assert!(history.push("foo"));
+    assert!(history.push("bar"));
+    assert!(!history.push("bar"));
+    // Searching again moves a query to the most recent spot
+    assert!(history.push("foo"));
+    assert_eq!(history.queries, vec!["bar", "foo"]);
+
+    for i in 0..SEARCH_HISTORY_LIMIT {
+        history.push(&i.to_string());
+    }
+    assert_eq!(history.queries.len(), SEARCH_HISTORY_LIMIT);
+    assert_eq!(history.queries.first().unwrap(), "0");
+
+    let search = SavedSearch {
+        name: "todos".to_string(),
+        query: "TODO|FIXME".to_string(),
+        global: true,
+        options: SearchOptions {
+            regex: true,
+            ..SearchOptions::default()
+        },
+        filter: "*.rs".to_string(),
+    };
+    history.pin(search.clone());
+    history.pin(SavedSearch {
+        query: "TODO".to_string(),
+        ..search.clone()
+    });
+    assert_eq!(history.saved.len(), 1);
+    history.save().unwrap();
+
+    let mut loaded = SearchHistory::load_from(path.clone());
+    assert_eq!(loaded.queries, history.queries);
+    assert_eq!(loaded.saved("todos").unwrap().query, "TODO");
+    assert!(loaded.saved("todos").unwrap().options.regex);
+    loaded.unpin("todos");
+    assert!(loaded.saved.is_empty());
+}
+
+#[test]
+fn test_text_input_word_boundaries() {
+    use crate::ui::text_input::{next_word_end, previous_word_start};
+
+    let text = "foo_bar.baz  (qux)";
+    assert_eq!(previous_word_start(text, text.len()), 17);
+    assert_eq!(previous_word_start(text, 17), 14);
+    assert_eq!(previous_word_start(text, 14), 13);
+    // Whitespace is skipped before taking the word
+    assert_eq!(previous_word_start(text, 13), 8);
+    assert_eq!(previous_word_start(text, 8), 7);
+    assert_eq!(previous_word_start(text, 7), 0);
+    assert_eq!(previous_word_start(text, 0), 0);
+
+    assert_eq!(next_word_end(text, 0), 7);
+    assert_eq!(next_word_end(text, 7), 8);
+    assert_eq!(next_word_end(text, 11), 14);
+    assert_eq!(next_word_end(text, 17), text.len());
+    assert_eq!(next_word_end(text, text.len()), text.len());
+
+    let text = "héllo wörld";
+    assert_eq!(previous_word_start(text, text.len()), "héllo ".len());
+    assert_eq!(next_word_end(text, 0), "héllo".len());
+}
+
+#[test]
+fn test_file_operations() {
+    use crate::editor::fileops::{
+        create_dir, create_file, duplicate, move_into, moved_path, rename, trash_into,
+    };
+    use crate::utils::canonical_path;
+    use std::fs;
+    use std::path::Path;
+
+    let dir = TempDir::new("fileops");
+
+    // Nested names create the folders on the way
+    let file = create_file(&dir, "src/main.rs").unwrap();
+    assert_eq!(file, dir.join("src/main.rs"));
+    assert!(create_file(&dir, "src/main.rs").is_err());
+    assert!(create_file(&dir, "../escape.rs").is_err());
+    assert!(create_file(&dir, "  ").is_err());
+    let docs = create_dir(&dir, "docs").unwrap();
+    assert!(docs.is_dir());
+
+    let renamed = rename(&file, "lib.rs").unwrap();
+    assert_eq!(renamed, dir.join("src/lib.rs"));
+    assert!(!file.exists());
+    fs::write(dir.join("src/other.rs"), "").unwrap();
+    assert!(rename(&renamed, "other.rs").is_err());
+    assert!(rename(&renamed, "a/b.rs").is_err());
+
+    // Names differing only in case are different files here, and renaming
+    // one onto the other must not replace it
+    fs::write(dir.join("readme.md"), "lower").unwrap();
+    fs::write(dir.join("README.md"), "upper").unwrap();
+    let error = rename(&dir.join("readme.md"), "README.md").unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::AlreadyExists);
+    assert_eq!(fs::read_to_string(dir.join("readme.md")).unwrap(), "lower");
+    assert_eq!(fs::read_to_string(dir.join("README.md")).unwrap(), "upper");
+    fs::remove_file(dir.join("README.md")).unwrap();
+    let readme = rename(&dir.join("readme.md"), "README.md").unwrap();
+    assert_eq!(fs::read_to_string(readme).unwrap(), "lower");
+    #[cfg(unix)]
+    {
+        // A dangling symlink still takes up the name
+        std::os::unix::fs::symlink(dir.join("missing"), dir.join("link.md")).unwrap();
+        assert!(rename(&dir.join("README.md"), "link.md").is_err());
+    }
+
+    fs::write(&renamed, "fn main() {}").unwrap();
+    let copy = duplicate(&renamed).unwrap();
+    assert_eq!(copy, dir.join("src/lib copy.rs"));
+    assert_eq!(fs::read_to_string(&copy).unwrap(), "fn main() {}");
+    assert_eq!(duplicate(&renamed).unwrap(), dir.join("src/lib copy 2.rs"));
+    let src_copy = duplicate(&dir.join("src")).unwrap();
+    assert!(src_copy.join("lib.rs").is_file());
+    #[cfg(unix)]
+    {
+        // Links are copied as links, so one looping back to its own folder
+        // doesn't recurse forever
+        let looped = create_dir(&dir, "looped").unwrap();
+        std::os::unix::fs::symlink("..", looped.join("parent")).unwrap();
+        std::os::unix::fs::symlink("../docs", looped.join("docs")).unwrap();
+        let looped_copy = duplicate(&looped).unwrap();
+        assert_eq!(fs::read_link(looped_copy.join("parent")).unwrap(), Path::new(".."));
+        assert_eq!(fs::read_link(looped_copy.join("docs")).unwrap(), Path::new("../docs"));
+        fs::remove_dir_all(&looped_copy).unwrap();
+        assert!(dir.join("docs").is_dir());
+    }
+
+    let src = dir.join("src");
+    assert!(move_into(&src, &src.join("nested")).is_err());
+    let moved = move_into(&src, &docs).unwrap();
+    assert_eq!(moved, docs.join("src"));
+    assert!(moved.join("lib.rs").is_file());
+
+    assert_eq!(
+        moved_path(&src.join("lib.rs"), &src, &moved),
+        Some(moved.join("lib.rs"))
+    );
+    assert_eq!(moved_path(&src, &src, &moved), Some(moved.clone()));
+    assert_eq!(moved_path(Path::new("srcs/x.rs"), Path::new("src"), &moved), None);
+    // Paths that no longer exist still resolve through the folder that does
+    assert_eq!(
+        canonical_path(&dir.join("docs/../src/lib.rs")),
+        canonical_path(&dir).join("src/lib.rs")
+    );
+
+    let trash = dir.join("Trash");
+    let trashed = trash_into(&moved.join("lib.rs"), &trash).unwrap();
+    assert!(trashed.is_file());
+    assert!(!moved.join("lib.rs").exists());
+    let info = fs::read_to_string(trash.join("info/lib.rs.trashinfo")).unwrap();
+    assert!(info.starts_with("[Trash Info]\nPath=/"));
+    assert!(info.contains("/docs/src/lib.rs\n"));
+    // A second entry with the same name gets its own slot
+    fs::write(moved.join("lib.rs"), "").unwrap();
+    assert!(trash_into(&moved.join("lib.rs"), &trash).unwrap().ends_with("lib.rs.2"));
+    // Errors other than a taken name are reported rather than retried
+    let long = moved.join("x".repeat(250));
+    fs::write(&long, "").unwrap();
+    assert!(trash_into(&long, &trash).is_err());
+    assert!(long.exists());
+}
+
+#[test]
+fn test_scrollbar_thumb() {
+    use crate::editor::filebrowser::scrollbar_thumb;
+
+    // Everything fits
+    assert_eq!(scrollbar_thumb(400.0, 0.0, 0.0), None);
+    // Half the rows visible: half the track, at either end
+    assert_eq!(scrollbar_thumb(400.0, 400.0, 0.0), Some((0.0, 200.0)));
+    assert_eq!(scrollbar_thumb(400.0, 400.0, 400.0), Some((200.0, 200.0)));
+    assert_eq!(scrollbar_thumb(400.0, 400.0, 200.0), Some((100.0, 200.0)));
+    // Huge trees keep a grabbable thumb, and overscroll stays in the track
+    let (top, height) = scrollbar_thumb(400.0, 1_000_000.0, 2_000_000.0).unwrap();
+    assert_eq!((top, height), (380.0, 20.0));
+}
+
+#[test]
+fn test_file_tree() {
+    use crate::editor::filetree::{FileTree, list_dir};
+    use std::fs;
+
+    let dir = TempDir::new("filetree");
+    fs::create_dir_all(dir.join("src/ui")).unwrap();
+    fs::write(dir.join("b.txt"), "").unwrap();
+    fs::write(dir.join("a.txt"), "").unwrap();
+    fs::write(dir.join("src/main.rs"), "").unwrap();
+
+    // Folders first, then by name
+    let entries = list_dir(&dir).unwrap();
+    let names: Vec<_> = entries.iter().map(|e| e.path.file_name().unwrap()).collect();
+    assert_eq!(names, ["src", "a.txt", "b.txt"]);
+
+    let mut tree = FileTree::new(vec![dir.clone()]);
+    assert!(tree.rows().is_empty());
+    tree.set_children(dir.clone(), entries);
+    assert_eq!(tree.rows().len(), 3);
+
+    // Expanding an unlisted folder asks for a listing
+    let src = dir.join("src");
+    assert!(tree.expand(&src));
+    assert_eq!(tree.unloaded_dirs(), vec![src.clone()]);
+    tree.set_children(src.clone(), list_dir(&src).unwrap());
+    assert!(!tree.expand(&src));
+    let rows = tree.rows();
+    assert_eq!(rows.len(), 5);
+    assert!(rows[0].expanded);
+    assert_eq!((rows[1].path.clone(), rows[1].depth), (src.join("ui"), 1));
+    assert_eq!(tree.position(&src.join("main.rs")), Some(2));
+
+    // Only loaded folders need reloading when something changes
+    assert_eq!(tree.stale_dirs(&src.join("new.rs")), vec![src.clone()]);
+    assert_eq!(tree.stale_dirs(&src), [dir.clone(), src.clone()]);
+    assert!(tree.stale_dirs(&src.join("ui/mod.rs")).is_empty());
+
+    tree.collapse(&src);
+    assert_eq!(tree.rows().len(), 3);
+    tree.expand(&src);
+
+    // A folder that disappears from its parent is forgotten
+    fs::remove_dir_all(&src).unwrap();
+    tree.set_children(dir.clone(), list_dir(&dir).unwrap());
+    assert_eq!(tree.rows().len(), 2);
+    assert!(!tree.is_expanded(&src));
+    assert!(!tree.is_loaded(&src));
+}
+
+#[test]
+fn test_file_tree_filter() {
+    use crate::editor::filetree::FileTree;
+    use std::path::PathBuf;
+
+    let files: Vec<PathBuf> = [
+        "./src/editor/search.rs",
+        "./src/editor/texteditor.rs",
+        "./src/ui/search_bar.rs",
+        "./src/main.rs",
+        "./README.md",
+    ]
+    .iter()
+    .map(PathBuf::from)
+    .collect();
+
+    let tree = FileTree::matching(vec![PathBuf::from(".")], &files, "SEARCH");
+    let rows: Vec<_> = tree
+        .rows()
+        .iter()
+        .map(|row| (row.path.to_string_lossy().to_string(), row.depth, row.is_dir))
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            ("./src".to_string(), 0, true),
+            ("./src/editor".to_string(), 1, true),
+            ("./src/editor/search.rs".to_string(), 2, false),
+            ("./src/ui".to_string(), 1, true),
+            ("./src/ui/search_bar.rs".to_string(), 2, false),
+        ]
+    );
+    assert!(tree.rows().iter().filter(|r| r.is_dir).all(|r| r.expanded));
+
+    // Folder names count as part of the path
+    let tree = FileTree::matching(vec![PathBuf::from(".")], &files, "editor/");
+    assert_eq!(tree.rows().len(), 4);
+    assert!(FileTree::matching(vec![PathBuf::from(".")], &files, "nothing").rows().is_empty());
+}
+
+#[test]
+fn test_panel_layout_persistence() {
+    use crate::config::{BROWSER_DEFAULT_WIDTH, BROWSER_MAX_WIDTH, BROWSER_MIN_WIDTH};
+    use crate::layout::{Dock, PanelLayout};
+    use crate::persist::JsonFile;
+    use std::fs;
+
+    let tmp = TempDir::new("layout-data");
+    let path = tmp.join("layout.json");
+
+    let mut layout = PanelLayout::load_from(path.clone());
+    assert!(layout.show_browser);
+    assert_eq!(layout.browser_width, BROWSER_DEFAULT_WIDTH);
+    assert_eq!(layout.browser_dock, Dock::Left);
+
+    layout.set_browser_width(10.0);
+    assert_eq!(layout.browser_width, BROWSER_MIN_WIDTH);
+    layout.set_browser_width(10_000.0);
+    assert_eq!(layout.browser_width, BROWSER_MAX_WIDTH);
+    layout.set_browser_width(300.0);
+    layout.show_info_panel = false;
+    layout.browser_dock = layout.browser_dock.other();
+    layout.save().unwrap();
+
+    let loaded = PanelLayout::load_from(path.clone());
+    assert_eq!(loaded.browser_width, 300.0);
+    assert!(!loaded.show_info_panel);
+    assert_eq!(loaded.browser_dock, Dock::Right);
+
+    // Out-of-range widths from an edited file are pulled back in
+    fs::write(&path, r#"{"browser_width": 5}"#).unwrap();
+    assert_eq!(PanelLayout::load_from(path.clone()).browser_width, BROWSER_MIN_WIDTH);
+}
+
+#[test]
+fn test_launch_path_and_recent_workspaces() {
+    use crate::config::RECENT_WORKSPACES_LIMIT;
+    use crate::recent::RecentWorkspaces;
+    use crate::persist::{JsonFile, write_json};
+    use crate::utils::parse_launch_path;
+    use std::fs;
+    use std::path::PathBuf;
+
+    assert_eq!(
+        parse_launch_path("src/main.rs:12:5"),
+        (PathBuf::from("src/main.rs"), Some((12, 5)))
+    );
+    assert_eq!(
+        parse_launch_path("src/main.rs:12"),
+        (PathBuf::from("src/main.rs"), Some((12, 1)))
+    );
+    assert_eq!(parse_launch_path("notes.txt"), (PathBuf::from("notes.txt"), None));
+
+    let dir = TempDir::new("recent");
+    // A file whose name looks like a position opens as is
+    let colon = dir.join("odd:3");
+    fs::write(&colon, "").unwrap();
+    assert_eq!(parse_launch_path(colon.to_str().unwrap()), (colon.clone(), None));
+
+    let path = dir.join("recent.json");
+    let mut recent = RecentWorkspaces::load_from(path.clone());
+    assert!(recent.paths.is_empty());
+    for i in 0..RECENT_WORKSPACES_LIMIT + 2 {
+        recent.push(&PathBuf::from(format!("/work/{}", i)));
+    }
+    recent.push(&PathBuf::from("/work/5"));
+    assert_eq!(recent.paths.len(), RECENT_WORKSPACES_LIMIT);
+    assert_eq!(recent.paths[0], PathBuf::from("/work/5"));
+    assert_eq!(recent.paths.iter().filter(|p| p.ends_with("5")).count(), 1);
+    recent.save().unwrap();
+    assert_eq!(RecentWorkspaces::load_from(path.clone()).paths, recent.paths);
+
+    // Saves go through a temp file, which is gone afterwards even when the
+    // rename fails
+    let names = |dir: &PathBuf| {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    };
+    assert_eq!(names(&dir), vec!["odd:3", "recent.json"]);
+    let blocked = dir.join("blocked");
+    fs::create_dir_all(blocked.join("inside")).unwrap();
+    assert!(write_json(&blocked, &recent.paths).is_err());
+    assert_eq!(names(&dir), vec!["blocked", "odd:3", "recent.json"]);
+}
+
+#[test]
+fn test_workspace_roots() {
+    use crate::editor::filetree::{FileTree, TreeEntry};
+    use crate::editor::search::{SearchScope, project_files};
+    use crate::roots::WorkspaceRoots;
+    use std::fs;
+    use std::path::PathBuf;
+
+    let tmp = TempDir::new("roots");
+    fs::create_dir_all(tmp.join("app/src")).unwrap();
+    fs::create_dir_all(tmp.join("lib")).unwrap();
+    let dir = tmp.canonicalize().unwrap();
+    let (app, lib) = (dir.join("app"), dir.join("lib"));
+
+    // A plain folder is a single root with no workspace file
+    let single = WorkspaceRoots::open(&app).unwrap();
+    assert_eq!(single.folders, vec![app.clone()]);
+    assert_eq!(single.file, None);
+    assert_eq!(single.display_path(&app.join("src/main.rs")), "src/main.rs");
+
+    // Paths from dialogs are spelled the way the folder's listing spells them
+    let relative = WorkspaceRoots::folder(PathBuf::from("."));
+    let main = std::env::current_dir().unwrap().join("src/main.rs");
+    assert_eq!(relative.normalize(&main), PathBuf::from("./src/main.rs"));
+    fs::write(app.join("src/main.rs"), "").unwrap();
+    assert_eq!(single.normalize(&app.join("src/../src/main.rs")), app.join("src/main.rs"));
+    assert_eq!(single.normalize(&lib.join("x.rs")), lib.join("x.rs"));
+
+    let mut workspace = single.clone();
+    assert!(workspace.add(lib.clone()));
+    assert!(!workspace.add(lib.clone()));
+    assert_eq!(workspace.display_path(&lib.join("lib.rs")), "lib/lib.rs");
+    assert_eq!(workspace.root_of(&app.join("src")), Some(app.as_path()));
+
+    // A folder nested in another is walked on its own, and left out of the
+    // outer walk, so nothing is searched twice
+    let src = app.join("src");
+    let mut nested = workspace.clone();
+    assert!(nested.add(src.clone()));
+    assert!(nested.add(app.join("src/../src")));
+    assert_eq!(
+        nested.walk_roots(),
+        vec![
+            (app.clone(), vec![src.clone(), src.clone()]),
+            (lib.clone(), vec![]),
+            (src.clone(), vec![]),
+        ]
+    );
+    let files: Vec<PathBuf> = nested
+        .walk_roots()
+        .into_iter()
+        .flat_map(|(folder, skip)| {
+            project_files(&folder, &SearchScope::default().skipping(skip)).unwrap()
+        })
+        .collect();
+    assert_eq!(files, vec![src.join("main.rs")]);
+
+    // Folders next to the workspace file are saved relative to it
+    let file = dir.join("project.byok-workspace");
+    workspace.file = Some(file.clone());
+    workspace.save().unwrap();
+    let text = fs::read_to_string(&file).unwrap();
+    assert!(text.contains("\"app\"") && !text.contains(dir.to_str().unwrap()));
+    // Written through a temp file, which is renamed over it
+    let tmp = format!("project.byok-workspace.{}.tmp", std::process::id());
+    assert!(!dir.join(tmp).exists());
+    let reopened = WorkspaceRoots::open(&file).unwrap();
+    assert_eq!(reopened, workspace);
+    assert_eq!(reopened.recent_entry(), file);
+
+    // Missing folders are skipped, but one has to be left
+    fs::write(&file, r#"{"folders": ["gone", "lib"]}"#).unwrap();
+    assert_eq!(WorkspaceRoots::open(&file).unwrap().folders, vec![lib.clone()]);
+    fs::write(&file, r#"{"folders": ["gone"]}"#).unwrap();
+    assert!(WorkspaceRoots::open(&file).is_err());
+
+    assert!(workspace.remove(&lib));
+    assert!(!workspace.remove(&app));
+    assert_eq!(workspace.folders, vec![app.clone()]);
+
+    // Several roots each get a row, with their entries under them
+    let mut tree = FileTree::new(vec![app.clone(), lib.clone()]);
+    let src = TreeEntry { path: app.join("src"), is_dir: true, ignored: false };
+    tree.set_children(app.clone(), vec![src]);
+    tree.set_children(lib.clone(), Vec::new());
+    let rows: Vec<(PathBuf, usize)> = tree.rows().iter().map(|r| (r.path.clone(), r.depth)).collect();
+    assert_eq!(rows, vec![(app.clone(), 0), (app.join("src"), 1), (lib.clone(), 0)]);
+    tree.collapse(&app);
+    assert_eq!(tree.rows().len(), 2);
+
+    // Filtering keeps only the roots with matches
+    let files = vec![app.join("src/main.rs"), lib.join("lib.rs")];
+    let filtered = FileTree::matching(vec![app.clone(), lib.clone()], &files, "main");
+    assert_eq!(filtered.roots(), vec![app.clone()]);
+    assert_eq!(filtered.rows()[0].path, app);
+}
+
+#[test]
+fn test_icon_theme_and_ignored_entries() {
+    use crate::config::{DIR_ICON, DIR_OPEN_ICON, FILE_ICON};
+    use crate::editor::filetree::{FileTree, list_dir};
+    use crate::icons::IconTheme;
+    use std::fs;
+    use std::path::Path;
+
+    let theme = IconTheme::default();
+    assert_eq!(theme.icon(Path::new("./Cargo.toml"), false, false), "📦");
+    assert_eq!(theme.icon(Path::new("./src/main.RS"), false, false), "🦀");
+    assert_eq!(theme.icon(Path::new("./notes.unknown"), false, false), FILE_ICON);
+    assert_eq!(theme.icon(Path::new("./src"), true, false), DIR_ICON);
+    assert_eq!(theme.icon(Path::new("./src"), true, true), DIR_OPEN_ICON);
+
+    let dir = TempDir::new("icons");
+    fs::create_dir_all(dir.join("target/debug")).unwrap();
+    fs::create_dir_all(dir.join(".git")).unwrap();
+    fs::write(dir.join(".gitignore"), "/target\n*.log\n").unwrap();
+    fs::write(dir.join("main.rs"), "").unwrap();
+    fs::write(dir.join("debug.log"), "").unwrap();
+
+    // A theme file adds to the built-in icons rather than replacing them
+    let theme_path = dir.join("icon_theme.json");
+    fs::write(&theme_path, r#"{"folder": "D", "extensions": {".LOG": "L"}}"#).unwrap();
+    let custom = IconTheme::load_from(&theme_path);
+    assert_eq!(custom.icon(&dir.join("debug.log"), false, false), "L");
+    assert_eq!(custom.icon(&dir.join("target"), true, false), "D");
+    assert_eq!(custom.folder_open, DIR_OPEN_ICON);
+    assert_eq!(custom.icon(&dir.join("main.rs"), false, false), "🦀");
+    fs::write(&theme_path, "not json").unwrap();
+    assert_eq!(IconTheme::load_from(&theme_path), IconTheme::default());
+    fs::remove_file(&theme_path).unwrap();
+
+    let mut tree = FileTree::new(vec![dir.clone()]);
+    tree.set_children(dir.clone(), list_dir(&dir).unwrap());
+    tree.expand(&dir.join("target"));
+    tree.set_children(dir.join("target"), list_dir(&dir.join("target")).unwrap());
+    let ignored = |path: &Path| tree.rows().iter().find(|r| r.path == path).unwrap().ignored;
+    assert!(ignored(&dir.join(".git")));
+    assert!(ignored(&dir.join("target")));
+    // Inherited from the ignored folder
+    assert!(ignored(&dir.join("target/debug")));
+    assert!(ignored(&dir.join("debug.log")));
+    assert!(!ignored(&dir.join("main.rs")));
+    assert!(!ignored(&dir.join(".gitignore")));
+}
+
+#[test]
+fn test_git_statuses() {
+    use crate::editor::git::{GitStatus, GitStatuses};
+    use git2::{Repository, Signature};
+    use std::fs;
+    use std::path::Path;
+
+    let dir = TempDir::new("git");
+    fs::create_dir_all(dir.join("src/nested")).unwrap();
+    fs::create_dir_all(dir.join("target/debug")).unwrap();
+    fs::create_dir_all(dir.join("scratch")).unwrap();
+    fs::write(dir.join(".gitignore"), "/target\n").unwrap();
+    fs::write(dir.join("src/main.rs"), "fn main() {}\n").unwrap();
+    fs::write(dir.join("src/nested/lib.rs"), "").unwrap();
+
+    let repo = Repository::init(&dir).unwrap();
+    let mut index = repo.index().unwrap();
+    for file in [".gitignore", "src/main.rs", "src/nested/lib.rs"] {
+        index.add_path(Path::new(file)).unwrap();
+    }
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = Signature::now("Test", "test@example.com").unwrap();
+    repo.commit(Some("HEAD"), &signature, &signature, "Initial", &tree, &[])
+        .unwrap();
+
+    assert_eq!(GitStatuses::read(std::slice::from_ref(&dir)).get(&dir.join("src")), None);
+
+    fs::write(dir.join("src/main.rs"), "fn main() { changed() }\n").unwrap();
+    fs::write(dir.join("src/nested/new.rs"), "").unwrap();
+    index.add_path(Path::new("src/nested/new.rs")).unwrap();
+    index.write().unwrap();
+    fs::write(dir.join("scratch/notes.txt"), "").unwrap();
+    fs::write(dir.join("target/debug/app"), "").unwrap();
+
+    let statuses = GitStatuses::read(std::slice::from_ref(&dir));
+    assert_eq!(statuses.get(&dir.join("src/main.rs")), Some(GitStatus::Modified));
+    assert_eq!(statuses.get(&dir.join("src/nested/new.rs")), Some(GitStatus::Added));
+    assert_eq!(statuses.get(&dir.join("src/nested/lib.rs")), None);
+    // Untracked and ignored folders are reported whole
+    assert_eq!(statuses.get(&dir.join("scratch/notes.txt")), Some(GitStatus::Untracked));
+    assert_eq!(statuses.get(&dir.join("target/debug/app")), Some(GitStatus::Ignored));
+    // Folders take the most important state under them, ignoring ignored files
+    assert_eq!(statuses.get(&dir.join("src/nested")), Some(GitStatus::Added));
+    assert_eq!(statuses.get(&dir.join("src")), Some(GitStatus::Modified));
+    assert_eq!(statuses.get(&dir), Some(GitStatus::Modified));
+
+    // A root inside the repository only sees its own files
+    let nested = GitStatuses::read(&[dir.join("src/nested")]);
+    assert_eq!(nested.get(&dir.join("src/nested/new.rs")), Some(GitStatus::Added));
+    assert_eq!(nested.get(&dir.join("src/main.rs")), None);
+    assert_eq!(nested.get(&dir.join("src")), None);
+
+    // Outside any repository there is nothing to show
+    let outside = TempDir::new("no-git");
+    fs::write(outside.join("file.txt"), "").unwrap();
+    assert_eq!(GitStatuses::read(std::slice::from_ref(&outside)), GitStatuses::default());
+}
+
+#[test]
+fn test_git_line_hunks_and_revert() {
+    use crate::editor::git::{Hunk, HunkKind, diff_lines, head_text, original_lines, revert_hunk};
+    use git2::{Repository, Signature};
+    use std::fs;
+    use std::path::Path;
+
+    let base = "one\ntwo\nthree\nfour\nfive\n";
+    let text = "one\nTWO\nthree\nnew\nfour\n";
+    let hunks = diff_lines(base, text);
+    assert_eq!(
+        hunks,
+        vec![
+            Hunk { kind: HunkKind::Modified, old_lines: 1..2, new_lines: 1..2 },
+            Hunk { kind: HunkKind::Added, old_lines: 3..3, new_lines: 3..4 },
+            Hunk { kind: HunkKind::Deleted, old_lines: 4..5, new_lines: 5..5 },
+        ]
+    );
+    assert!(diff_lines(base, base).is_empty());
+    // A deletion at the end is marked on the last line
+    assert_eq!(hunks[2].marker_line(5), 4);
+    assert!(hunks[2].contains_line(4, 5));
+    assert_eq!(original_lines(base, &hunks[0]), vec!["two".to_string()]);
+
+    assert_eq!(revert_hunk(base, text, &hunks[0]), "one\ntwo\nthree\nnew\nfour\n");
+    assert_eq!(revert_hunk(base, text, &hunks[1]), "one\nTWO\nthree\nfour\n");
+    assert_eq!(revert_hunk(base, text, &hunks[2]), "one\nTWO\nthree\nnew\nfour\nfive\n");
+    // Reverting every hunk, last first, gets HEAD back
+    let reverted = hunks.iter().rev().fold(text.to_string(), |text, hunk| revert_hunk(base, &text, hunk));
+    assert_eq!(reverted, base);
+
+    // Missing final newlines survive a revert
+    let hunks = diff_lines("a\nb", "a\nc\n");
+    assert_eq!(revert_hunk("a\nb", "a\nc\n", &hunks[0]), "a\nb");
+
+    let dir = TempDir::new("gutter");
+    fs::write(dir.join("file.txt"), base).unwrap();
+    let repo = Repository::init(&dir).unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new("file.txt")).unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = Signature::now("Test", "test@example.com").unwrap();
+    repo.commit(Some("HEAD"), &signature, &signature, "Initial", &tree, &[])
+        .unwrap();
+
+    fs::write(dir.join("file.txt"), text).unwrap();
+    fs::write(dir.join("untracked.txt"), "").unwrap();
+    assert_eq!(head_text(&dir.join("file.txt")).as_deref(), Some(base));
+    assert_eq!(head_text(&dir.join("untracked.txt")), None);
+}
+
+#[test]
+fn test_source_control_stage_and_commit() {
+    use crate::editor::git::{
+        Change, ChangeKind, SourceControl, changed_lines, original_lines,
+    };
+    use git2::Repository;
+    use std::fs;
+    use std::path::Path;
+
+    let dir = TempDir::new("scm");
+    let repo = Repository::init(&dir).unwrap();
+    let mut config = repo.config().unwrap();
+    config.set_str("user.name", "Test").unwrap();
+    config.set_str("user.email", "test@example.com").unwrap();
+
+    fs::write(dir.join("a.txt"), "one\ntwo\nthree\n").unwrap();
+    let scm = SourceControl::open(&dir).unwrap();
+    let change = |path: &str, kind, staged| Change {
+        path: path.to_string(),
+        kind,
+        staged,
+        old_path: None,
+    };
+    assert_eq!(scm.changes().unwrap(), vec![change("a.txt", ChangeKind::Untracked, false)]);
+
+    // Nothing can be committed before something is staged
+    assert!(scm.commit("Initial").is_err());
+    scm.stage("a.txt").unwrap();
+    assert_eq!(scm.changes().unwrap(), vec![change("a.txt", ChangeKind::Added, true)]);
+    assert!(scm.commit("  ").is_err());
+    scm.commit("Initial").unwrap();
+    assert!(scm.changes().unwrap().is_empty());
+    assert_eq!(scm.branch().as_deref(), Some(repo.head().unwrap().shorthand().unwrap()));
+
+    // Two separate edits, staged one hunk at a time
+    fs::write(dir.join("a.txt"), "ONE\ntwo\nthree\nfour\n").unwrap();
+    let unstaged = change("a.txt", ChangeKind::Modified, false);
+    let diff = scm.diff(&unstaged).unwrap();
+    assert_eq!(diff.hunks.len(), 2);
+    assert_eq!(original_lines(&diff.old, &diff.hunks[0]), vec!["one"]);
+    assert_eq!(changed_lines(&diff.new, &diff.hunks[0]), vec!["ONE"]);
+    scm.stage_hunk("a.txt", &diff, &diff.hunks[1]).unwrap();
+    let staged = change("a.txt", ChangeKind::Modified, true);
+    assert_eq!(scm.changes().unwrap(), vec![staged.clone(), unstaged.clone()]);
+    assert_eq!(scm.diff(&staged).unwrap().new, "one\ntwo\nthree\nfour\n");
+    assert_eq!(scm.diff(&unstaged).unwrap().hunks.len(), 1);
+
+    // Unstaging the hunk leaves the work tree alone
+    let staged_diff = scm.diff(&staged).unwrap();
+    scm.unstage_hunk("a.txt", &staged_diff, &staged_diff.hunks[0]).unwrap();
+    assert_eq!(scm.changes().unwrap(), vec![unstaged.clone()]);
+    assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "ONE\ntwo\nthree\nfour\n");
+
+    // Hunks of new files can be staged too
+    fs::write(dir.join("b.txt"), "new\n").unwrap();
+    let new_file = change("b.txt", ChangeKind::Untracked, false);
+    let diff = scm.diff(&new_file).unwrap();
+    scm.stage_hunk("b.txt", &diff, &diff.hunks[0]).unwrap();
+    assert!(scm.changes().unwrap().contains(&change("b.txt", ChangeKind::Added, true)));
+
+    scm.stage("a.txt").unwrap();
+    scm.unstage(&change("b.txt", ChangeKind::Added, true)).unwrap();
+    fs::remove_file(dir.join("b.txt")).unwrap();
+    scm.commit("Edit a").unwrap();
+    assert!(scm.changes().unwrap().is_empty());
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    assert_eq!(head.message(), Some("Edit a"));
+    assert_eq!(head.parent_count(), 1);
+
+    // Deleting a file stages its removal
+    fs::remove_file(dir.join("a.txt")).unwrap();
+    assert_eq!(scm.changes().unwrap(), vec![change("a.txt", ChangeKind::Deleted, false)]);
+    scm.stage("a.txt").unwrap();
+    assert_eq!(scm.changes().unwrap(), vec![change("a.txt", ChangeKind::Deleted, true)]);
+    scm.unstage(&change("a.txt", ChangeKind::Deleted, true)).unwrap();
+    assert_eq!(scm.changes().unwrap(), vec![change("a.txt", ChangeKind::Deleted, false)]);
+
+    // Staging a hunk keeps bytes that aren't UTF-8 as they are
+    let latin1 = b"caf\xe9\none\ntwo\n";
+    fs::write(dir.join("c.txt"), latin1).unwrap();
+    scm.stage("c.txt").unwrap();
+    scm.commit("Add c").unwrap();
+    fs::write(dir.join("c.txt"), b"caf\xe9\nONE\ntwo\nna\xefve\n").unwrap();
+    let unstaged_c = change("c.txt", ChangeKind::Modified, false);
+    let diff = scm.diff(&unstaged_c).unwrap();
+    assert_eq!(diff.hunks.len(), 2);
+    scm.stage_hunk("c.txt", &diff, &diff.hunks[1]).unwrap();
+    let staged_blob = || {
+        let mut index = repo.index().unwrap();
+        index.read(true).unwrap();
+        let id = index.get_path(Path::new("c.txt"), 0).unwrap().id;
+        repo.find_blob(id).unwrap().content().to_vec()
+    };
+    assert_eq!(staged_blob(), b"caf\xe9\none\ntwo\nna\xefve\n");
+    let staged_c = change("c.txt", ChangeKind::Modified, true);
+    let diff = scm.diff(&staged_c).unwrap();
+    scm.unstage_hunk("c.txt", &diff, &diff.hunks[0]).unwrap();
+    assert_eq!(staged_blob(), latin1);
+
+    // A diff listed before the file changed still stages the hunk it
+    // showed, wherever it moved to, and refuses once that hunk is gone
+    fs::write(dir.join("c.txt"), b"caf\xe9\none\nTWO\n").unwrap();
+    let listed = scm.diff(&unstaged_c).unwrap();
+    fs::write(dir.join("c.txt"), b"new\ncaf\xe9\none\nTWO\n").unwrap();
+    scm.stage_hunk("c.txt", &listed, &listed.hunks[0]).unwrap();
+    assert_eq!(staged_blob(), b"caf\xe9\none\nTWO\n");
+    fs::write(dir.join("c.txt"), b"caf\xe9\none\nthree\n").unwrap();
+    let listed = scm.diff(&unstaged_c).unwrap();
+    fs::write(dir.join("c.txt"), b"caf\xe9\none\nTHREE\n").unwrap();
+    assert!(scm.stage_hunk("c.txt", &listed, &listed.hunks[0]).is_err());
+    assert_eq!(staged_blob(), b"caf\xe9\none\nTWO\n");
+
+    // A staged rename is listed under its new name, and unstaging it puts
+    // back both sides
+    scm.stage("c.txt").unwrap();
+    scm.commit("Edit c").unwrap();
+    fs::rename(dir.join("c.txt"), dir.join("d.txt")).unwrap();
+    scm.stage("c.txt").unwrap();
+    scm.stage("d.txt").unwrap();
+    let renamed = Change {
+        old_path: Some("c.txt".to_string()),
+        ..change("d.txt", ChangeKind::Renamed, true)
+    };
+    assert!(scm.changes().unwrap().contains(&renamed));
+    scm.unstage(&renamed).unwrap();
+    let changes = scm.changes().unwrap();
+    assert!(changes.contains(&change("c.txt", ChangeKind::Deleted, false)));
+    assert!(changes.contains(&change("d.txt", ChangeKind::Untracked, false)));
+    assert!(changes.iter().all(|c| !c.staged));
+}
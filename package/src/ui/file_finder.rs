@@ -0,0 +1,299 @@
+use gpui::{
+    AppContext, Context, DismissEvent, Entity, EventEmitter, FocusHandle, Focusable,
+    InteractiveElement, IntoElement, KeyDownEvent, MouseButton, ParentElement, Render,
+    ScrollHandle, StatefulInteractiveElement, Styled, Subscription, Window, div,
+    prelude::FluentBuilder, px, rgb,
+};
+use std::path::{Path, PathBuf};
+
+use crate::config::{FILE_FINDER_MAX_RESULTS, FILE_FINDER_RECENCY_BONUS, RECENT_FILES_LIMIT};
+use crate::roots::WorkspaceRoots;
+use crate::structs::fuzzy::fuzzy_match;
+use crate::ui::text_input::{TextChanged, TextInput};
+use crate::ui::workspace::OpenPath;
+
+/// A workspace file matching the finder's query.
+pub struct FinderMatch {
+    pub path: PathBuf,
+    /// Path relative to its workspace folder, as shown
+    pub display: String,
+    /// Char indices into `display` to highlight
+    pub positions: Vec<usize>,
+    pub score: i64,
+}
+
+/// Splits an optional `:line` or `:line:col` suffix off a quick-open query.
+pub fn parse_position(query: &str) -> (&str, Option<(usize, usize)>) {
+    // A trailing colon is the user about to type a number
+    let query = query.trim_end_matches(':');
+    let number = |s: &str| s.parse::<usize>().ok();
+
+    let Some((rest, last)) = query.rsplit_once(':') else {
+        return (query, None);
+    };
+    let Some(last) = number(last) else {
+        return (query, None);
+    };
+    match rest
+        .rsplit_once(':')
+        .and_then(|(path, line)| Some((path, number(line)?)))
+    {
+        Some((path, line)) => (path, Some((line, last))),
+        None => (rest, Some((last, 1))),
+    }
+}
+
+/// Quick-open over every file in the workspace (ctrl-p). Files are fuzzy
+/// matched against the query and ranked by match quality, with recently
+/// opened ones bumped up.
+pub struct FileFinder {
+    pub query: Entity<TextInput>,
+    roots: WorkspaceRoots,
+    files: Vec<PathBuf>,
+    /// Most recent first
+    recent: Vec<PathBuf>,
+    matches: Vec<FinderMatch>,
+    selected: usize,
+    loading: bool,
+    scroll_handle: ScrollHandle,
+    _query_subscription: Subscription,
+}
+
+impl EventEmitter<DismissEvent> for FileFinder {}
+
+impl Focusable for FileFinder {
+    fn focus_handle(&self, cx: &gpui::App) -> FocusHandle {
+        self.query.focus_handle(cx)
+    }
+}
+
+impl FileFinder {
+    pub fn new(roots: WorkspaceRoots, recent: Vec<PathBuf>, cx: &mut Context<Self>) -> Self {
+        // Spelled like the listing so a file doesn't show up twice
+        let mut normalized: Vec<PathBuf> = Vec::with_capacity(recent.len());
+        for path in recent {
+            let path = roots.normalize(&path);
+            if !normalized.contains(&path) {
+                normalized.push(path);
+            }
+        }
+        let query = cx.new(|cx| TextInput::new("Go to file (append :line:col to jump)", cx));
+        let query_subscription = cx.subscribe(&query, |this, _, _: &TextChanged, cx| {
+            this.update_matches(cx);
+            cx.notify();
+        });
+        let mut this = Self {
+            query,
+            roots,
+            files: Vec::new(),
+            recent: normalized,
+            matches: Vec::new(),
+            selected: 0,
+            loading: true,
+            scroll_handle: ScrollHandle::new(),
+            _query_subscription: query_subscription,
+        };
+        this.update_matches(cx);
+        this
+    }
+
+    /// Fills in the workspace files once they've been listed.
+    pub fn set_files(&mut self, files: Vec<PathBuf>, cx: &mut Context<Self>) {
+        self.files = files;
+        self.loading = false;
+        self.update_matches(cx);
+        cx.notify();
+    }
+
+    fn display_path(&self, path: &Path) -> String {
+        self.roots.display_path(path)
+    }
+
+    fn recency_bonus(&self, path: &Path) -> i64 {
+        self.recent.iter().position(|p| p == path).map_or(0, |i| {
+            FILE_FINDER_RECENCY_BONUS * (RECENT_FILES_LIMIT.saturating_sub(i)) as i64
+                / RECENT_FILES_LIMIT as i64
+        })
+    }
+
+    /// With an empty query the recent files are listed first, most recent on top.
+    fn update_matches(&mut self, cx: &gpui::App) {
+        let query = self.query.read(cx).text().to_string();
+        let (query, _) = parse_position(&query);
+
+        // Recent files that aren't in the listing (yet) can still be opened
+        let mut candidates: Vec<&PathBuf> = self.recent.iter().collect();
+        candidates.extend(self.files.iter().filter(|p| !self.recent.contains(p)));
+
+        let mut matches: Vec<FinderMatch> = candidates
+            .into_iter()
+            .filter_map(|path| {
+                let display = self.display_path(path);
+                let m = fuzzy_match(query, &display)?;
+                Some(FinderMatch {
+                    score: m.score + self.recency_bonus(path),
+                    positions: m.positions,
+                    path: path.clone(),
+                    display,
+                })
+            })
+            .collect();
+        if !query.is_empty() {
+            matches.sort_by(|a, b| {
+                b.score
+                    .cmp(&a.score)
+                    .then(a.display.len().cmp(&b.display.len()))
+                    .then_with(|| a.display.cmp(&b.display))
+            });
+        }
+        matches.truncate(FILE_FINDER_MAX_RESULTS);
+
+        self.matches = matches;
+        self.selected = 0;
+        self.scroll_handle.scroll_to_item(0);
+    }
+
+    fn select(&mut self, delta: isize, cx: &mut Context<Self>) {
+        if self.matches.is_empty() {
+            return;
+        }
+        let len = self.matches.len() as isize;
+        self.selected = (self.selected as isize + delta).rem_euclid(len) as usize;
+        self.scroll_handle.scroll_to_item(self.selected);
+        cx.notify();
+    }
+
+    fn confirm(&mut self, ix: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(m) = self.matches.get(ix) else {
+            return;
+        };
+        let action = match parse_position(self.query.read(cx).text()).1 {
+            Some((line, column)) => OpenPath::at(m.path.clone(), line, column),
+            None => OpenPath::new(m.path.clone()),
+        };
+        cx.emit(DismissEvent);
+        window.dispatch_action(Box::new(action), cx);
+    }
+
+    fn handle_key(&mut self, event: &KeyDownEvent, window: &mut Window, cx: &mut Context<Self>) {
+        let keystroke = &event.keystroke;
+        let ctrl = keystroke.modifiers.control;
+        match keystroke.key.as_str() {
+            "escape" => cx.emit(DismissEvent),
+            "enter" => self.confirm(self.selected, window, cx),
+            "up" => self.select(-1, cx),
+            "down" => self.select(1, cx),
+            "p" if ctrl => self.select(-1, cx),
+            "n" if ctrl => self.select(1, cx),
+            // Everything else is typing, handled by the query field
+            _ => return,
+        }
+        cx.stop_propagation();
+        cx.notify();
+    }
+}
+
+/// Splits `text` into runs, highlighting the chars at `positions`.
+pub fn highlighted(text: &str, positions: &[usize], offset: usize) -> gpui::Div {
+    let mut runs: Vec<(String, bool)> = Vec::new();
+    for (i, c) in text.chars().enumerate() {
+        let hit = positions.binary_search(&(i + offset)).is_ok();
+        match runs.last_mut() {
+            Some((run, h)) if *h == hit => run.push(c),
+            _ => runs.push((c.to_string(), hit)),
+        }
+    }
+
+    div()
+        .flex()
+        .flex_row()
+        .children(runs.into_iter().map(|(run, hit)| {
+            div()
+                .when(hit, |this| {
+                    this.text_color(rgb(0x18a3ff))
+                        .font_weight(gpui::FontWeight::BOLD)
+                })
+                .child(run)
+        }))
+}
+
+impl Render for FileFinder {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let selected = self.selected;
+        let rows = self.matches.iter().enumerate().map(|(ix, m)| {
+            // File name first, its directory dimmed after it
+            let name_start = m.display.rfind('/').map_or(0, |i| i + 1);
+            let name_offset = m.display[..name_start].chars().count();
+            div()
+                .px_4()
+                .py_1()
+                .flex()
+                .flex_row()
+                .gap_2()
+                .cursor_pointer()
+                .text_color(rgb(0xcccccc))
+                .when(ix == selected, |this| {
+                    this.bg(rgb(0x04395e)).text_color(rgb(0xffffff))
+                })
+                .hover(|s| s.bg(rgb(0x2a2d2e)))
+                .child(highlighted(
+                    &m.display[name_start..],
+                    &m.positions,
+                    name_offset,
+                ))
+                .child(
+                    highlighted(&m.display[..name_start], &m.positions, 0)
+                        .text_color(rgb(0x888888)),
+                )
+                .on_mouse_down(
+                    MouseButton::Left,
+                    cx.listener(move |this, _, window, cx| this.confirm(ix, window, cx)),
+                )
+        });
+
+        let status = if self.loading {
+            Some("Listing files...".to_string())
+        } else if self.matches.is_empty() {
+            Some("No matching files".to_string())
+        } else {
+            None
+        };
+
+        div()
+            .on_key_down(cx.listener(Self::handle_key))
+            .absolute()
+            .top(px(40.0))
+            .left_1_2()
+            .w(px(500.0))
+            .ml(px(-250.0))
+            .bg(rgb(0x252526))
+            .border_1()
+            .border_color(rgb(0x454545))
+            .shadow_lg()
+            .flex()
+            .flex_col()
+            .text_xs()
+            .child(
+                div()
+                    .px_4()
+                    .py_2()
+                    .border_b_1()
+                    .border_color(rgb(0x454545))
+                    .text_color(rgb(0xffffff))
+                    .child(self.query.clone()),
+            )
+            .child(
+                div()
+                    .id("file-finder-matches")
+                    .max_h(px(400.0))
+                    .flex()
+                    .flex_col()
+                    .overflow_y_scroll()
+                    .track_scroll(&self.scroll_handle)
+                    .children(rows),
+            )
+            .children(
+                status.map(|status| div().px_4().py_2().text_color(rgb(0x888888)).child(status)),
+            )
+    }
+}
@@ -0,0 +1,125 @@
+use gpui::{
+    AppContext, Context, DismissEvent, Entity, EventEmitter, FocusHandle, Focusable,
+    InteractiveElement, IntoElement, KeyDownEvent, ParentElement, Render, Styled, Subscription,
+    Window, div, px, rgb,
+};
+
+use crate::editor::texteditor::TextEditor;
+use crate::ui::text_input::{TextChanged, TextInput};
+
+/// Parses `line` or `line:col` (1-based). The column defaults to 1.
+pub fn parse_line_column(input: &str) -> Option<(usize, usize)> {
+    let input = input.trim().trim_end_matches(':');
+    let (line, column) = match input.split_once(':') {
+        Some((line, column)) => (line, column.trim().parse().ok()?),
+        None => (input, 1),
+    };
+    let line = line.trim().parse().ok()?;
+    (line > 0).then_some((line, column.max(1)))
+}
+
+/// Jump prompt for the active editor (ctrl-g). The editor follows along
+/// while typing and goes back to where it was if the prompt is cancelled.
+pub struct GoToLinePrompt {
+    pub query: Entity<TextInput>,
+    editor: Entity<TextEditor>,
+    /// Where the editor was before the prompt opened
+    origin: (usize, f32),
+    current_line: usize,
+    line_count: usize,
+    _query_subscription: Subscription,
+}
+
+impl EventEmitter<DismissEvent> for GoToLinePrompt {}
+
+impl Focusable for GoToLinePrompt {
+    fn focus_handle(&self, cx: &gpui::App) -> FocusHandle {
+        self.query.focus_handle(cx)
+    }
+}
+
+impl GoToLinePrompt {
+    pub fn new(editor: Entity<TextEditor>, cx: &mut Context<Self>) -> Self {
+        let (current_line, line_count) = editor.read(cx).cursor_line(cx);
+        let query = cx.new(|cx| TextInput::new("Go to line", cx));
+        let query_subscription =
+            cx.subscribe(&query, |this, _, _: &TextChanged, cx| this.preview(cx));
+        Self {
+            query,
+            origin: editor.read(cx).view_position(),
+            editor,
+            current_line,
+            line_count,
+            _query_subscription: query_subscription,
+        }
+    }
+
+    fn preview(&mut self, cx: &mut Context<Self>) {
+        match parse_line_column(self.query.read(cx).text()) {
+            Some((line, column)) => self
+                .editor
+                .update(cx, |editor, cx| editor.go_to_position(line, column, cx)),
+            None => {
+                let origin = self.origin;
+                self.editor
+                    .update(cx, |editor, cx| editor.restore_view_position(origin, cx));
+            }
+        }
+    }
+
+    fn cancel(&mut self, cx: &mut Context<Self>) {
+        let origin = self.origin;
+        self.editor
+            .update(cx, |editor, cx| editor.restore_view_position(origin, cx));
+        cx.emit(DismissEvent);
+    }
+
+    fn handle_key(&mut self, event: &KeyDownEvent, _: &mut Window, cx: &mut Context<Self>) {
+        match event.keystroke.key.as_str() {
+            "escape" => self.cancel(cx),
+            // The preview already moved the editor there
+            "enter" if parse_line_column(self.query.read(cx).text()).is_some() => {
+                cx.emit(DismissEvent)
+            }
+            "enter" => self.cancel(cx),
+            // Everything else is typing, handled by the query field
+            _ => return,
+        }
+        cx.stop_propagation();
+        cx.notify();
+    }
+}
+
+impl Render for GoToLinePrompt {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let hint = format!(
+            "Current line {} of {}. Type a line number, optionally followed by :column",
+            self.current_line, self.line_count
+        );
+
+        div()
+            .on_key_down(cx.listener(Self::handle_key))
+            .absolute()
+            .top(px(40.0))
+            .left_1_2()
+            .w(px(400.0))
+            .ml(px(-200.0))
+            .bg(rgb(0x252526))
+            .border_1()
+            .border_color(rgb(0x454545))
+            .shadow_lg()
+            .flex()
+            .flex_col()
+            .text_xs()
+            .child(
+                div()
+                    .px_4()
+                    .py_2()
+                    .border_b_1()
+                    .border_color(rgb(0x454545))
+                    .text_color(rgb(0xffffff))
+                    .child(self.query.clone()),
+            )
+            .child(div().px_4().py_2().text_color(rgb(0x888888)).child(hint))
+    }
+}
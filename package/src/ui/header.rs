@@ -1,10 +1,15 @@
 use gpui::{
-    Context, InteractiveElement, IntoElement, MouseButton, ParentElement, Render, Styled, Window,
-    div, px, rgb,
+    Context, InteractiveElement, IntoElement, MouseButton, ParentElement, Styled, Window, div, rgb,
 };
 
 pub struct Header {}
 
+impl Default for Header {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Header {
     pub fn new() -> Self {
         Self {}
@@ -1,6 +1,13 @@
+pub mod file_finder;
+pub mod go_to_line;
 pub mod header;
-pub mod palette; // Planned
+pub mod palette;
 pub mod statusbar; // Planned
 pub mod settings;
 pub mod workspace;
 pub mod search_bar;
+pub mod project_replace;
+pub mod search_results;
+pub mod source_control;
+pub mod symbol_picker;
+pub mod text_input;
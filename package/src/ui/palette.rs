@@ -1,22 +1,238 @@
+use gpui::prelude::FluentBuilder;
 use gpui::*;
-use gpui::prelude::*;
 
+use crate::editor::texteditor::{
+    NewFile, NextHunk, OpenFile, PreviewHunk, PreviousHunk, RevertHunk, SaveFile, SaveFileAs,
+};
+use crate::history::SearchHistory;
+use crate::persist::JsonFile;
+use crate::recent::RecentWorkspaces;
+use crate::structs::fuzzy::fuzzy_match;
+use crate::ui::file_finder::highlighted;
+use crate::ui::search_bar::{DeleteSavedSearch, RunSavedSearch};
+use crate::ui::search_results::{NextResult, PreviousResult};
+use crate::ui::text_input::{TextChanged, TextInput};
+use crate::ui::workspace::{
+    AddFolderToWorkspace, CloseTab, FlushCache, FocusBrowser, GoToLine, GoToSymbol, OpenFolder,
+    OpenSettings, OpenWorkspace, OpenWorkspaceFile, Quit, RevealActiveFile, SaveWorkspaceAs,
+    ShowCacheStats, ShowSourceControl, SwitchBrowserSide, ToggleBrowser, ToggleFileSwitcher,
+    ToggleInfoPanel, ToggleProjectReplace, ToggleReplace, ToggleSearch,
+};
+
+/// Something the palette can run.
+pub struct PaletteCommand {
+    pub label: String,
+    pub action: Box<dyn Action>,
+}
+
+impl PaletteCommand {
+    fn new(label: impl Into<String>, action: impl Action) -> Self {
+        Self {
+            label: label.into(),
+            action: Box::new(action),
+        }
+    }
+}
+
+/// Workspace commands, followed by the saved searches and recent workspaces.
+pub fn commands(history: &SearchHistory, recent: &RecentWorkspaces) -> Vec<PaletteCommand> {
+    let mut commands = vec![
+        PaletteCommand::new("File: New File", NewFile),
+        PaletteCommand::new("File: Open...", OpenFile),
+        PaletteCommand::new("File: Open Folder...", OpenFolder),
+        PaletteCommand::new("File: Open Workspace...", OpenWorkspaceFile),
+        PaletteCommand::new("File: Add Folder to Workspace...", AddFolderToWorkspace),
+        PaletteCommand::new("File: Save Workspace As...", SaveWorkspaceAs),
+        PaletteCommand::new("File: Go to File...", ToggleFileSwitcher),
+        PaletteCommand::new("File: Reveal Active File in Browser", RevealActiveFile),
+        PaletteCommand::new("File: Save", SaveFile),
+        PaletteCommand::new("File: Save As...", SaveFileAs),
+        PaletteCommand::new("File: Close Tab", CloseTab),
+        PaletteCommand::new("Edit: Find", ToggleSearch { global: false }),
+        PaletteCommand::new("Edit: Find in Project", ToggleSearch { global: true }),
+        PaletteCommand::new("Edit: Replace", ToggleReplace),
+        PaletteCommand::new("Edit: Replace in Project", ToggleProjectReplace),
+        PaletteCommand::new("Edit: Next Search Result", NextResult),
+        PaletteCommand::new("Edit: Previous Search Result", PreviousResult),
+        PaletteCommand::new("Go: Go to Line...", GoToLine),
+        PaletteCommand::new("Go: Go to Symbol...", GoToSymbol),
+        PaletteCommand::new("Go: Next Change", NextHunk),
+        PaletteCommand::new("Go: Previous Change", PreviousHunk),
+        PaletteCommand::new("Git: Preview Change", PreviewHunk),
+        PaletteCommand::new("Git: Revert Change", RevertHunk),
+        PaletteCommand::new("Git: Open Source Control", ShowSourceControl),
+        PaletteCommand::new("View: Toggle Browser", ToggleBrowser),
+        PaletteCommand::new("View: Focus Browser", FocusBrowser),
+        PaletteCommand::new("View: Move Browser to Other Side", SwitchBrowserSide),
+        PaletteCommand::new("View: Toggle Info Panel", ToggleInfoPanel),
+        PaletteCommand::new("View: File Cache Stats", ShowCacheStats),
+        PaletteCommand::new("View: Flush File Cache", FlushCache),
+        PaletteCommand::new("Settings: Open Settings", OpenSettings),
+        PaletteCommand::new("Quit", Quit),
+    ];
+    for search in &history.saved {
+        let name = search.name.clone();
+        commands.push(PaletteCommand::new(
+            format!("Search: {}", name),
+            RunSavedSearch { name: name.clone() },
+        ));
+        commands.push(PaletteCommand::new(
+            format!("Search: Unpin {}", name),
+            DeleteSavedSearch { name },
+        ));
+    }
+    for path in &recent.paths {
+        commands.push(PaletteCommand::new(
+            format!("File: Open Recent {}", path.display()),
+            OpenWorkspace { path: path.clone() },
+        ));
+    }
+    commands
+}
+
+/// Fuzzy-filtered list of commands (ctrl-shift-p).
 pub struct CommandPalette {
-    pub focus_handle: FocusHandle,
+    pub query: Entity<TextInput>,
+    commands: Vec<PaletteCommand>,
+    /// (command index, matched char positions), best first
+    matches: Vec<(usize, Vec<usize>)>,
+    selected: usize,
+    scroll_handle: ScrollHandle,
+    _query_subscription: Subscription,
+}
+
+impl EventEmitter<DismissEvent> for CommandPalette {}
+
+impl Focusable for CommandPalette {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.query.focus_handle(cx)
+    }
 }
 
 impl CommandPalette {
     pub fn new(cx: &mut Context<Self>) -> Self {
-        Self {
-            focus_handle: cx.focus_handle(),
+        let query = cx.new(|cx| TextInput::new("Type a command...", cx));
+        let query_subscription = cx.subscribe(&query, |this, _, _: &TextChanged, cx| {
+            this.update_matches(cx);
+            cx.notify();
+        });
+        let mut this = Self {
+            query,
+            commands: commands(&SearchHistory::get(cx), &RecentWorkspaces::get(cx)),
+            matches: Vec::new(),
+            selected: 0,
+            scroll_handle: ScrollHandle::new(),
+            _query_subscription: query_subscription,
+        };
+        this.update_matches(cx);
+        this
+    }
+
+    fn update_matches(&mut self, cx: &App) {
+        let query = self.query.read(cx).text();
+        let mut scored: Vec<(i64, usize, Vec<usize>)> = self
+            .commands
+            .iter()
+            .enumerate()
+            .filter_map(|(ix, command)| {
+                let m = fuzzy_match(query, &command.label)?;
+                Some((m.score, ix, m.positions))
+            })
+            .collect();
+        if !query.is_empty() {
+            scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        }
+        self.matches = scored
+            .into_iter()
+            .map(|(_, ix, positions)| (ix, positions))
+            .collect();
+        self.selected = 0;
+        self.scroll_handle.scroll_to_item(0);
+    }
+
+    fn select(&mut self, delta: isize, cx: &mut Context<Self>) {
+        if self.matches.is_empty() {
+            return;
+        }
+        let len = self.matches.len() as isize;
+        self.selected = (self.selected as isize + delta).rem_euclid(len) as usize;
+        self.scroll_handle.scroll_to_item(self.selected);
+        cx.notify();
+    }
+
+    /// Closes the palette, then runs the command from wherever focus lands
+    /// so editor commands reach the editor.
+    fn confirm(&mut self, ix: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let Some((command_ix, _)) = self.matches.get(ix) else {
+            return;
+        };
+        let action = self.commands[*command_ix].action.boxed_clone();
+        cx.emit(DismissEvent);
+        cx.defer_in(window, move |_, window, cx| {
+            window.dispatch_action(action, cx)
+        });
+    }
+
+    fn handle_key(&mut self, event: &KeyDownEvent, window: &mut Window, cx: &mut Context<Self>) {
+        let keystroke = &event.keystroke;
+        let ctrl = keystroke.modifiers.control;
+        match keystroke.key.as_str() {
+            "escape" => cx.emit(DismissEvent),
+            "enter" => self.confirm(self.selected, window, cx),
+            "up" => self.select(-1, cx),
+            "down" => self.select(1, cx),
+            "p" if ctrl => self.select(-1, cx),
+            "n" if ctrl => self.select(1, cx),
+            // Everything else is typing, handled by the query field
+            _ => return,
         }
+        cx.stop_propagation();
+        cx.notify();
     }
 }
 
 impl Render for CommandPalette {
-    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let selected = self.selected;
+        let rows: Vec<_> = self
+            .matches
+            .iter()
+            .enumerate()
+            .map(|(row, (ix, positions))| {
+                let command = &self.commands[*ix];
+                let binding = window
+                    .highest_precedence_binding_for_action(command.action.as_ref())
+                    .map(|binding| {
+                        binding
+                            .keystrokes()
+                            .iter()
+                            .map(|k| k.unparse())
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    });
+                div()
+                    .px_4()
+                    .py_1()
+                    .flex()
+                    .flex_row()
+                    .cursor_pointer()
+                    .text_color(rgb(0xcccccc))
+                    .when(row == selected, |this| {
+                        this.bg(rgb(0x04395e)).text_color(rgb(0xffffff))
+                    })
+                    .hover(|s| s.bg(rgb(0x2a2d2e)))
+                    .child(highlighted(&command.label, positions, 0))
+                    .child(div().flex_1())
+                    .children(binding.map(|b| div().text_color(rgb(0x888888)).child(b)))
+                    .on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(move |this, _, window, cx| this.confirm(row, window, cx)),
+                    )
+            })
+            .collect();
+
         div()
-            .track_focus(&self.focus_handle)
+            .on_key_down(cx.listener(Self::handle_key))
             .shadow_lg()
             .absolute()
             .top(px(40.0))
@@ -26,10 +242,36 @@ impl Render for CommandPalette {
             .bg(rgb(0x252526))
             .border_1()
             .border_color(rgb(0x454545))
+            .flex()
+            .flex_col()
+            .text_xs()
+            .child(
+                div()
+                    .px_4()
+                    .py_2()
+                    .border_b_1()
+                    .border_color(rgb(0x454545))
+                    .text_color(rgb(0xffffff))
+                    .child(self.query.clone()),
+            )
             .child(
                 div()
-                    .p_2()
-                    .child("Type a command...")
+                    .id("command-palette-matches")
+                    .max_h(px(400.0))
+                    .flex()
+                    .flex_col()
+                    .overflow_y_scroll()
+                    .track_scroll(&self.scroll_handle)
+                    .children(rows),
             )
+            .when(self.matches.is_empty(), |this| {
+                this.child(
+                    div()
+                        .px_4()
+                        .py_2()
+                        .text_color(rgb(0x888888))
+                        .child("No matching commands"),
+                )
+            })
     }
 }
@@ -0,0 +1,239 @@
+use gpui::{
+    Context, FocusHandle, FontWeight, InteractiveElement, IntoElement, MouseButton, ParentElement,
+    Render, StatefulInteractiveElement, Styled, Window, actions, div, prelude::FluentBuilder, px,
+    rgb,
+};
+
+use crate::editor::replace::{ProjectReplace, ReplaceMatch, ReplaceSummary};
+
+actions!(
+    ProjectReplaceView,
+    [ApplyProjectReplace, UndoProjectReplace]
+);
+
+/// Preview of a project-wide replace. Every match can be unchecked before
+/// applying; the workspace does the actual writing since it owns the editors.
+pub struct ProjectReplaceView {
+    pub query: String,
+    pub replacement: String,
+    pub focus_handle: FocusHandle,
+    /// `None` while the project is still being scanned
+    pub plan: Option<ProjectReplace>,
+    /// Set once applied; holds the edits needed to undo
+    pub summary: Option<ReplaceSummary>,
+    /// Set once the replace has been undone
+    pub status: Option<String>,
+}
+
+impl ProjectReplaceView {
+    pub fn new(query: String, replacement: String, cx: &mut Context<Self>) -> Self {
+        Self {
+            query,
+            replacement,
+            focus_handle: cx.focus_handle(),
+            plan: None,
+            summary: None,
+            status: None,
+        }
+    }
+
+    pub fn set_plan(&mut self, plan: ProjectReplace, cx: &mut Context<Self>) {
+        self.plan = Some(plan);
+        cx.notify();
+    }
+
+    fn toggle_match(&mut self, file_ix: usize, match_ix: usize, cx: &mut Context<Self>) {
+        if self.summary.is_some() {
+            return;
+        }
+        if let Some(m) = self
+            .plan
+            .as_mut()
+            .and_then(|plan| plan.files.get_mut(file_ix))
+            .and_then(|file| file.matches.get_mut(match_ix))
+        {
+            m.included = !m.included;
+            cx.notify();
+        }
+    }
+
+    /// Checks every match in the file, or unchecks them all if they already are.
+    fn toggle_file(&mut self, file_ix: usize, cx: &mut Context<Self>) {
+        if self.summary.is_some() {
+            return;
+        }
+        if let Some(file) = self
+            .plan
+            .as_mut()
+            .and_then(|plan| plan.files.get_mut(file_ix))
+        {
+            let include = file.included_count() < file.matches.len();
+            for m in &mut file.matches {
+                m.included = include;
+            }
+            cx.notify();
+        }
+    }
+
+    fn header_text(&self) -> String {
+        match (&self.plan, &self.summary) {
+            (None, _) => "Scanning project...".to_string(),
+            (Some(_), Some(summary)) => summary.to_string(),
+            (Some(plan), None) => format!(
+                "{} matches in {} files, {} selected",
+                plan.match_count(),
+                plan.files.len(),
+                plan.included_count()
+            ),
+        }
+    }
+}
+
+fn checkbox(checked: bool) -> &'static str {
+    if checked { "☑" } else { "☐" }
+}
+
+fn button(label: String) -> gpui::Div {
+    div()
+        .px_2()
+        .py_1()
+        .rounded_md()
+        .bg(rgb(0x0e639c))
+        .text_color(rgb(0xffffff))
+        .cursor_pointer()
+        .hover(|s| s.bg(rgb(0x1177bb)))
+        .child(label)
+}
+
+fn render_match(m: &ReplaceMatch) -> impl IntoElement {
+    let line = &m.line_content;
+    let range = m.line_range.clone();
+    div()
+        .flex()
+        .flex_row()
+        .child(line[..range.start].trim_start().to_string())
+        .child(
+            div()
+                .text_color(rgb(0xe06c75))
+                .line_through()
+                .child(line[range.clone()].to_string()),
+        )
+        .child(div().text_color(rgb(0x98c379)).child(m.replacement.clone()))
+        .child(line[range.end..].to_string())
+}
+
+impl Render for ProjectReplaceView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let applied = self.summary.is_some();
+        let undone = self.status.is_some();
+        let included = self.plan.as_ref().map_or(0, |p| p.included_count());
+
+        let files = self
+            .plan
+            .iter()
+            .flat_map(|plan| plan.files.iter())
+            .enumerate();
+        let rows = files.map(|(file_ix, file)| {
+            let all_included = file.included_count() == file.matches.len();
+            div()
+                .flex()
+                .flex_col()
+                .child(
+                    div()
+                        .flex()
+                        .flex_row()
+                        .gap_2()
+                        .py_1()
+                        .cursor_pointer()
+                        .font_weight(FontWeight::BOLD)
+                        .child(checkbox(all_included))
+                        .child(file.path.to_string_lossy().to_string())
+                        .child(div().text_color(rgb(0x888888)).child(format!(
+                            "{}/{}",
+                            file.included_count(),
+                            file.matches.len()
+                        )))
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |this, _, _, cx| this.toggle_file(file_ix, cx)),
+                        ),
+                )
+                .children(file.matches.iter().enumerate().map(|(match_ix, m)| {
+                    div()
+                        .flex()
+                        .flex_row()
+                        .gap_2()
+                        .pl_4()
+                        .cursor_pointer()
+                        .hover(|s| s.bg(rgb(0x2a2d2e)))
+                        .when(!m.included, |this| this.opacity(0.5))
+                        .child(checkbox(m.included))
+                        .child(
+                            div()
+                                .min_w(px(40.0))
+                                .text_color(rgb(0x888888))
+                                .child(m.line.to_string()),
+                        )
+                        .child(render_match(m))
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |this, _, _, cx| {
+                                this.toggle_match(file_ix, match_ix, cx)
+                            }),
+                        )
+                }))
+        });
+
+        div()
+            .track_focus(&self.focus_handle)
+            .size_full()
+            .flex()
+            .flex_col()
+            .bg(rgb(0x1e1e1e))
+            .text_color(rgb(0xcccccc))
+            .text_sm()
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap_2()
+                    .p_2()
+                    .border_b_1()
+                    .border_color(rgb(0x404040))
+                    .child(format!(
+                        "Replace \"{}\" with \"{}\"",
+                        self.query, self.replacement
+                    ))
+                    .child(div().text_color(rgb(0x888888)).child(self.header_text()))
+                    .child(div().flex_1())
+                    .children(self.status.clone())
+                    .when(!applied && included > 0, |this| {
+                        this.child(button(format!("Replace {}", included)).on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(|_, _, window, cx| {
+                                window.dispatch_action(Box::new(ApplyProjectReplace), cx)
+                            }),
+                        ))
+                    })
+                    .when(applied && !undone, |this| {
+                        this.child(button("Undo".to_string()).on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(|_, _, window, cx| {
+                                window.dispatch_action(Box::new(UndoProjectReplace), cx)
+                            }),
+                        ))
+                    }),
+            )
+            .child(
+                div()
+                    .id("project-replace-results")
+                    .flex_1()
+                    .flex()
+                    .flex_col()
+                    .p_2()
+                    .overflow_y_scroll()
+                    .children(rows),
+            )
+    }
+}
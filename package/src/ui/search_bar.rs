@@ -1,11 +1,20 @@
 use gpui::*;
-use gpui::prelude::*;
+use gpui::prelude::FluentBuilder;
 use serde::Deserialize;
 
+use crate::editor::search::{SearchOptions, SearchQuery, SearchScope};
+use crate::history::{SavedSearch, SearchHistory};
+use crate::persist::JsonFile;
+use crate::ui::text_input::{TextChanged, TextInput};
+
 #[derive(Clone, PartialEq, Debug, Deserialize)]
 pub struct PerformSearch {
     pub query: String,
     pub global: bool,
+    #[serde(default)]
+    pub options: SearchOptions,
+    #[serde(default)]
+    pub scope: SearchScope,
 }
 
 impl gpui::Action for PerformSearch {
@@ -17,112 +26,743 @@ impl gpui::Action for PerformSearch {
     }
     fn boxed_clone(&self) -> Box<dyn gpui::Action> { Box::new(self.clone()) }
     fn partial_eq(&self, action: &dyn gpui::Action) -> bool {
-        action.as_any().downcast_ref::<Self>().map_or(false, |a| self == a)
+        action.as_any().downcast_ref::<Self>().is_some_and(|a| self == a)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+pub struct PerformReplace {
+    pub query: String,
+    pub replacement: String,
+    #[serde(default)]
+    pub options: SearchOptions,
+    /// Replace every match in the buffer instead of just the selected one
+    #[serde(default)]
+    pub all: bool,
+    /// Preview a replace across the whole project instead of the active buffer
+    #[serde(default)]
+    pub global: bool,
+    #[serde(default)]
+    pub scope: SearchScope,
+}
+
+impl gpui::Action for PerformReplace {
+    fn name(&self) -> &'static str { "PerformReplace" }
+    fn name_for_type() -> &'static str { "PerformReplace" }
+    fn build(value: serde_json::Value) -> gpui::Result<Box<dyn gpui::Action>> {
+        let action: PerformReplace = serde_json::from_value(value)?;
+        Ok(Box::new(action))
+    }
+    fn boxed_clone(&self) -> Box<dyn gpui::Action> { Box::new(self.clone()) }
+    fn partial_eq(&self, action: &dyn gpui::Action) -> bool {
+        action.as_any().downcast_ref::<Self>().is_some_and(|a| self == a)
+    }
+}
+
+/// Re-runs a pinned search, see `SearchHistory::saved`.
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+pub struct RunSavedSearch {
+    pub name: String,
+}
+
+impl gpui::Action for RunSavedSearch {
+    fn name(&self) -> &'static str { "RunSavedSearch" }
+    fn name_for_type() -> &'static str { "RunSavedSearch" }
+    fn build(value: serde_json::Value) -> gpui::Result<Box<dyn gpui::Action>> {
+        let action: RunSavedSearch = serde_json::from_value(value)?;
+        Ok(Box::new(action))
+    }
+    fn boxed_clone(&self) -> Box<dyn gpui::Action> { Box::new(self.clone()) }
+    fn partial_eq(&self, action: &dyn gpui::Action) -> bool {
+        action.as_any().downcast_ref::<Self>().is_some_and(|a| self == a)
     }
 }
 
+/// Removes a pinned search.
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+pub struct DeleteSavedSearch {
+    pub name: String,
+}
+
+impl gpui::Action for DeleteSavedSearch {
+    fn name(&self) -> &'static str { "DeleteSavedSearch" }
+    fn name_for_type() -> &'static str { "DeleteSavedSearch" }
+    fn build(value: serde_json::Value) -> gpui::Result<Box<dyn gpui::Action>> {
+        let action: DeleteSavedSearch = serde_json::from_value(value)?;
+        Ok(Box::new(action))
+    }
+    fn boxed_clone(&self) -> Box<dyn gpui::Action> { Box::new(self.clone()) }
+    fn partial_eq(&self, action: &dyn gpui::Action) -> bool {
+        action.as_any().downcast_ref::<Self>().is_some_and(|a| self == a)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SearchToggle {
+    Regex,
+    CaseSensitive,
+    WholeWord,
+    SmartCase,
+    PreserveCase,
+}
+
+/// One of the bar's text fields.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SearchField {
+    Query,
+    Replacement,
+    /// Include/exclude globs, project search only
+    Filter,
+    /// Name for the search being pinned
+    PinName,
+}
+
 #[derive(Clone)]
 pub struct SearchBar {
-    pub query: String,
+    pub query: Entity<TextInput>,
     pub is_global: bool,
     pub is_visible: bool,
     pub focus_handle: FocusHandle,
+    pub options: SearchOptions,
+    /// Why the current query can't be compiled, shown inline
+    pub error: Option<String>,
+    pub replacement: Entity<TextInput>,
+    pub replace_visible: bool,
+    /// Comma separated globs limiting project search, `!` excludes
+    pub filter: Entity<TextInput>,
+    /// Number of matches in the active buffer, filled in by the workspace
+    pub match_count: Option<usize>,
+    /// The selected match and what it would become, filled in by the workspace
+    pub preview: Option<(String, String)>,
+    /// Result of the last replace-all
+    pub status: Option<String>,
+    /// Position in `SearchHistory::queries` while recalling with up/down
+    history_index: Option<usize>,
+    /// What was typed before recalling history, restored by going past the newest entry
+    draft: String,
+    /// Name for the search being pinned
+    pin_name: Entity<TextInput>,
+    /// The "Pin as:" row is shown
+    pinning: bool,
 }
 
 impl SearchBar {
     pub fn new(cx: &mut Context<MainScreen>) -> Self {
+        let query = cx.new(|cx| TextInput::new("Type to search...", cx));
+        let replacement = cx.new(|cx| TextInput::new("Replacement ($1 for groups)", cx));
+        let filter = cx.new(|cx| TextInput::new("*.rs, !tests/** (tab to edit)", cx));
+        let pin_name = cx.new(|cx| TextInput::new("Name (enter to save, escape to cancel)", cx));
+
+        cx.subscribe(&query, |this, _, _: &TextChanged, cx| {
+            this.search_bar.history_index = None;
+            this.search_bar.query_changed(cx);
+        })
+        .detach();
+        // Neither moves the selection, only what is shown around the query changes
+        cx.subscribe(&replacement, |this, _, _: &TextChanged, cx| {
+            this.refresh_search_preview(cx)
+        })
+        .detach();
+        cx.subscribe(&filter, |this, _, _: &TextChanged, cx| {
+            if this.search_bar.is_global {
+                cx.dispatch_action(&CancelProjectSearch);
+            }
+            cx.notify();
+        })
+        .detach();
+
         Self {
-            query: String::new(),
+            query,
             is_global: false, // Default to local
             is_visible: false,
             focus_handle: cx.focus_handle(),
+            options: SearchOptions {
+                smart_case: true,
+                ..SearchOptions::default()
+            },
+            error: None,
+            replacement,
+            replace_visible: false,
+            filter,
+            match_count: None,
+            preview: None,
+            status: None,
+            history_index: None,
+            draft: String::new(),
+            pin_name,
+            pinning: false,
         }
     }
 
     pub fn toggle(&mut self, global: bool, window: &mut Window, cx: &mut Context<MainScreen>) {
         if self.is_visible && self.is_global == global {
+            self.remember(cx);
             self.is_visible = false;
             // logic to return focus to editor would go here
         } else {
             self.is_visible = true;
             self.is_global = global;
-            self.query.clear();
-            window.focus(&self.focus_handle);
+            self.set_query(String::new(), cx);
+            self.error = None;
+            self.match_count = None;
+            self.preview = None;
+            self.status = None;
+            self.focus_field(SearchField::Query, window, cx);
+        }
+        self.replace_visible = false;
+        self.history_index = None;
+        self.pinning = false;
+        cx.notify();
+    }
+
+    /// Opens the find bar with the replace field shown.
+    pub fn toggle_replace(&mut self, global: bool, window: &mut Window, cx: &mut Context<MainScreen>) {
+        if self.is_visible && self.replace_visible && self.is_global == global {
+            self.remember(cx);
+            self.is_visible = false;
+            self.replace_visible = false;
+        } else {
+            if !self.is_visible || self.is_global != global {
+                self.set_query(String::new(), cx);
+                self.error = None;
+                self.match_count = None;
+                self.preview = None;
+            }
+            self.is_visible = true;
+            self.is_global = global;
+            self.replace_visible = true;
+            self.status = None;
+            self.focus_field(SearchField::Query, window, cx);
+        }
+        cx.notify();
+    }
+
+    pub fn toggle_option(&mut self, option: SearchToggle, cx: &mut Context<MainScreen>) {
+        let options = &mut self.options;
+        match option {
+            SearchToggle::Regex => options.regex = !options.regex,
+            SearchToggle::CaseSensitive => options.case_sensitive = !options.case_sensitive,
+            SearchToggle::WholeWord => options.whole_word = !options.whole_word,
+            SearchToggle::SmartCase => options.smart_case = !options.smart_case,
+            SearchToggle::PreserveCase => options.preserve_case = !options.preserve_case,
+        }
+        self.query_changed(cx);
+    }
+
+    fn input(&self, field: SearchField) -> &Entity<TextInput> {
+        match field {
+            SearchField::Query => &self.query,
+            SearchField::Replacement => &self.replacement,
+            SearchField::Filter => &self.filter,
+            SearchField::PinName => &self.pin_name,
+        }
+    }
+
+    /// The field holding focus, the query if none does.
+    pub fn active_field(&self, window: &Window, cx: &App) -> SearchField {
+        [SearchField::Replacement, SearchField::Filter, SearchField::PinName]
+            .into_iter()
+            .find(|field| self.input(*field).focus_handle(cx).is_focused(window))
+            .unwrap_or(SearchField::Query)
+    }
+
+    fn focus_field(&self, field: SearchField, window: &mut Window, cx: &mut App) {
+        window.focus(&self.input(field).focus_handle(cx));
+    }
+
+    /// Tab cycles through the fields that are currently shown.
+    fn focus_next_field(&mut self, window: &mut Window, cx: &mut Context<MainScreen>) {
+        let next = match self.active_field(window, cx) {
+            SearchField::Query if self.replace_visible => SearchField::Replacement,
+            SearchField::Query | SearchField::Replacement if self.is_global => SearchField::Filter,
+            _ => SearchField::Query,
+        };
+        self.focus_field(next, window, cx);
+    }
+
+    pub fn query_text(&self, cx: &App) -> String {
+        self.query.read(cx).text().to_string()
+    }
+
+    pub fn replacement_text(&self, cx: &App) -> String {
+        self.replacement.read(cx).text().to_string()
+    }
+
+    fn filter_text(&self, cx: &App) -> String {
+        self.filter.read(cx).text().to_string()
+    }
+
+    /// Replaces the query without treating it as typed, see `query_changed`.
+    fn set_query(&self, query: String, cx: &mut Context<MainScreen>) {
+        self.query.update(cx, |input, cx| input.set_text(query, cx));
+    }
+
+    /// Adds the query to the persisted history.
+    fn remember(&mut self, cx: &mut Context<MainScreen>) {
+        self.history_index = None;
+        let query = self.query_text(cx);
+        if query.is_empty() || self.error.is_some() {
+            return;
+        }
+        SearchHistory::update(cx, |history| {
+            history.push(&query);
+        });
+    }
+
+    /// Steps through past queries, newest first going `older`.
+    fn recall(&mut self, older: bool, cx: &mut Context<MainScreen>) {
+        let queries = SearchHistory::get(cx).queries;
+        if queries.is_empty() {
+            return;
+        }
+        let index = match (self.history_index, older) {
+            (None, false) => return,
+            (None, true) => {
+                self.draft = self.query_text(cx);
+                queries.len() - 1
+            }
+            (Some(0), true) => 0,
+            (Some(i), true) => i - 1,
+            (Some(i), false) if i + 1 < queries.len() => i + 1,
+            (Some(_), false) => {
+                self.history_index = None;
+                let draft = std::mem::take(&mut self.draft);
+                self.set_query(draft, cx);
+                self.query_changed(cx);
+                return;
+            }
+        };
+        self.history_index = Some(index);
+        self.set_query(queries[index].clone(), cx);
+        self.query_changed(cx);
+    }
+
+    /// Starts naming the current search so it can be pinned, or pins it if
+    /// a name was already entered.
+    fn pin(&mut self, window: &mut Window, cx: &mut Context<MainScreen>) {
+        let query = self.query_text(cx);
+        if query.is_empty() {
+            return;
+        }
+        if !self.pinning {
+            self.pinning = true;
+            self.pin_name.update(cx, |input, cx| {
+                input.set_text(query, cx);
+                input.select_all_text(cx);
+            });
+            self.focus_field(SearchField::PinName, window, cx);
+            return;
+        }
+        self.cancel_pin(window, cx);
+        let name = self.pin_name.read(cx).text().trim().to_string();
+        if name.is_empty() {
+            return;
+        }
+
+        let search = SavedSearch {
+            name,
+            query,
+            global: self.is_global,
+            options: self.options,
+            filter: self.filter_text(cx),
+        };
+        self.status = Some(format!("Pinned \"{}\"", search.name));
+        SearchHistory::update(cx, |history| history.pin(search));
+    }
+
+    fn cancel_pin(&mut self, window: &mut Window, cx: &mut Context<MainScreen>) {
+        self.pinning = false;
+        self.focus_field(SearchField::Query, window, cx);
+    }
+
+    /// Shows a saved search in the bar, ready to run.
+    pub fn load_saved(&mut self, search: &SavedSearch, window: &mut Window, cx: &mut Context<MainScreen>) {
+        self.is_visible = true;
+        self.is_global = search.global;
+        self.set_query(search.query.clone(), cx);
+        self.options = search.options;
+        let filter = search.filter.clone();
+        self.filter.update(cx, |input, cx| input.set_text(filter, cx));
+        self.replace_visible = false;
+        self.history_index = None;
+        self.pinning = false;
+        self.status = None;
+        self.error = SearchQuery::new(&search.query, self.options)
+            .err()
+            .map(|e| first_line(&e.to_string()));
+        self.focus_field(SearchField::Query, window, cx);
+        cx.notify();
+    }
+
+    /// Re-validates the query and, for local search, runs it right away.
+    /// Project search only runs on enter, but a running one is stale now.
+    fn query_changed(&mut self, cx: &mut Context<MainScreen>) {
+        self.error = SearchQuery::new(&self.query_text(cx), self.options)
+            .err()
+            .map(|e| first_line(&e.to_string()));
+
+        if !self.is_global {
+            if self.error.is_none() {
+                let action = self.search_action(cx);
+                cx.dispatch_action(&action);
+            }
+        } else {
+            cx.dispatch_action(&CancelProjectSearch);
         }
         cx.notify();
     }
+
+    pub fn search_action(&self, cx: &App) -> PerformSearch {
+        PerformSearch {
+            query: self.query_text(cx),
+            global: self.is_global,
+            options: self.options,
+            scope: self.scope(cx),
+        }
+    }
+
+    fn scope(&self, cx: &App) -> SearchScope {
+        SearchScope::parse(&self.filter_text(cx))
+    }
+
+    pub fn replace_action(&self, all: bool, cx: &App) -> PerformReplace {
+        PerformReplace {
+            query: self.query_text(cx),
+            replacement: self.replacement_text(cx),
+            options: self.options,
+            all,
+            global: self.is_global,
+            scope: self.scope(cx),
+        }
+    }
+
+    /// Project replace always covers every match, it is narrowed down in the preview
+    fn replace(&mut self, all: bool, cx: &mut Context<MainScreen>) {
+        if self.error.is_none() && !self.query.read(cx).text().is_empty() {
+            let action = self.replace_action(all || self.is_global, cx);
+            cx.dispatch_action(&action);
+        }
+    }
+
+    fn is_enabled(&self, option: SearchToggle) -> bool {
+        match option {
+            SearchToggle::Regex => self.options.regex,
+            SearchToggle::CaseSensitive => self.options.case_sensitive,
+            SearchToggle::WholeWord => self.options.whole_word,
+            SearchToggle::SmartCase => self.options.smart_case,
+            SearchToggle::PreserveCase => self.options.preserve_case,
+        }
+    }
+}
+
+/// regex errors are multi-line with a caret diagram, keep the summary for the bar
+fn first_line(message: &str) -> String {
+    message
+        .lines()
+        .rfind(|l| l.starts_with("error:"))
+        .unwrap_or_else(|| message.lines().next().unwrap_or(message))
+        .to_string()
 }
 
-use crate::ui::workspace::MainScreen;
+use crate::ui::workspace::{CancelProjectSearch, MainScreen};
 
 impl SearchBar {
-    pub fn render(&self, _window: &mut Window, cx: &mut Context<MainScreen>) -> impl IntoElement {
+    fn render_toggle(
+        &self,
+        option: SearchToggle,
+        label: &'static str,
+        tooltip_key: &'static str,
+        cx: &mut Context<MainScreen>,
+    ) -> impl IntoElement {
+        let enabled = self.is_enabled(option);
+        div()
+            .id(tooltip_key)
+            .px_1()
+            .rounded_sm()
+            .text_xs()
+            .cursor_pointer()
+            .text_color(if enabled { rgb(0xffffff) } else { rgb(0x888888) })
+            .bg(if enabled { rgb(0x04395e) } else { rgb(0x252526) })
+            .hover(|s| s.bg(rgb(0x3a3a3a)))
+            .child(label)
+            .on_mouse_down(
+                MouseButton::Left,
+                cx.listener(move |this, _, _, cx| {
+                    this.search_bar.toggle_option(option, cx);
+                }),
+            )
+    }
+
+    pub fn render(&self, window: &mut Window, cx: &mut Context<MainScreen>) -> impl IntoElement {
         if !self.is_visible {
              return div();
         }
- 
-        let query = self.query.clone();
-        
+
+        let active_field = self.active_field(window, cx);
+
         div()
             .track_focus(&self.focus_handle)
             .flex()
-            .flex_row()
-            .items_center()
+            .flex_col()
             .bg(rgb(0x252526))
             .border_1()
             .border_color(rgb(0x454545))
             .p_2()
             .gap_2()
-            .on_key_down(cx.listener(|this, event: &KeyDownEvent, _, cx| {
+            // Editing keys are handled by the focused field, the rest bubble up here
+            .on_key_down(cx.listener(|this, event: &KeyDownEvent, window, cx| {
                 let text = event.keystroke.key.as_str();
+
+                // alt-c / alt-w / alt-r / alt-s flip the matching options
+                if event.keystroke.modifiers.alt {
+                    let option = match text {
+                        "c" => Some(SearchToggle::CaseSensitive),
+                        "w" => Some(SearchToggle::WholeWord),
+                        "r" => Some(SearchToggle::Regex),
+                        "s" => Some(SearchToggle::SmartCase),
+                        "p" => Some(SearchToggle::PreserveCase),
+                        _ => None,
+                    };
+                    if let Some(option) = option {
+                        this.search_bar.toggle_option(option, cx);
+                    } else if text == "enter" {
+                        // alt-enter pins the search under a name
+                        this.search_bar.pin(window, cx);
+                    } else {
+                        return;
+                    }
+                    cx.stop_propagation();
+                    cx.notify();
+                    return;
+                }
+
+                let bar = &mut this.search_bar;
+                let field = bar.active_field(window, cx);
                 match text {
-                    "backspace" => {
-                        this.search_bar.query.pop();
+                    "enter" if field == SearchField::PinName => bar.pin(window, cx),
+                    "escape" if field == SearchField::PinName => bar.cancel_pin(window, cx),
+                    "up" | "down" if field == SearchField::Query => {
+                        bar.recall(text == "up", cx);
                     }
-                    "escape" => {
-                        this.search_bar.is_visible = false;
+                    "tab" => bar.focus_next_field(window, cx),
+                    // ctrl-enter replaces everything, enter in the replace field replaces the selected match
+                    "enter" if bar.replace_visible && event.keystroke.modifiers.control => {
+                        bar.remember(cx);
+                        bar.replace(true, cx);
                     }
-                    _ => {
-                        if text.len() == 1 {
-                             this.search_bar.query.push_str(text);
-                             // Dispatch local search immediately
-                             if !this.search_bar.is_global {
-                                 cx.dispatch_action(&PerformSearch {
-                                     query: this.search_bar.query.clone(),
-                                     global: false,
-                                 });
-                             }
+                    "enter" if field == SearchField::Replacement => {
+                        bar.remember(cx);
+                        bar.replace(false, cx);
+                    }
+                    // Force search (useful for global which might be heavy)
+                    "enter" => {
+                        if bar.error.is_none() {
+                            bar.remember(cx);
+                            let action = bar.search_action(cx);
+                            cx.dispatch_action(&action);
                         }
                     }
+                    "escape" => {
+                        bar.remember(cx);
+                        bar.is_visible = false;
+                    }
+                    _ => return,
                 }
-                
-                // If Enter, force search (useful for global which might be heavy)
-                if text == "enter" {
-                    cx.dispatch_action(&PerformSearch {
-                        query: this.search_bar.query.clone(),
-                        global: this.search_bar.is_global,
-                    });
-                }
-                
+
+                cx.stop_propagation();
                 cx.notify();
             }))
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap_2()
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(0xcccccc))
+                            .min_w(px(80.0))
+                            .child(if self.is_global { "Global Search:" } else { "Find:" })
+                    )
+                    .child(render_field(
+                        &self.query,
+                        active_field == SearchField::Query,
+                        self.error.is_some(),
+                    ))
+                    .child(self.render_toggle(SearchToggle::CaseSensitive, "Aa", "search-case", cx))
+                    .child(self.render_toggle(SearchToggle::WholeWord, "W", "search-word", cx))
+                    .child(self.render_toggle(SearchToggle::Regex, ".*", "search-regex", cx))
+                    .child(self.render_toggle(SearchToggle::SmartCase, "Smart", "search-smart-case", cx))
+                    .child(
+                        div()
+                            .id("search-pin")
+                            .px_1()
+                            .rounded_sm()
+                            .text_xs()
+                            .text_color(rgb(0x888888))
+                            .cursor_pointer()
+                            .hover(|s| s.bg(rgb(0x3a3a3a)))
+                            .child("Pin")
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(|this, _, window, cx| {
+                                    this.search_bar.pin(window, cx);
+                                    cx.notify();
+                                }),
+                            )
+                    )
+                    .children(self.match_count.filter(|_| !self.is_global).map(|count| {
+                        div()
+                            .text_xs()
+                            .text_color(rgb(0x888888))
+                            .child(match count {
+                                1 => "1 match".to_string(),
+                                n => format!("{} matches", n),
+                            })
+                    }))
+                    .children(self.status.clone().filter(|_| !self.replace_visible).map(|status| {
+                        div()
+                            .text_xs()
+                            .text_color(rgb(0x888888))
+                            .child(status)
+                    }))
+                    .children(self.error.clone().map(|error| {
+                        div()
+                            .text_xs()
+                            .text_color(rgb(0xe74c3c))
+                            .child(error)
+                    }))
+            )
+            .when(self.replace_visible, |this| this.child(self.render_replace_row(active_field, cx)))
+            .when(self.is_global, |this| this.child(self.render_filter_row(active_field)))
+            .when(self.pinning, |this| this.child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap_2()
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(0xcccccc))
+                            .min_w(px(80.0))
+                            .child("Pin as:")
+                    )
+                    .child(render_field(
+                        &self.pin_name,
+                        active_field == SearchField::PinName,
+                        false,
+                    ))
+            ))
+    }
+
+    fn render_filter_row(&self, active_field: SearchField) -> impl IntoElement {
+        div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap_2()
             .child(
                 div()
                     .text_xs()
                     .text_color(rgb(0xcccccc))
-                    .child(if self.is_global { "Global Search:" } else { "Find:" })
+                    .min_w(px(80.0))
+                    .child("Files:")
             )
+            .child(render_field(
+                &self.filter,
+                active_field == SearchField::Filter,
+                false,
+            ))
+    }
+
+    fn render_replace_row(&self, active_field: SearchField, cx: &mut Context<MainScreen>) -> impl IntoElement {
+        div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap_2()
             .child(
                 div()
-                    .text_sm()
-                    .text_color(rgb(0xffffff))
-                    .bg(rgb(0x1e1e1e))
-                    .border_1()
-                    .border_color(rgb(0x555555))
+                    .text_xs()
+                    .text_color(rgb(0xcccccc))
+                    .min_w(px(80.0))
+                    .child("Replace:")
+            )
+            .child(render_field(
+                &self.replacement,
+                active_field == SearchField::Replacement,
+                false,
+            ))
+            .child(self.render_toggle(SearchToggle::PreserveCase, "AB", "search-preserve-case", cx))
+            .when(!self.is_global, |this| this.child(
+                div()
+                    .id("replace-next")
+                    .px_2()
+                    .rounded_sm()
+                    .text_xs()
+                    .text_color(rgb(0xcccccc))
+                    .cursor_pointer()
+                    .hover(|s| s.bg(rgb(0x3a3a3a)))
+                    .child("Replace")
+                    .on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(|this, _, _, cx| this.search_bar.replace(false, cx)),
+                    )
+            ))
+            .child(
+                div()
+                    .id("replace-all")
                     .px_2()
-                    .py_1()
-                    .min_w(px(200.0))
-                    .child(if query.is_empty() { "Type to search...".to_string() } else { query })
+                    .rounded_sm()
+                    .text_xs()
+                    .text_color(rgb(0xcccccc))
+                    .cursor_pointer()
+                    .hover(|s| s.bg(rgb(0x3a3a3a)))
+                    .child(if self.is_global { "Preview Replace" } else { "Replace All" })
+                    .on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(|this, _, _, cx| this.search_bar.replace(true, cx)),
+                    )
             )
+            // Preview of what the selected match turns into
+            .children(self.preview.clone().map(|(from, to)| {
+                div()
+                    .flex()
+                    .flex_row()
+                    .gap_1()
+                    .text_xs()
+                    .child(div().text_color(rgb(0xe06c75)).line_through().child(from))
+                    .child(div().text_color(rgb(0x888888)).child("→"))
+                    .child(div().text_color(rgb(0x98c379)).child(to))
+            }))
+            .children(self.status.clone().map(|status| {
+                div()
+                    .text_xs()
+                    .text_color(rgb(0x888888))
+                    .child(status)
+            }))
     }
 }
+
+fn render_field(input: &Entity<TextInput>, active: bool, invalid: bool) -> impl IntoElement {
+    div()
+        .text_sm()
+        .text_color(rgb(0xffffff))
+        .bg(rgb(0x1e1e1e))
+        .border_1()
+        .border_color(if invalid {
+            rgb(0xe74c3c)
+        } else if active {
+            rgb(0x007acc)
+        } else {
+            rgb(0x555555)
+        })
+        .px_2()
+        .py_1()
+        .flex_1()
+        .min_w(px(200.0))
+        .max_w(px(400.0))
+        .child(input.clone())
+}
@@ -0,0 +1,387 @@
+use gpui::{
+    AnyElement, Context, FocusHandle, FontWeight, InteractiveElement, IntoElement, MouseButton,
+    ParentElement, Render, ScrollStrategy, Styled, UniformListScrollHandle, Window, actions, div,
+    prelude::FluentBuilder, px, rgb, uniform_list,
+};
+use std::iter;
+use std::ops::Range;
+use std::path::PathBuf;
+
+use crate::editor::search::SearchResult;
+use crate::roots::WorkspaceRoots;
+use crate::ui::workspace::OpenPath;
+
+actions!(SearchResultsView, [NextResult, PreviousResult]);
+
+/// Height of every line in the list; it is virtualized, so they all match.
+const ROW_HEIGHT: f32 = 20.0;
+
+/// Matches found in one file, in line order.
+pub struct FileResults {
+    pub path: PathBuf,
+    pub results: Vec<SearchResult>,
+}
+
+/// Lines a result takes up: its context and the matching line.
+fn result_rows(result: &SearchResult) -> usize {
+    result.context_before.len() + 1 + result.context_after.len()
+}
+
+/// One line of the flattened list.
+enum Row<'a> {
+    Header(usize),
+    Context {
+        file_ix: usize,
+        ix: usize,
+        number: usize,
+        text: &'a str,
+    },
+    Match {
+        file_ix: usize,
+        ix: usize,
+    },
+    /// Space after a file's results
+    Gap,
+}
+
+/// Rows for context lines of a result, numbered from `first`.
+fn context_rows(
+    file_ix: usize,
+    ix: usize,
+    first: usize,
+    lines: &[String],
+) -> impl Iterator<Item = Row<'_>> {
+    lines.iter().enumerate().map(move |(i, text)| Row::Context {
+        file_ix,
+        ix,
+        number: first + i,
+        text,
+    })
+}
+
+/// Project search results, filled in as files are searched.
+pub struct SearchResultsView {
+    pub query: String,
+    /// Ordered by path, whatever order the files were searched in
+    pub files: Vec<FileResults>,
+    /// First row of each file in the flattened list
+    row_starts: Vec<usize>,
+    row_count: usize,
+    pub focus_handle: FocusHandle,
+    /// Folders searched, for labelling file headers
+    roots: Option<WorkspaceRoots>,
+    /// (file, result) of the result last opened
+    selected: Option<(usize, usize)>,
+    searching: bool,
+    /// Bumped for every new search so batches from a superseded one are dropped
+    generation: usize,
+    scroll_handle: UniformListScrollHandle,
+}
+
+impl SearchResultsView {
+    pub fn new(cx: &mut Context<Self>) -> Self {
+        Self {
+            query: String::new(),
+            files: Vec::new(),
+            row_starts: Vec::new(),
+            row_count: 0,
+            focus_handle: cx.focus_handle(),
+            roots: None,
+            selected: None,
+            searching: false,
+            generation: 0,
+            scroll_handle: UniformListScrollHandle::new(),
+        }
+    }
+
+    /// Clears the view for a new search and returns its generation.
+    pub fn start(&mut self, query: String, roots: WorkspaceRoots, cx: &mut Context<Self>) -> usize {
+        self.query = query;
+        self.roots = Some(roots);
+        self.files.clear();
+        self.row_starts.clear();
+        self.row_count = 0;
+        self.selected = None;
+        self.searching = true;
+        self.generation += 1;
+        cx.notify();
+        self.generation
+    }
+
+    /// Adds one file's results. Returns false once a newer search has
+    /// started, telling the sender to stop.
+    pub fn push(
+        &mut self,
+        generation: usize,
+        results: Vec<SearchResult>,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        if generation != self.generation {
+            return false;
+        }
+        if let Some(first) = results.first() {
+            let path = first.path.clone();
+            let at = self.files.partition_point(|f| f.path < path);
+            self.files.insert(at, FileResults { path, results });
+            if let Some((file_ix, _)) = &mut self.selected
+                && *file_ix >= at
+            {
+                *file_ix += 1;
+            }
+            self.count_rows();
+            cx.notify();
+        }
+        true
+    }
+
+    fn count_rows(&mut self) {
+        self.row_starts.clear();
+        let mut row = 0;
+        for file in &self.files {
+            self.row_starts.push(row);
+            row += 2 + file.results.iter().map(result_rows).sum::<usize>();
+        }
+        self.row_count = row;
+    }
+
+    /// The rows of one file: its header, each result's lines, then a gap.
+    fn file_rows(&self, file_ix: usize) -> impl Iterator<Item = Row<'_>> {
+        let results = self.files[file_ix].results.iter().enumerate();
+        iter::once(Row::Header(file_ix))
+            .chain(results.flat_map(move |(ix, result)| {
+                let before = result.line - result.context_before.len();
+                context_rows(file_ix, ix, before, &result.context_before)
+                    .chain(iter::once(Row::Match { file_ix, ix }))
+                    .chain(context_rows(
+                        file_ix,
+                        ix,
+                        result.line + 1,
+                        &result.context_after,
+                    ))
+            }))
+            .chain(iter::once(Row::Gap))
+    }
+
+    /// Row of the matching line of a result.
+    fn match_row(&self, file_ix: usize, ix: usize) -> usize {
+        let results = &self.files[file_ix].results;
+        self.row_starts[file_ix]
+            + 1
+            + results[..ix].iter().map(result_rows).sum::<usize>()
+            + results[ix].context_before.len()
+    }
+
+    pub fn finish(&mut self, generation: usize, cx: &mut Context<Self>) {
+        if generation == self.generation {
+            self.searching = false;
+            cx.notify();
+        }
+    }
+
+    pub fn result_count(&self) -> usize {
+        self.files.iter().map(|f| f.results.len()).sum()
+    }
+
+    /// Moves the selection `forward` or back by one result, wrapping at
+    /// either end, and returns where to open it.
+    pub fn select_adjacent(&mut self, forward: bool, cx: &mut Context<Self>) -> Option<OpenPath> {
+        let total = self.result_count();
+        if total == 0 {
+            return None;
+        }
+
+        let current = self.selected.map(|(file_ix, ix)| {
+            self.files[..file_ix]
+                .iter()
+                .map(|f| f.results.len())
+                .sum::<usize>()
+                + ix
+        });
+        let next = match (current, forward) {
+            (None, true) => 0,
+            (None, false) => total - 1,
+            (Some(i), true) => (i + 1) % total,
+            (Some(i), false) => (i + total - 1) % total,
+        };
+
+        let mut remaining = next;
+        for (file_ix, file) in self.files.iter().enumerate() {
+            if remaining < file.results.len() {
+                return Some(self.select(file_ix, remaining, cx));
+            }
+            remaining -= file.results.len();
+        }
+        None
+    }
+
+    fn select(&mut self, file_ix: usize, ix: usize, cx: &mut Context<Self>) -> OpenPath {
+        self.selected = Some((file_ix, ix));
+        self.scroll_handle
+            .scroll_to_item(self.match_row(file_ix, ix), ScrollStrategy::Center);
+        cx.notify();
+        open_action(&self.files[file_ix].results[ix])
+    }
+
+    fn status_text(&self) -> String {
+        let count = self.result_count();
+        let mut text = format!(
+            "{} result{} in {} file{}",
+            count,
+            if count == 1 { "" } else { "s" },
+            self.files.len(),
+            if self.files.len() == 1 { "" } else { "s" },
+        );
+        if self.searching {
+            text.push_str(", searching...");
+        }
+        text
+    }
+}
+
+/// Opens the file with the cursor at the start of the match.
+fn open_action(result: &SearchResult) -> OpenPath {
+    let column = result.line_content[..result.column.start].chars().count() + 1;
+    OpenPath::at(result.path.clone(), result.line, column)
+}
+
+fn render_line(number: usize, content: impl IntoElement) -> gpui::Div {
+    div()
+        .flex()
+        .flex_row()
+        .gap_2()
+        .child(
+            div()
+                .min_w(px(40.0))
+                .text_right()
+                .text_color(rgb(0x666666))
+                .child(number.to_string()),
+        )
+        .child(content)
+}
+
+fn render_match(result: &SearchResult) -> gpui::Div {
+    let line = &result.line_content;
+    let column = result.column.clone();
+    render_line(
+        result.line,
+        div()
+            .flex()
+            .flex_row()
+            .child(line[..column.start].to_string())
+            .child(
+                div()
+                    .bg(rgb(0x613214))
+                    .text_color(rgb(0xffffff))
+                    .child(line[column.clone()].to_string()),
+            )
+            .child(line[column.end..].to_string()),
+    )
+}
+
+impl SearchResultsView {
+    fn render_rows(&self, range: Range<usize>, cx: &mut Context<Self>) -> Vec<AnyElement> {
+        if self.files.is_empty() {
+            return Vec::new();
+        }
+        let first = self
+            .row_starts
+            .partition_point(|&start| start <= range.start)
+            - 1;
+        (first..self.files.len())
+            .flat_map(|file_ix| self.file_rows(file_ix))
+            .skip(range.start - self.row_starts[first])
+            .take(range.len())
+            .map(|row| self.render_row(row, cx))
+            .collect()
+    }
+
+    fn render_row(&self, row: Row, cx: &mut Context<Self>) -> AnyElement {
+        let line = div()
+            .h(px(ROW_HEIGHT))
+            .flex()
+            .flex_row()
+            .items_center()
+            .overflow_hidden()
+            .whitespace_nowrap();
+        let (file_ix, ix, content) = match row {
+            Row::Header(file_ix) => {
+                let file = &self.files[file_ix];
+                return line
+                    .gap_2()
+                    .font_weight(FontWeight::BOLD)
+                    .child(match &self.roots {
+                        Some(roots) => roots.display_path(&file.path),
+                        None => file.path.to_string_lossy().to_string(),
+                    })
+                    .child(
+                        div()
+                            .text_color(rgb(0x888888))
+                            .child(file.results.len().to_string()),
+                    )
+                    .into_any_element();
+            }
+            Row::Gap => return line.into_any_element(),
+            Row::Context {
+                file_ix,
+                ix,
+                number,
+                text,
+            } => (
+                file_ix,
+                ix,
+                render_line(number, text.to_string()).text_color(rgb(0x888888)),
+            ),
+            Row::Match { file_ix, ix } => {
+                (file_ix, ix, render_match(&self.files[file_ix].results[ix]))
+            }
+        };
+        line.cursor_pointer()
+            .hover(|s| s.bg(rgb(0x2a2d2e)))
+            .when(self.selected == Some((file_ix, ix)), |this| {
+                this.bg(rgb(0x04395e))
+            })
+            .child(content)
+            .on_mouse_down(
+                MouseButton::Left,
+                cx.listener(move |this, _, window, cx| {
+                    let action = this.select(file_ix, ix, cx);
+                    window.dispatch_action(Box::new(action), cx);
+                }),
+            )
+            .into_any_element()
+    }
+}
+
+impl Render for SearchResultsView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        div()
+            .track_focus(&self.focus_handle)
+            .size_full()
+            .flex()
+            .flex_col()
+            .bg(rgb(0x1e1e1e))
+            .text_color(rgb(0xcccccc))
+            .text_sm()
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .gap_2()
+                    .p_2()
+                    .border_b_1()
+                    .border_color(rgb(0x404040))
+                    .child(format!("Search \"{}\"", self.query))
+                    .child(div().text_color(rgb(0x888888)).child(self.status_text())),
+            )
+            .child(
+                uniform_list(
+                    "search-results",
+                    self.row_count,
+                    cx.processor(|this, range, _, cx| this.render_rows(range, cx)),
+                )
+                .flex_1()
+                .p_2()
+                .track_scroll(self.scroll_handle.clone()),
+            )
+    }
+}
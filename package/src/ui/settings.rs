@@ -1,18 +1,51 @@
 use gpui::{
-    div, px, rgb, IntoElement, Render, Styled, Window, Context, InteractiveElement, ParentElement,
-    EventEmitter,
+    div, px, rgb, AppContext, Context, Entity, InteractiveElement, IntoElement, MouseButton,
+    ParentElement, Render, Styled, Subscription, Window,
 };
 
-pub struct SettingsView;
+use crate::settings::Settings;
+use crate::ui::text_input::{TextChanged, TextInput};
+
+pub struct SettingsView {
+    autosave_delay: Entity<TextInput>,
+    /// The delay field doesn't hold a positive number of milliseconds
+    delay_invalid: bool,
+    _delay_subscription: Subscription,
+}
 
 impl SettingsView {
-    pub fn new() -> Self {
-        Self
+    pub fn new(cx: &mut Context<Self>) -> Self {
+        let autosave_delay = cx.new(|cx| {
+            let mut input = TextInput::new("1000", cx);
+            input.set_text(Settings::get(cx).autosave_delay_ms.to_string(), cx);
+            input
+        });
+        let delay_subscription = cx.subscribe(&autosave_delay, |this, input, _: &TextChanged, cx| {
+            let delay = input.read(cx).text().trim().parse::<u64>().ok().filter(|ms| *ms > 0);
+            this.delay_invalid = delay.is_none();
+            if let Some(delay) = delay {
+                let mut settings = Settings::get(cx);
+                settings.autosave_delay_ms = delay;
+                if let Err(e) = settings.save() {
+                    eprintln!("Failed to save settings: {}", e);
+                }
+                cx.set_global(settings);
+            }
+            cx.notify();
+        });
+
+        Self {
+            autosave_delay,
+            delay_invalid: false,
+            _delay_subscription: delay_subscription,
+        }
     }
 }
 
 impl Render for SettingsView {
-    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let settings = Settings::get(cx);
+
         div()
             .flex()
             .flex_col()
@@ -38,6 +71,63 @@ impl Render for SettingsView {
                     .child(
                          div().child("Theme: Dark (Not editable yet)")
                     )
+                    .child(
+                        div()
+                            .cursor_pointer()
+                            .hover(|s| s.text_color(rgb(0xffffff)))
+                            .child(format!("Autosave: {} (click to change)", settings.autosave.label()))
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(|_, _, _, cx| {
+                                    let mut settings = Settings::get(cx);
+                                    settings.autosave = settings.autosave.next();
+                                    if let Err(e) = settings.save() {
+                                        eprintln!("Failed to save settings: {}", e);
+                                    }
+                                    cx.set_global(settings);
+                                    cx.notify();
+                                }),
+                            ),
+                    )
+                    .child(
+                        div()
+                            .flex()
+                            .flex_row()
+                            .items_center()
+                            .gap_2()
+                            .child("Autosave delay (ms):")
+                            .child(
+                                div()
+                                    .w(px(100.0))
+                                    .px_2()
+                                    .bg(rgb(0x252526))
+                                    .border_1()
+                                    .border_color(if self.delay_invalid { rgb(0xe74c3c) } else { rgb(0x555555) })
+                                    .text_color(rgb(0xffffff))
+                                    .child(self.autosave_delay.clone())
+                            )
+                    )
+                    .child(
+                        div()
+                            .cursor_pointer()
+                            .hover(|s| s.text_color(rgb(0xffffff)))
+                            .child(format!(
+                                "Search index: {} (click to change)",
+                                if settings.search_index { "On" } else { "Off" }
+                            ))
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(|_, _, _, cx| {
+                                    let mut settings = Settings::get(cx);
+                                    settings.search_index = !settings.search_index;
+                                    if let Err(e) = settings.save() {
+                                        eprintln!("Failed to save settings: {}", e);
+                                    }
+                                    cx.set_global(settings);
+                                    cx.notify();
+                                }),
+                            ),
+                    )
             )
     }
 }
@@ -0,0 +1,586 @@
+use futures::channel::oneshot;
+use gpui::{
+    AppContext, Context, Entity, EventEmitter, FocusHandle, FontWeight, InteractiveElement,
+    IntoElement, KeyDownEvent, MouseButton, ParentElement, Render, StatefulInteractiveElement,
+    Styled, Window, actions, div, prelude::FluentBuilder, px, rgb,
+};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::editor::git::{
+    Change, ChangeKind, FileDiff, Hunk, SourceControl, changed_lines, original_lines,
+};
+use crate::pools::Pools;
+use crate::roots::root_name;
+use crate::ui::text_input::TextInput;
+use crate::ui::workspace::OpenPath;
+
+actions!(SourceControlView, [CommitStaged, RefreshSourceControl]);
+
+/// Emitted after the index or HEAD changed from the panel, so git
+/// decorations elsewhere can catch up.
+pub struct RepositoryChanged;
+
+/// What the panel shows, read on the thread pool.
+struct Snapshot {
+    /// Top of the repository, which change paths are relative to
+    workdir: PathBuf,
+    branch: Option<String>,
+    changes: Vec<Change>,
+    /// Diff of the selected change, if it is still there
+    diff: Option<(Change, FileDiff)>,
+}
+
+fn read_snapshot(root: PathBuf, selected: Option<Change>) -> Result<Snapshot, String> {
+    let scm = SourceControl::open(&root).map_err(|e| e.message().to_string())?;
+    let changes = scm.changes().map_err(|e| e.message().to_string())?;
+    // The kind may change between refreshes, say from added to modified
+    let diff = selected
+        .and_then(|selected| {
+            changes
+                .iter()
+                .find(|c| c.path == selected.path && c.staged == selected.staged)
+        })
+        .and_then(|change| Some((change.clone(), scm.diff(change).ok()?)));
+    Ok(Snapshot {
+        workdir: scm.workdir().to_path_buf(),
+        branch: scm.branch(),
+        changes,
+        diff,
+    })
+}
+
+/// Changed files in the repository of one workspace folder, with staging
+/// per file and per hunk, a diff of the selected file and a commit message
+/// box. With several folders open, the header picks which one.
+pub struct SourceControlView {
+    pub focus_handle: FocusHandle,
+    /// The workspace folders
+    roots: Vec<PathBuf>,
+    /// The one whose repository is shown
+    root: PathBuf,
+    pools: Arc<Pools>,
+    message: Entity<TextInput>,
+    workdir: Option<PathBuf>,
+    branch: Option<String>,
+    changes: Vec<Change>,
+    selected: Option<Change>,
+    diff: Option<FileDiff>,
+    /// Last failure, such as the folder not being in a repository
+    error: Option<String>,
+    /// Outcome of the last commit
+    status: Option<String>,
+    /// Bumped for every refresh so a slower earlier one doesn't win
+    generation: usize,
+    /// Held while an operation changes the repository, so two quick clicks
+    /// don't both try to lock the index
+    writing: Arc<Mutex<()>>,
+}
+
+impl EventEmitter<RepositoryChanged> for SourceControlView {}
+
+impl SourceControlView {
+    /// Opens on the repository of the first of `roots`.
+    pub fn new(roots: Vec<PathBuf>, pools: Arc<Pools>, cx: &mut Context<Self>) -> Self {
+        let message = cx.new(|cx| TextInput::new("Commit message (enter to commit)", cx));
+        let mut this = Self {
+            focus_handle: cx.focus_handle(),
+            root: roots.first().cloned().unwrap_or_default(),
+            roots,
+            pools,
+            message,
+            workdir: None,
+            branch: None,
+            changes: Vec::new(),
+            selected: None,
+            diff: None,
+            error: None,
+            status: None,
+            generation: 0,
+            writing: Arc::default(),
+        };
+        this.refresh(cx);
+        this
+    }
+
+    /// Follows the workspace folders, staying on the current one while it
+    /// is still open.
+    pub fn set_roots(&mut self, roots: Vec<PathBuf>, cx: &mut Context<Self>) {
+        let root = if roots.contains(&self.root) {
+            self.root.clone()
+        } else {
+            roots.first().cloned().unwrap_or_default()
+        };
+        self.roots = roots;
+        self.select_root(root, cx);
+        cx.notify();
+    }
+
+    fn select_root(&mut self, root: PathBuf, cx: &mut Context<Self>) {
+        if self.root != root {
+            self.root = root;
+            self.selected = None;
+            self.diff = None;
+            self.status = None;
+            self.refresh(cx);
+        }
+    }
+
+    /// Re-reads the changes and the selected diff on the thread pool.
+    pub fn refresh(&mut self, cx: &mut Context<Self>) {
+        self.generation += 1;
+        let generation = self.generation;
+        let (sender, receiver) = oneshot::channel();
+        let root = self.root.clone();
+        let selected = self.selected.clone();
+        self.pools.threads.spawn(move || {
+            sender.send(read_snapshot(root, selected)).ok();
+        });
+        cx.spawn(async move |this, cx| {
+            let Ok(snapshot) = receiver.await else {
+                return;
+            };
+            this.update(cx, |this, cx| {
+                if generation != this.generation {
+                    return;
+                }
+                match snapshot {
+                    Ok(snapshot) => {
+                        this.workdir = Some(snapshot.workdir);
+                        this.branch = snapshot.branch;
+                        this.changes = snapshot.changes;
+                        let (selected, diff) = snapshot.diff.unzip();
+                        this.selected = selected;
+                        this.diff = diff;
+                        this.error = None;
+                    }
+                    Err(e) => {
+                        this.workdir = None;
+                        this.branch = None;
+                        this.changes.clear();
+                        this.selected = None;
+                        this.diff = None;
+                        this.error = Some(e);
+                    }
+                }
+                cx.notify();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    fn select(&mut self, change: Change, cx: &mut Context<Self>) {
+        self.selected = Some(change);
+        self.diff = None;
+        self.refresh(cx);
+        cx.notify();
+    }
+
+    /// Runs a change to the repository on the thread pool, then hands what
+    /// it returned to `done` and refreshes everything that shows its state.
+    fn run<T: Send + 'static>(
+        &mut self,
+        op: impl FnOnce(&SourceControl) -> Result<T, git2::Error> + Send + 'static,
+        done: impl FnOnce(&mut Self, T, &mut Context<Self>) + 'static,
+        cx: &mut Context<Self>,
+    ) {
+        let (sender, receiver) = oneshot::channel();
+        let root = self.root.clone();
+        let writing = self.writing.clone();
+        self.pools.threads.spawn(move || {
+            // Nothing is guarded but the turn itself, so one that panicked
+            // leaves nothing to clean up
+            let _writing = writing.lock().unwrap_or_else(PoisonError::into_inner);
+            let result = SourceControl::open(&root)
+                .and_then(|scm| op(&scm))
+                .map_err(|e| e.message().to_string());
+            sender.send(result).ok();
+        });
+        cx.spawn(async move |this, cx| {
+            let Ok(result) = receiver.await else {
+                return;
+            };
+            this.update(cx, |this, cx| {
+                match result {
+                    Ok(value) => {
+                        this.error = None;
+                        done(this, value, cx);
+                        cx.emit(RepositoryChanged);
+                    }
+                    Err(e) => this.error = Some(e),
+                }
+                // On failure too: what was shown may be out of date, which
+                // is often why it failed
+                this.refresh(cx);
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    fn toggle_staged(&mut self, change: Change, cx: &mut Context<Self>) {
+        self.status = None;
+        if change.staged {
+            self.run(move |scm| scm.unstage(&change), |_, (), _| {}, cx);
+        } else {
+            self.run(move |scm| scm.stage(&change.path), |_, (), _| {}, cx);
+        }
+    }
+
+    fn toggle_hunk_staged(&mut self, hunk: Hunk, cx: &mut Context<Self>) {
+        let (Some(change), Some(diff)) = (self.selected.clone(), self.diff.clone()) else {
+            return;
+        };
+        self.status = None;
+        if change.staged {
+            self.run(
+                move |scm| scm.unstage_hunk(&change.path, &diff, &hunk),
+                |_, (), _| {},
+                cx,
+            );
+        } else {
+            self.run(
+                move |scm| scm.stage_hunk(&change.path, &diff, &hunk),
+                |_, (), _| {},
+                cx,
+            );
+        }
+    }
+
+    fn commit(&mut self, _: &CommitStaged, _window: &mut Window, cx: &mut Context<Self>) {
+        let message = self.message.read(cx).text().trim().to_string();
+        self.status = None;
+        self.run(
+            move |scm| scm.commit(&message),
+            |this, id, cx| {
+                this.message.update(cx, |input, cx| input.set_text("", cx));
+                let id = id.to_string();
+                this.status = Some(format!("Committed {}", &id[..7.min(id.len())]));
+            },
+            cx,
+        );
+    }
+
+    fn refresh_action(
+        &mut self,
+        _: &RefreshSourceControl,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.refresh(cx);
+    }
+
+    fn handle_message_key(
+        &mut self,
+        event: &KeyDownEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if event.keystroke.key == "enter" {
+            self.commit(&CommitStaged, window, cx);
+            cx.stop_propagation();
+        }
+    }
+
+    fn render_change(&self, change: &Change, cx: &mut Context<Self>) -> gpui::Div {
+        let selected = self.selected.as_ref() == Some(change);
+        let select = change.clone();
+        let toggle = change.clone();
+        div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap_2()
+            .px_2()
+            .cursor_pointer()
+            .hover(|s| s.bg(rgb(0x2a2d2e)))
+            .when(selected, |this| this.bg(rgb(0x37373d)))
+            .child(
+                div()
+                    .min_w(px(12.0))
+                    .text_color(rgb(change.kind.color()))
+                    .child(change.kind.letter()),
+            )
+            .child(
+                div()
+                    .flex_1()
+                    .overflow_hidden()
+                    .child(match &change.old_path {
+                        Some(old_path) => format!("{} → {}", old_path, change.path),
+                        None => change.path.clone(),
+                    }),
+            )
+            .child(
+                div()
+                    .px_1()
+                    .rounded_md()
+                    .hover(|s| s.bg(rgb(0x45494e)))
+                    .child(if change.staged { "−" } else { "+" })
+                    .on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(move |this, _, _, cx| {
+                            cx.stop_propagation();
+                            this.toggle_staged(toggle.clone(), cx)
+                        }),
+                    ),
+            )
+            .on_mouse_down(
+                MouseButton::Left,
+                cx.listener(move |this, _, _, cx| this.select(select.clone(), cx)),
+            )
+    }
+
+    /// A choice of folder when there is more than one, each possibly its own
+    /// repository.
+    fn render_roots(&self, cx: &mut Context<Self>) -> Vec<gpui::Div> {
+        if self.roots.len() < 2 {
+            return Vec::new();
+        }
+        self.roots
+            .iter()
+            .map(|root| {
+                let selected = *root == self.root;
+                let select = root.clone();
+                div()
+                    .px_2()
+                    .rounded_md()
+                    .cursor_pointer()
+                    .hover(|s| s.bg(rgb(0x2a2d2e)))
+                    .when(selected, |this| {
+                        this.bg(rgb(0x37373d)).text_color(rgb(0xffffff))
+                    })
+                    .child(root_name(root))
+                    .on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(move |this, _, _, cx| {
+                            this.select_root(select.clone(), cx);
+                            cx.notify();
+                        }),
+                    )
+            })
+            .collect()
+    }
+
+    fn render_section(&self, title: &str, staged: bool, cx: &mut Context<Self>) -> gpui::Div {
+        let rows: Vec<_> = self
+            .changes
+            .iter()
+            .filter(|c| c.staged == staged)
+            .map(|change| self.render_change(change, cx))
+            .collect();
+        div()
+            .flex()
+            .flex_col()
+            .child(
+                div()
+                    .px_2()
+                    .py_1()
+                    .font_weight(FontWeight::BOLD)
+                    .child(format!("{} ({})", title, rows.len())),
+            )
+            .children(rows)
+    }
+
+    fn render_diff(&self, cx: &mut Context<Self>) -> gpui::Div {
+        let (Some(change), Some(diff)) = (&self.selected, &self.diff) else {
+            let hint = if self.selected.is_some() {
+                "Loading diff..."
+            } else {
+                "Select a file to see its changes"
+            };
+            return div().p_2().text_color(rgb(0x888888)).child(hint);
+        };
+        let path = self
+            .workdir
+            .as_ref()
+            .unwrap_or(&self.root)
+            .join(&change.path);
+        let hunk_label = if change.staged {
+            "Unstage Hunk"
+        } else {
+            "Stage Hunk"
+        };
+        let can_stage_hunks = change.kind != ChangeKind::Conflicted;
+
+        let hunks = diff.hunks.iter().map(|hunk| {
+            let header = format!(
+                "@@ -{},{} +{},{} @@",
+                hunk.old_lines.start + 1,
+                hunk.old_lines.len(),
+                hunk.new_lines.start + 1,
+                hunk.new_lines.len()
+            );
+            let removed = original_lines(&diff.old, hunk).into_iter().map(|line| {
+                div()
+                    .bg(rgb(0x4b1818))
+                    .text_color(rgb(0xe06c75))
+                    .child(format!("- {}", line))
+            });
+            let added = changed_lines(&diff.new, hunk).into_iter().map(|line| {
+                div()
+                    .bg(rgb(0x1e3a1e))
+                    .text_color(rgb(0x98c379))
+                    .child(format!("+ {}", line))
+            });
+            let hunk = hunk.clone();
+            div()
+                .flex()
+                .flex_col()
+                .pb_2()
+                .child(
+                    div()
+                        .flex()
+                        .flex_row()
+                        .items_center()
+                        .gap_2()
+                        .py_1()
+                        .child(div().text_color(rgb(0x888888)).child(header))
+                        .when(can_stage_hunks, |this| {
+                            this.child(button(hunk_label.to_string()).on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(move |this, _, _, cx| {
+                                    this.toggle_hunk_staged(hunk.clone(), cx)
+                                }),
+                            ))
+                        }),
+                )
+                .children(removed)
+                .children(added)
+        });
+
+        div()
+            .flex()
+            .flex_col()
+            .p_2()
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap_2()
+                    .pb_2()
+                    .font_weight(FontWeight::BOLD)
+                    .child(format!(
+                        "{}{}",
+                        change.path,
+                        if change.staged { " (staged)" } else { "" }
+                    ))
+                    .when(change.kind != ChangeKind::Deleted, |this| {
+                        this.child(button("Open File".to_string()).on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |_, _, window, cx| {
+                                window.dispatch_action(Box::new(OpenPath::new(path.clone())), cx)
+                            }),
+                        ))
+                    }),
+            )
+            .when(diff.hunks.is_empty(), |this| {
+                this.child(div().text_color(rgb(0x888888)).child("No text changes"))
+            })
+            .children(hunks)
+    }
+}
+
+fn button(label: String) -> gpui::Div {
+    div()
+        .px_2()
+        .py_1()
+        .rounded_md()
+        .bg(rgb(0x0e639c))
+        .text_color(rgb(0xffffff))
+        .cursor_pointer()
+        .hover(|s| s.bg(rgb(0x1177bb)))
+        .child(label)
+}
+
+impl Render for SourceControlView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let staged = self.changes.iter().filter(|c| c.staged).count();
+
+        div()
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::commit))
+            .on_action(cx.listener(Self::refresh_action))
+            .size_full()
+            .flex()
+            .flex_col()
+            .bg(rgb(0x1e1e1e))
+            .text_color(rgb(0xcccccc))
+            .text_sm()
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap_2()
+                    .p_2()
+                    .border_b_1()
+                    .border_color(rgb(0x404040))
+                    .child(div().font_weight(FontWeight::BOLD).child("Source Control"))
+                    .children(self.render_roots(cx))
+                    .children(
+                        self.branch
+                            .clone()
+                            .map(|branch| div().text_color(rgb(0x888888)).child(branch)),
+                    )
+                    .children(
+                        self.error
+                            .clone()
+                            .map(|error| div().text_color(rgb(0xe06c75)).child(error)),
+                    )
+                    .children(self.status.clone())
+                    .child(div().flex_1())
+                    .child(button("Refresh".to_string()).on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(|this, _, _, cx| this.refresh(cx)),
+                    )),
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap_2()
+                    .p_2()
+                    .border_b_1()
+                    .border_color(rgb(0x404040))
+                    .child(
+                        div()
+                            .flex_1()
+                            .on_key_down(cx.listener(Self::handle_message_key))
+                            .child(self.message.clone()),
+                    )
+                    .child(button(format!("Commit {}", staged)).on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(|this, _, window, cx| this.commit(&CommitStaged, window, cx)),
+                    )),
+            )
+            .child(
+                div()
+                    .flex_1()
+                    .flex()
+                    .flex_row()
+                    .overflow_hidden()
+                    .child(
+                        div()
+                            .id("source-control-changes")
+                            .w(px(320.0))
+                            .flex()
+                            .flex_col()
+                            .border_r_1()
+                            .border_color(rgb(0x404040))
+                            .overflow_y_scroll()
+                            .child(self.render_section("Staged Changes", true, cx))
+                            .child(self.render_section("Changes", false, cx)),
+                    )
+                    .child(
+                        div()
+                            .id("source-control-diff")
+                            .flex_1()
+                            .overflow_y_scroll()
+                            .child(self.render_diff(cx)),
+                    ),
+            )
+    }
+}
@@ -1,13 +1,39 @@
 use gpui::*;
 
+use crate::editor::index::IndexProgress;
+use crate::workspace::WorkspaceItem;
+
 pub struct StatusBar {}
 
+impl Default for StatusBar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl StatusBar {
     pub fn new() -> Self {
         Self {}
     }
 
-    pub fn render(&self, _cx: &App) -> impl IntoElement {
+    pub fn render(
+        &self,
+        active_item: Option<&WorkspaceItem>,
+        index: Option<IndexProgress>,
+        cx: &App,
+    ) -> impl IntoElement {
+        let language = match active_item {
+            Some(WorkspaceItem::Editor(editor)) => Some(editor.read(cx).language.name()),
+            _ => None,
+        };
+        let index = index.map(|progress| {
+            if progress.building {
+                format!("Indexing {}/{}", progress.indexed, progress.total)
+            } else {
+                format!("{} files indexed", progress.indexed)
+            }
+        });
+
         div()
             .h_6()
             .w_full()
@@ -18,5 +44,8 @@ impl StatusBar {
             .px_2()
             .text_xs()
             .child("Ready")
+            .child(div().flex_1())
+            .children(index.map(|text| div().px_2().child(text)))
+            .children(language)
     }
 }
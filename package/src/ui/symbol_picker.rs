@@ -0,0 +1,261 @@
+use gpui::{
+    AppContext, Context, DismissEvent, Entity, EventEmitter, FocusHandle, Focusable,
+    InteractiveElement, IntoElement, KeyDownEvent, MouseButton, ParentElement, Render,
+    ScrollHandle, StatefulInteractiveElement, Styled, Subscription, Window, div,
+    prelude::FluentBuilder, px, rgb,
+};
+
+use crate::editor::outline::{OutlineSource, Symbol, outline_with_source};
+use crate::editor::texteditor::TextEditor;
+use crate::structs::fuzzy::fuzzy_match;
+use crate::ui::file_finder::highlighted;
+use crate::ui::text_input::{TextChanged, TextInput};
+
+/// Outline of the active buffer (ctrl-shift-o), fuzzy filtered by name.
+/// The editor scrolls to each symbol as it is selected and goes back to
+/// where it was if the picker is cancelled.
+pub struct SymbolPicker {
+    pub query: Entity<TextInput>,
+    editor: Entity<TextEditor>,
+    /// Where the editor was before the picker opened
+    origin: (usize, f32),
+    symbols: Vec<Symbol>,
+    source: OutlineSource,
+    /// (symbol index, matched char positions in its name), best first
+    matches: Vec<(usize, Vec<usize>)>,
+    selected: usize,
+    scroll_handle: ScrollHandle,
+    _query_subscription: Subscription,
+}
+
+impl EventEmitter<DismissEvent> for SymbolPicker {}
+
+impl Focusable for SymbolPicker {
+    fn focus_handle(&self, cx: &gpui::App) -> FocusHandle {
+        self.query.focus_handle(cx)
+    }
+}
+
+impl SymbolPicker {
+    pub fn new(editor: Entity<TextEditor>, cx: &mut Context<Self>) -> Self {
+        let ((symbols, source), origin, current_line) = {
+            let editor = editor.read(cx);
+            (
+                outline_with_source(&editor.get_text(cx), editor.language),
+                editor.view_position(),
+                editor.cursor_line(cx).0,
+            )
+        };
+
+        let query = cx.new(|cx| TextInput::new("Go to symbol", cx));
+        let query_subscription = cx.subscribe(&query, |this, _, _: &TextChanged, cx| {
+            this.update_matches(cx);
+            this.preview(cx);
+            cx.notify();
+        });
+        let mut this = Self {
+            query,
+            editor,
+            origin,
+            symbols,
+            source,
+            matches: Vec::new(),
+            selected: 0,
+            scroll_handle: ScrollHandle::new(),
+            _query_subscription: query_subscription,
+        };
+        this.update_matches(cx);
+        // Start on the symbol the cursor is in, without moving the editor
+        this.selected = this
+            .symbols
+            .iter()
+            .rposition(|s| s.line <= current_line)
+            .unwrap_or(0);
+        this.scroll_handle.scroll_to_item(this.selected);
+        this
+    }
+
+    /// In file order with an empty query, otherwise best match first.
+    fn update_matches(&mut self, cx: &gpui::App) {
+        let query = self.query.read(cx).text();
+        let mut scored: Vec<(i64, usize, Vec<usize>)> = self
+            .symbols
+            .iter()
+            .enumerate()
+            .filter_map(|(ix, symbol)| {
+                let m = fuzzy_match(query, &symbol.name)?;
+                Some((m.score, ix, m.positions))
+            })
+            .collect();
+        if !query.is_empty() {
+            scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        }
+        self.matches = scored
+            .into_iter()
+            .map(|(_, ix, positions)| (ix, positions))
+            .collect();
+        self.selected = 0;
+        self.scroll_handle.scroll_to_item(0);
+    }
+
+    fn preview(&mut self, cx: &mut Context<Self>) {
+        let Some(symbol) = self
+            .matches
+            .get(self.selected)
+            .map(|(ix, _)| &self.symbols[*ix])
+        else {
+            return;
+        };
+        let (line, column) = (symbol.line, symbol.column);
+        self.editor
+            .update(cx, |editor, cx| editor.go_to_position(line, column, cx));
+    }
+
+    fn select(&mut self, delta: isize, cx: &mut Context<Self>) {
+        if self.matches.is_empty() {
+            return;
+        }
+        let len = self.matches.len() as isize;
+        self.selected = (self.selected as isize + delta).rem_euclid(len) as usize;
+        self.scroll_handle.scroll_to_item(self.selected);
+        self.preview(cx);
+    }
+
+    fn confirm(&mut self, ix: usize, cx: &mut Context<Self>) {
+        if ix >= self.matches.len() {
+            return self.cancel(cx);
+        }
+        self.selected = ix;
+        self.preview(cx);
+        cx.emit(DismissEvent);
+    }
+
+    fn cancel(&mut self, cx: &mut Context<Self>) {
+        let origin = self.origin;
+        self.editor
+            .update(cx, |editor, cx| editor.restore_view_position(origin, cx));
+        cx.emit(DismissEvent);
+    }
+
+    fn handle_key(&mut self, event: &KeyDownEvent, _: &mut Window, cx: &mut Context<Self>) {
+        let keystroke = &event.keystroke;
+        let ctrl = keystroke.modifiers.control;
+        match keystroke.key.as_str() {
+            "escape" => self.cancel(cx),
+            "enter" => self.confirm(self.selected, cx),
+            "up" => self.select(-1, cx),
+            "down" => self.select(1, cx),
+            "p" if ctrl => self.select(-1, cx),
+            "n" if ctrl => self.select(1, cx),
+            // Everything else is typing, handled by the query field
+            _ => return,
+        }
+        cx.stop_propagation();
+        cx.notify();
+    }
+}
+
+impl Render for SymbolPicker {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let selected = self.selected;
+        let filtered = !self.query.read(cx).text().is_empty();
+        let rows = self
+            .matches
+            .iter()
+            .enumerate()
+            .map(|(row, (ix, positions))| {
+                let symbol = &self.symbols[*ix];
+                // Nesting is only meaningful in file order
+                let depth = if !filtered { symbol.depth } else { 0 };
+                div()
+                    .pr_4()
+                    .py_1()
+                    .pl(px(16.0 + 12.0 * depth as f32))
+                    .flex()
+                    .flex_row()
+                    .gap_2()
+                    .cursor_pointer()
+                    .text_color(rgb(0xcccccc))
+                    .when(row == selected, |this| {
+                        this.bg(rgb(0x04395e)).text_color(rgb(0xffffff))
+                    })
+                    .hover(|s| s.bg(rgb(0x2a2d2e)))
+                    .child(
+                        div()
+                            .min_w(px(48.0))
+                            .text_color(rgb(0x888888))
+                            .child(symbol.kind.label()),
+                    )
+                    .child(highlighted(&symbol.name, positions, 0))
+                    .child(div().flex_1())
+                    .child(
+                        div()
+                            .text_color(rgb(0x888888))
+                            .child(symbol.line.to_string()),
+                    )
+                    .on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(move |this, _, _, cx| this.confirm(row, cx)),
+                    )
+            });
+
+        let status = if self.symbols.is_empty() {
+            Some("No symbols in this file")
+        } else if self.matches.is_empty() {
+            Some("No matching symbols")
+        } else {
+            None
+        };
+
+        // Only a parsed outline is known to be complete
+        let approximate = self.source == OutlineSource::Patterns && !self.symbols.is_empty();
+
+        div()
+            .on_key_down(cx.listener(Self::handle_key))
+            .absolute()
+            .top(px(40.0))
+            .left_1_2()
+            .w(px(500.0))
+            .ml(px(-250.0))
+            .bg(rgb(0x252526))
+            .border_1()
+            .border_color(rgb(0x454545))
+            .shadow_lg()
+            .flex()
+            .flex_col()
+            .text_xs()
+            .child(
+                div()
+                    .px_4()
+                    .py_2()
+                    .border_b_1()
+                    .border_color(rgb(0x454545))
+                    .text_color(rgb(0xffffff))
+                    .child(self.query.clone()),
+            )
+            .child(
+                div()
+                    .id("symbol-picker-matches")
+                    .max_h(px(400.0))
+                    .flex()
+                    .flex_col()
+                    .overflow_y_scroll()
+                    .track_scroll(&self.scroll_handle)
+                    .children(rows),
+            )
+            .children(
+                status.map(|status| div().px_4().py_2().text_color(rgb(0x888888)).child(status)),
+            )
+            .when(approximate, |this| {
+                this.child(
+                    div()
+                        .px_4()
+                        .py_1()
+                        .border_t_1()
+                        .border_color(rgb(0x454545))
+                        .text_color(rgb(0x888888))
+                        .child("Found by matching lines, not parsed; some symbols may be missing"),
+                )
+            })
+    }
+}
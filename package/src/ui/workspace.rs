@@ -1,30 +1,80 @@
-use crate::config::{BACKGROUND_COLOR, PRIMARY_COLOR};
-use crate::editor::filebrowser::FileBrowser;
-use crate::editor::texteditor::TextEditor;
+use crate::config::{
+    BACKGROUND_COLOR, BROWSER_DEFAULT_WIDTH, PRIMARY_COLOR, RECENT_FILES_LIMIT,
+    RECOVERY_INTERVAL_SECS, SEARCH_INDEX_SAVE_INTERVAL_SECS, WORKSPACE_FILE_EXTENSION,
+};
+use crate::editor::filebrowser::{EntryMoved, FileBrowser, FilesChanged, GitStatusChanged};
+use crate::editor::fileops::moved_path;
+use crate::editor::git::GitStatus;
+use crate::editor::imageviewer::ImageViewer;
+use crate::editor::index::SearchIndex;
+use crate::editor::replace::{FileEdit, ProjectReplace, ReplaceSummary};
+use crate::editor::search::{
+    CancelFlag, SearchOptions, SearchQuery, SearchScope, perform_search_streaming, project_files,
+};
+use crate::editor::texteditor::{FileSaved, TextEditor};
+use crate::recovery::{RecoveryEntry, RecoveryJournal};
+use crate::settings::{AutosaveMode, Settings};
 use crate::state::appstate::AppState;
+use crate::ui::file_finder::FileFinder;
+use crate::ui::go_to_line::GoToLinePrompt;
 use crate::ui::header::Header;
+use crate::ui::palette::CommandPalette;
+use crate::ui::project_replace::{ApplyProjectReplace, ProjectReplaceView, UndoProjectReplace};
+use crate::ui::search_results::{NextResult, PreviousResult, SearchResultsView};
 use crate::ui::settings::SettingsView;
+use crate::ui::source_control::{RepositoryChanged, SourceControlView};
+use crate::ui::symbol_picker::SymbolPicker;
 use crate::workspace::WorkspaceItem;
+use futures::StreamExt;
+use futures::channel::{mpsc, oneshot};
 use gpui::prelude::*;
 use gpui::*;
 use serde::Deserialize;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
-use crate::ui::search_bar::{PerformSearch, SearchBar};
+use crate::history::SearchHistory;
+use crate::layout::{Dock, PanelLayout};
+use crate::persist::JsonFile;
+use crate::recent::RecentWorkspaces;
+use crate::roots::WorkspaceRoots;
+use crate::ui::search_bar::{
+    DeleteSavedSearch, PerformReplace, PerformSearch, RunSavedSearch, SearchBar,
+};
 use crate::ui::statusbar::StatusBar;
+use crate::utils::canonical_path;
 
 pub struct MainScreen {
     pub items: Vec<WorkspaceItem>,
     pub active_item_index: usize,
     pub file_browser: Entity<FileBrowser>,
+    /// Folders the browser, quick-open and project search work in
+    pub workspace: WorkspaceRoots,
     pub search_bar: SearchBar,
-    pub show_browser: bool,
-    pub show_info_panel: bool,
+    /// Panel visibility and sizes, saved whenever they change
+    pub layout: PanelLayout,
     pub focus_handle: FocusHandle,
     pub header: Header,
     pub status_bar: StatusBar, // Add Status Bar
     pub state: Entity<AppState>,
-    pub show_file_switcher: bool,
+    /// Picker shown over the workspace (quick-open, go to line, ...)
+    pub modal: Option<AnyView>,
+    modal_subscription: Option<Subscription>,
+    /// Files opened this session, most recent first
+    recent_files: Vec<PathBuf>,
+    /// Set while a save/discard prompt is open, gpui can't stack window prompts.
+    prompt_open: bool,
+    recovery: RecoveryJournal,
+    /// (editor, version) pairs last written to the recovery journal
+    journaled_versions: Vec<(EntityId, usize)>,
+    item_subscriptions: HashMap<EntityId, Vec<Subscription>>,
+    /// Stops the project search that is currently streaming results
+    search_cancel: Option<CancelFlag>,
+    /// Trigram index used to narrow project search, when enabled in settings
+    search_index: Option<Arc<SearchIndex>>,
+    _subscriptions: Vec<Subscription>,
 }
 
 #[derive(Clone, PartialEq, Debug, Deserialize)]
@@ -50,13 +100,97 @@ impl gpui::Action for SwitchTab {
         action
             .as_any()
             .downcast_ref::<Self>()
-            .map_or(false, |a| self == a)
+            .is_some_and(|a| self == a)
+    }
+}
+
+/// Opens a folder or workspace file in place of the current workspace, see
+/// `MainScreen::open_workspace`.
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+pub struct OpenWorkspace {
+    pub path: PathBuf,
+}
+
+impl gpui::Action for OpenWorkspace {
+    fn name(&self) -> &'static str {
+        "OpenWorkspace"
+    }
+
+    fn name_for_type() -> &'static str {
+        "OpenWorkspace"
+    }
+
+    fn build(value: serde_json::Value) -> gpui::Result<Box<dyn gpui::Action>> {
+        let action: OpenWorkspace = serde_json::from_value(value)?;
+        Ok(Box::new(action))
+    }
+
+    fn boxed_clone(&self) -> Box<dyn gpui::Action> {
+        Box::new(self.clone())
+    }
+
+    fn partial_eq(&self, action: &dyn gpui::Action) -> bool {
+        action
+            .as_any()
+            .downcast_ref::<Self>()
+            .is_some_and(|a| self == a)
     }
 }
 
 #[derive(Clone, PartialEq, Debug, Deserialize)]
 pub struct OpenPath {
     pub path: PathBuf,
+    /// 1-based (line, column) to put the cursor at
+    #[serde(default)]
+    pub position: Option<(usize, usize)>,
+}
+
+impl OpenPath {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            position: None,
+        }
+    }
+
+    pub fn at(path: PathBuf, line: usize, column: usize) -> Self {
+        Self {
+            path,
+            position: Some((line, column)),
+        }
+    }
+}
+
+/// Takes one of several folders out of the workspace.
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+pub struct RemoveWorkspaceFolder {
+    pub path: PathBuf,
+}
+
+impl gpui::Action for RemoveWorkspaceFolder {
+    fn name(&self) -> &'static str {
+        "RemoveWorkspaceFolder"
+    }
+
+    fn name_for_type() -> &'static str {
+        "RemoveWorkspaceFolder"
+    }
+
+    fn build(value: serde_json::Value) -> gpui::Result<Box<dyn gpui::Action>> {
+        let action: RemoveWorkspaceFolder = serde_json::from_value(value)?;
+        Ok(Box::new(action))
+    }
+
+    fn boxed_clone(&self) -> Box<dyn gpui::Action> {
+        Box::new(self.clone())
+    }
+
+    fn partial_eq(&self, action: &dyn gpui::Action) -> bool {
+        action
+            .as_any()
+            .downcast_ref::<Self>()
+            .is_some_and(|a| self == a)
+    }
 }
 
 impl gpui::Action for OpenPath {
@@ -81,7 +215,7 @@ impl gpui::Action for OpenPath {
         action
             .as_any()
             .downcast_ref::<Self>()
-            .map_or(false, |a| self == a)
+            .is_some_and(|a| self == a)
     }
 }
 
@@ -105,7 +239,7 @@ impl gpui::Action for TriggerSearch {
         action
             .as_any()
             .downcast_ref::<Self>()
-            .map_or(false, |a| self == a)
+            .is_some_and(|a| self == a)
     }
 }
 
@@ -132,7 +266,7 @@ impl gpui::Action for ToggleSearch {
         action
             .as_any()
             .downcast_ref::<Self>()
-            .map_or(false, |a| self == a)
+            .is_some_and(|a| self == a)
     }
 }
 
@@ -141,10 +275,26 @@ actions!(
     [
         Quit,
         ToggleBrowser,
+        SwitchBrowserSide,
+        FocusBrowser,
+        RevealActiveFile,
+        OpenFolder,
+        OpenWorkspaceFile,
+        AddFolderToWorkspace,
+        SaveWorkspaceAs,
         ToggleInfoPanel,
         OpenSettings,
         CloseTab,
         ToggleFileSwitcher,
+        GoToLine,
+        GoToSymbol,
+        ToggleCommandPalette,
+        ShowCacheStats,
+        FlushCache,
+        ToggleReplace,
+        ToggleProjectReplace,
+        CancelProjectSearch,
+        ShowSourceControl,
         // ToggleSearch is not a simple unit action, needs registered deserializer if generic,
         // but since we are manually binding, we can just use the struct if we implement Action.
         // Wait, actions! macro handles unit structs primarily.
@@ -156,48 +306,288 @@ impl MainScreen {
     pub fn new(
         editor: Entity<TextEditor>,
         state: Entity<AppState>,
+        workspace: WorkspaceRoots,
+        window: &mut Window,
         cx: &mut Context<Self>,
     ) -> Self {
-        let file_browser = cx.new(|cx| FileBrowser::new(state.clone(), cx));
+        let file_browser = cx.new(|cx| FileBrowser::new(workspace.clone(), state.clone(), cx));
+        let entry_moved = cx.subscribe(&file_browser, |this, _, event: &EntryMoved, cx| {
+            this.follow_moved_entry(&event.from, &event.to, cx)
+        });
+        // Tabs show the git state too, and a commit moves what the gutters compare against
+        let git_changed = cx.subscribe(&file_browser, |this, _, _: &GitStatusChanged, cx| {
+            for item in &this.items {
+                match item {
+                    WorkspaceItem::Editor(editor) => {
+                        editor.update(cx, |editor, cx| editor.reload_diff_base(cx))
+                    }
+                    WorkspaceItem::SourceControl(view) => {
+                        view.update(cx, |view, cx| view.refresh(cx))
+                    }
+                    _ => {}
+                }
+            }
+            cx.notify()
+        });
+        // The index follows the browser's watcher rather than watching the tree again
+        let files_changed = cx.subscribe(&file_browser, |this, _, event: &FilesChanged, cx| {
+            if let Some(index) = this.search_index.clone() {
+                let paths = event.paths.clone();
+                this.state
+                    .read(cx)
+                    .pools
+                    .threads
+                    .spawn(move || index.update(&paths));
+            }
+        });
+
+        // Autosave everything when the window goes to the background
+        let activation = cx.observe_window_activation(window, |this, window, cx| {
+            if !window.is_window_active() {
+                for item in &this.items {
+                    if let WorkspaceItem::Editor(editor) = item {
+                        editor.update(cx, |editor, cx| {
+                            editor.autosave_on(AutosaveMode::OnFocusChange, cx)
+                        });
+                    }
+                }
+            }
+        });
 
-        let initial_items = vec![WorkspaceItem::Editor(editor)];
+        cx.spawn(async move |this, cx| {
+            loop {
+                cx.background_executor()
+                    .timer(Duration::from_secs(RECOVERY_INTERVAL_SECS))
+                    .await;
+                if this
+                    .update(cx, |this, cx| this.write_recovery_journal(cx))
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        })
+        .detach();
 
-        Self {
-            items: initial_items,
+        let settings_observer =
+            cx.observe_global::<Settings>(|this, cx| this.sync_search_index(cx));
+
+        let mut this = Self {
+            items: Vec::new(),
             active_item_index: 0,
             file_browser,
+            workspace,
             search_bar: SearchBar::new(cx),
-            show_browser: true,
-            show_info_panel: true,
+            layout: PanelLayout::load(),
             focus_handle: cx.focus_handle(),
             header: Header::new(),
             status_bar: StatusBar::new(),
             state,
-            show_file_switcher: false,
+            modal: None,
+            modal_subscription: None,
+            recent_files: Vec::new(),
+            prompt_open: false,
+            recovery: RecoveryJournal::default(),
+            journaled_versions: Vec::new(),
+            item_subscriptions: HashMap::new(),
+            search_cancel: None,
+            search_index: None,
+            _subscriptions: vec![
+                activation,
+                settings_observer,
+                entry_moved,
+                git_changed,
+                files_changed,
+            ],
+        };
+        this.sync_search_index(cx);
+        this.remember_workspace(cx);
+        this.add_editor(editor, window, cx);
+        this.offer_recovery(window, cx);
+        this
+    }
+
+    /// Points tabs and recent files at a file or folder that was renamed or
+    /// moved in the browser.
+    fn follow_moved_entry(&mut self, from: &Path, to: &Path, cx: &mut Context<Self>) {
+        // Tabs may have been opened through another spelling of the same path
+        let from = canonical_path(from);
+        for item in &self.items {
+            let Some(path) = item
+                .path(cx)
+                .and_then(|p| moved_path(&canonical_path(&p), &from, to))
+            else {
+                continue;
+            };
+            match item {
+                WorkspaceItem::Editor(editor) => {
+                    editor.update(cx, |editor, cx| editor.set_file_path(path, cx))
+                }
+                WorkspaceItem::Image(viewer) => viewer.update(cx, |viewer, cx| {
+                    viewer.path = path;
+                    cx.notify();
+                }),
+                _ => {}
+            }
+        }
+        for recent in &mut self.recent_files {
+            if let Some(path) = moved_path(&canonical_path(recent), &from, to) {
+                *recent = path;
+            }
         }
+        cx.notify();
     }
 
-    pub fn open_path(&mut self, action: &OpenPath, _window: &mut Window, cx: &mut Context<Self>) {
-        if let Some(existing_index) = self.items.iter().position(|item| {
-            if let WorkspaceItem::Editor(editor) = item {
-                let editor_model = editor.read(cx);
-                if let Some(opened_path) = &editor_model.file_path {
-                    return opened_path == &action.path;
+    /// Adds an editor tab and makes it active.
+    fn add_editor(
+        &mut self,
+        editor: Entity<TextEditor>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let focus_handle = editor.read(cx).focus_handle.clone();
+        let weak_editor = editor.downgrade();
+        let subscription = cx.on_focus_out(&focus_handle, window, move |_, _, _, cx| {
+            weak_editor
+                .update(cx, |editor, cx| {
+                    editor.autosave_on(AutosaveMode::OnFocusChange, cx)
+                })
+                .ok();
+        });
+        let saved = cx.subscribe(&editor, |this, _, _: &FileSaved, cx| {
+            this.file_browser
+                .update(cx, |browser, cx| browser.refresh_git(cx))
+        });
+        self.item_subscriptions
+            .insert(editor.entity_id(), vec![subscription, saved]);
+
+        self.items.push(WorkspaceItem::Editor(editor));
+        self.active_item_index = self.items.len() - 1;
+        cx.notify();
+    }
+
+    /// Snapshots dirty buffers into the recovery journal. Skipped when
+    /// nothing changed since the last write; the disk write itself runs
+    /// on the thread pool.
+    fn write_recovery_journal(&mut self, cx: &mut Context<Self>) {
+        let dirty = self.dirty_editors(cx);
+        let versions: Vec<(EntityId, usize)> = dirty
+            .iter()
+            .map(|e| (e.entity_id(), e.read(cx).version()))
+            .collect();
+        if versions == self.journaled_versions {
+            return;
+        }
+        self.journaled_versions = versions;
+
+        let entries: Vec<RecoveryEntry> = dirty
+            .iter()
+            .map(|e| {
+                let editor = e.read(cx);
+                RecoveryEntry {
+                    path: editor.file_path.clone(),
+                    text: editor.get_text(cx),
                 }
+            })
+            .collect();
+        let journal = self.recovery.clone();
+        // Queued now so a clear on quit drops it even if it hasn't run yet
+        let ticket = journal.queue();
+        self.state.read(cx).pools.threads.spawn(move || {
+            if let Err(e) = journal.write_queued(ticket, &entries) {
+                eprintln!("Failed to write recovery journal: {}", e);
             }
-            false
+        });
+    }
+
+    /// Offers to restore buffers left behind by a session that didn't exit cleanly.
+    fn offer_recovery(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let entries = self.recovery.load();
+        if entries.is_empty() {
+            return;
+        }
+
+        let names: Vec<String> = entries
+            .iter()
+            .map(|e| {
+                e.path
+                    .as_ref()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .unwrap_or_else(|| "Untitled".to_string())
+            })
+            .collect();
+        let message = format!(
+            "Restore {} unsaved buffer(s) from the previous session?",
+            entries.len()
+        );
+        let detail = names.join("\n");
+
+        self.prompt_open = true;
+        let answer = window.prompt(
+            PromptLevel::Info,
+            &message,
+            Some(&detail),
+            &["Restore", "Discard"],
+            cx,
+        );
+        cx.spawn_in(window, async move |this, cx| {
+            let restore = matches!(answer.await, Ok(0));
+            this.update_in(cx, |this, window, cx| {
+                this.prompt_open = false;
+                if restore {
+                    for entry in entries {
+                        let pools = this.state.read(cx).pools.clone();
+                        let state = cx.new(|_| AppState::with_pools(pools));
+                        let editor = cx.new(|cx| {
+                            let mut editor = TextEditor::new(cx, state);
+                            editor.restore(entry.path, entry.text, cx);
+                            editor
+                        });
+                        this.add_editor(editor, window, cx);
+                    }
+                } else if let Err(e) = this.recovery.clear() {
+                    eprintln!("Failed to clear recovery journal: {}", e);
+                }
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    pub fn open_path(&mut self, action: &OpenPath, window: &mut Window, cx: &mut Context<Self>) {
+        let canonical = canonical_path(&action.path);
+        self.recent_files.retain(|p| canonical_path(p) != canonical);
+        self.recent_files.insert(0, action.path.clone());
+        self.recent_files.truncate(RECENT_FILES_LIMIT);
+
+        if let Some(existing_index) = self.items.iter().position(|item| {
+            item.path(cx)
+                .is_some_and(|p| canonical_path(&p) == canonical)
         }) {
             self.active_item_index = existing_index;
+        } else if ImageViewer::is_supported(&action.path) {
+            let path = action.path.clone();
+            let viewer = cx.new(|cx| ImageViewer::new(path, cx));
+            window.focus(&viewer.read(cx).focus_handle);
+            self.items.push(WorkspaceItem::Image(viewer));
+            self.active_item_index = self.items.len() - 1;
         } else {
             let path = action.path.clone();
-            let state = cx.new(|_| AppState::new());
+            let pools = self.state.read(cx).pools.clone();
+            let state = cx.new(|_| AppState::with_pools(pools));
             let editor = cx.new(|cx| {
                 let mut editor = TextEditor::new(cx, state);
                 editor.open_file_from_path(path, cx);
                 editor
             });
-            self.items.push(WorkspaceItem::Editor(editor));
-            self.active_item_index = self.items.len() - 1;
+            self.add_editor(editor, window, cx);
+        }
+
+        if let Some((line, column)) = action.position
+            && let Some(editor) = self.active_editor()
+        {
+            editor.update(cx, |editor, cx| editor.go_to_position(line, column, cx));
+            window.focus(&editor.read(cx).focus_handle);
         }
         cx.notify();
     }
@@ -215,30 +605,341 @@ impl MainScreen {
         {
             self.active_item_index = index;
         } else {
-            let settings_view = cx.new(|_cx| SettingsView::new());
+            let settings_view = cx.new(SettingsView::new);
             self.items.push(WorkspaceItem::Settings(settings_view));
             self.active_item_index = self.items.len() - 1;
         }
         cx.notify();
     }
 
+    /// Shows the source control tab, starting on the primary folder's
+    /// repository, reusing an open one.
+    pub fn show_source_control(
+        &mut self,
+        _: &ShowSourceControl,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some((index, view)) = self.source_control_view() {
+            self.active_item_index = index;
+            view.update(cx, |view, cx| view.refresh(cx));
+        } else {
+            let roots = self.workspace.folders.clone();
+            let pools = self.state.read(cx).pools.clone();
+            let view = cx.new(|cx| SourceControlView::new(roots, pools, cx));
+            // Staging and committing change what the browser, tabs and gutters show
+            let changed = cx.subscribe(&view, |this, _, _: &RepositoryChanged, cx| {
+                this.file_browser
+                    .update(cx, |browser, cx| browser.refresh_git(cx))
+            });
+            self.item_subscriptions
+                .insert(view.entity_id(), vec![changed]);
+            self.items.push(WorkspaceItem::SourceControl(view));
+            self.active_item_index = self.items.len() - 1;
+        }
+        cx.notify();
+    }
+
+    fn source_control_view(&self) -> Option<(usize, Entity<SourceControlView>)> {
+        self.items
+            .iter()
+            .enumerate()
+            .find_map(|(index, item)| match item {
+                WorkspaceItem::SourceControl(view) => Some((index, view.clone())),
+                _ => None,
+            })
+    }
+
     pub fn toggle_browser(
         &mut self,
         _: &ToggleBrowser,
         _window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        self.show_browser = !self.show_browser;
+        self.layout.show_browser = !self.layout.show_browser;
+        self.save_layout();
+        cx.notify();
+    }
+
+    /// Moves the browser to the other side of the window.
+    pub fn switch_browser_side(
+        &mut self,
+        _: &SwitchBrowserSide,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.layout.browser_dock = self.layout.browser_dock.other();
+        self.layout.show_browser = true;
+        self.save_layout();
+        cx.notify();
+    }
+
+    fn save_layout(&self) {
+        if let Err(e) = self.layout.save() {
+            eprintln!("Failed to save panel layout: {}", e);
+        }
+    }
+
+    /// Shows the browser and moves keyboard focus into it.
+    pub fn focus_browser(&mut self, _: &FocusBrowser, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.layout.show_browser {
+            self.layout.show_browser = true;
+            self.save_layout();
+        }
+        window.focus(&self.file_browser.focus_handle(cx));
+        cx.notify();
+    }
+
+    /// Shows the browser with the active tab's file selected.
+    pub fn reveal_active_file(
+        &mut self,
+        _: &RevealActiveFile,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(path) = self
+            .items
+            .get(self.active_item_index)
+            .and_then(|item| item.path(cx))
+        else {
+            return;
+        };
+        if !self.layout.show_browser {
+            self.layout.show_browser = true;
+            self.save_layout();
+        }
+        self.file_browser
+            .update(cx, |browser, cx| browser.reveal_file(&path, cx));
+        cx.notify();
+    }
+
+    /// Asks for a folder and makes it the workspace root.
+    pub fn open_folder(&mut self, _: &OpenFolder, window: &mut Window, cx: &mut Context<Self>) {
+        let paths = cx.prompt_for_paths(PathPromptOptions {
+            files: false,
+            directories: true,
+            multiple: false,
+            prompt: Some("Open Folder".into()),
+        });
+        cx.spawn_in(window, async move |_, cx| {
+            let path = match paths.await {
+                Ok(Ok(Some(mut paths))) if !paths.is_empty() => paths.remove(0),
+                Ok(Err(e)) => {
+                    eprintln!("Failed to open folder picker: {}", e);
+                    return;
+                }
+                _ => return,
+            };
+            cx.update(|window, cx| {
+                window.dispatch_action(Box::new(OpenWorkspace { path }), cx);
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Asks for a workspace file and opens its folders.
+    pub fn open_workspace_file(
+        &mut self,
+        _: &OpenWorkspaceFile,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let paths = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
+            prompt: Some("Open Workspace".into()),
+        });
+        cx.spawn_in(window, async move |_, cx| {
+            let path = match paths.await {
+                Ok(Ok(Some(mut paths))) if !paths.is_empty() => paths.remove(0),
+                Ok(Err(e)) => {
+                    eprintln!("Failed to open workspace picker: {}", e);
+                    return;
+                }
+                _ => return,
+            };
+            cx.update(|window, cx| {
+                window.dispatch_action(Box::new(OpenWorkspace { path }), cx);
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Switches the browser, quick-open and project search over to another
+    /// folder or workspace file. Open tabs stay open.
+    pub fn open_workspace(
+        &mut self,
+        action: &OpenWorkspace,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let workspace = match WorkspaceRoots::open(&action.path) {
+            Ok(workspace) => workspace,
+            Err(e) => {
+                eprintln!("{:#}", e);
+                if !action.path.exists() {
+                    RecentWorkspaces::update(cx, |recent| {
+                        recent.paths.retain(|p| p != &action.path)
+                    });
+                }
+                return;
+            }
+        };
+        if workspace == self.workspace {
+            return;
+        }
+        self.set_workspace(workspace, cx);
+        self.recent_files.clear();
+        self.remember_workspace(cx);
+    }
+
+    /// Asks for a folder and adds it to the workspace.
+    pub fn add_folder_to_workspace(
+        &mut self,
+        _: &AddFolderToWorkspace,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let paths = cx.prompt_for_paths(PathPromptOptions {
+            files: false,
+            directories: true,
+            multiple: true,
+            prompt: Some("Add Folder to Workspace".into()),
+        });
+        cx.spawn_in(window, async move |this, cx| {
+            let folders = match paths.await {
+                Ok(Ok(Some(paths))) => paths,
+                Ok(Err(e)) => {
+                    eprintln!("Failed to open folder picker: {}", e);
+                    return;
+                }
+                _ => return,
+            };
+            this.update(cx, |this, cx| {
+                let mut workspace = this.workspace.clone();
+                for folder in folders {
+                    match folder.canonicalize() {
+                        Ok(folder) => {
+                            workspace.add(folder);
+                        }
+                        Err(e) => eprintln!("Can't add {}: {}", folder.display(), e),
+                    }
+                }
+                this.change_folders(workspace, cx);
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    pub fn remove_workspace_folder(
+        &mut self,
+        action: &RemoveWorkspaceFolder,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let mut workspace = self.workspace.clone();
+        if workspace.remove(&action.path) {
+            self.change_folders(workspace, cx);
+        }
+    }
+
+    /// Asks where to save the workspace file, then writes the folders to it.
+    pub fn save_workspace_as(
+        &mut self,
+        _: &SaveWorkspaceAs,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let dir = self
+            .workspace
+            .primary()
+            .canonicalize()
+            .ok()
+            .and_then(|primary| primary.parent().map(Path::to_path_buf))
+            .unwrap_or_default();
+        let name = format!(
+            "{}.{}",
+            crate::roots::root_name(self.workspace.primary()),
+            WORKSPACE_FILE_EXTENSION
+        );
+        let path = cx.prompt_for_new_path(&dir, Some(&name));
+        cx.spawn_in(window, async move |this, cx| {
+            let mut path = match path.await {
+                Ok(Ok(Some(path))) => path,
+                Ok(Err(e)) => {
+                    eprintln!("Failed to open save dialog: {}", e);
+                    return;
+                }
+                _ => return,
+            };
+            if !WorkspaceRoots::is_workspace_file(&path) {
+                path.set_extension(WORKSPACE_FILE_EXTENSION);
+            }
+            this.update(cx, |this, cx| {
+                let mut workspace = this.workspace.clone();
+                workspace.file = Some(path);
+                match workspace.save() {
+                    Ok(()) => {
+                        this.set_workspace(workspace, cx);
+                        this.remember_workspace(cx);
+                    }
+                    Err(e) => eprintln!("Failed to save workspace: {:#}", e),
+                }
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Applies added or removed folders, keeping the workspace file in step.
+    fn change_folders(&mut self, workspace: WorkspaceRoots, cx: &mut Context<Self>) {
+        if workspace == self.workspace {
+            return;
+        }
+        if let Err(e) = workspace.save() {
+            eprintln!("Failed to save workspace: {:#}", e);
+        }
+        self.set_workspace(workspace, cx);
+    }
+
+    fn set_workspace(&mut self, workspace: WorkspaceRoots, cx: &mut Context<Self>) {
+        if let Some(cancel) = self.search_cancel.take() {
+            cancel.cancel();
+        }
+        let primary_changed = workspace.primary() != self.workspace.primary();
+        self.workspace = workspace.clone();
+        self.file_browser
+            .update(cx, |browser, cx| browser.set_roots(workspace, cx));
+        if let Some((_, view)) = self.source_control_view() {
+            let roots = self.workspace.folders.clone();
+            view.update(cx, |view, cx| view.set_roots(roots, cx));
+        }
+        // The index only covers the primary folder
+        if primary_changed {
+            self.stop_search_index(cx);
+            self.sync_search_index(cx);
+        }
         cx.notify();
     }
 
+    /// Puts the current workspace at the top of the recent workspaces.
+    fn remember_workspace(&self, cx: &mut Context<Self>) {
+        let entry = self.workspace.recent_entry();
+        RecentWorkspaces::update(cx, |recent| recent.push(&entry));
+    }
+
     pub fn toggle_info_panel(
         &mut self,
         _: &ToggleInfoPanel,
         _window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        self.show_info_panel = !self.show_info_panel;
+        self.layout.show_info_panel = !self.layout.show_info_panel;
+        self.save_layout();
         cx.notify();
     }
 
@@ -249,25 +950,309 @@ impl MainScreen {
         }
     }
 
-    pub fn close_tab(&mut self, _: &CloseTab, _: &mut Window, cx: &mut Context<Self>) {
-        if !self.items.is_empty() {
-            self.items.remove(self.active_item_index);
-            if self.active_item_index >= self.items.len() && !self.items.is_empty() {
-                self.active_item_index = self.items.len() - 1;
-            } else if self.items.is_empty() {
-                self.active_item_index = 0;
+    pub fn close_tab(&mut self, _: &CloseTab, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(item) = self.items.get(self.active_item_index) else {
+            return;
+        };
+        let item_id = item.entity_id();
+        let dirty: Vec<_> = item.dirty_editor(cx).into_iter().collect();
+        let confirm = self.confirm_unsaved(dirty, window, cx);
+
+        cx.spawn_in(window, async move |this, cx| {
+            if confirm.await {
+                this.update(cx, |this, cx| this.remove_item(item_id, cx))
+                    .ok();
             }
-            cx.notify();
+        })
+        .detach();
+    }
+
+    fn remove_item(&mut self, item_id: EntityId, cx: &mut Context<Self>) {
+        let Some(index) = self.items.iter().position(|i| i.entity_id() == item_id) else {
+            return;
+        };
+        self.items.remove(index);
+        self.item_subscriptions.remove(&item_id);
+        if index < self.active_item_index {
+            self.active_item_index -= 1;
+        }
+        if self.active_item_index >= self.items.len() && !self.items.is_empty() {
+            self.active_item_index = self.items.len() - 1;
+        } else if self.items.is_empty() {
+            self.active_item_index = 0;
+        }
+        cx.notify();
+    }
+
+    /// Editors across all tabs that hold unsaved changes.
+    pub fn dirty_editors(&self, cx: &App) -> Vec<Entity<TextEditor>> {
+        self.items
+            .iter()
+            .filter_map(|item| item.dirty_editor(cx))
+            .collect()
+    }
+
+    /// Asks whether to save or discard the given dirty editors.
+    /// Resolves to true when it's fine to go ahead and drop them, and to
+    /// false if the user cancelled or a save failed.
+    pub fn confirm_unsaved(
+        &mut self,
+        editors: Vec<Entity<TextEditor>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Task<bool> {
+        if editors.is_empty() {
+            return Task::ready(true);
+        }
+        if self.prompt_open {
+            return Task::ready(false);
         }
+
+        let names: Vec<String> = editors.iter().map(|e| e.read(cx).display_name()).collect();
+        let (message, detail, answers) = if let [name] = names.as_slice() {
+            (
+                format!("Do you want to save the changes you made to {}?", name),
+                "Your changes will be lost if you don't save them.".to_string(),
+                ["Save", "Don't Save", "Cancel"],
+            )
+        } else {
+            (
+                format!("You have {} files with unsaved changes.", names.len()),
+                names.join("\n"),
+                ["Save All", "Discard All", "Cancel"],
+            )
+        };
+
+        self.prompt_open = true;
+        let answer = window.prompt(PromptLevel::Warning, &message, Some(&detail), &answers, cx);
+
+        cx.spawn_in(window, async move |this, cx| {
+            let answer = answer.await;
+            this.update(cx, |this, _| this.prompt_open = false).ok();
+
+            match answer {
+                Ok(0) => {
+                    for editor in editors {
+                        let Ok(save) = editor.update(cx, |editor, cx| editor.save_or_prompt(cx))
+                        else {
+                            return false;
+                        };
+                        if !save.await {
+                            return false;
+                        }
+                    }
+                    true
+                }
+                Ok(1) => true,
+                _ => false,
+            }
+        })
+    }
+
+    pub fn show_cache_stats(
+        &mut self,
+        _: &ShowCacheStats,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.prompt_open {
+            return;
+        }
+        let resources = self.state.read(cx).pools.resources.clone();
+        let detail = resources.stats().to_string();
+
+        self.prompt_open = true;
+        let answer = window.prompt(
+            PromptLevel::Info,
+            "File cache",
+            Some(&detail),
+            &["Flush Cache", "Close"],
+            cx,
+        );
+        cx.spawn(async move |this, cx| {
+            if let Ok(0) = answer.await {
+                resources.flush();
+            }
+            this.update(cx, |this, _| this.prompt_open = false).ok();
+        })
+        .detach();
+    }
+
+    pub fn flush_cache(&mut self, _: &FlushCache, _: &mut Window, cx: &mut Context<Self>) {
+        self.state.read(cx).pools.resources.flush();
+    }
+
+    pub fn quit(&mut self, _: &Quit, window: &mut Window, cx: &mut Context<Self>) {
+        let confirm = self.confirm_unsaved(self.dirty_editors(cx), window, cx);
+        let journal = self.recovery.clone();
+        cx.spawn_in(window, async move |_, cx| {
+            if confirm.await {
+                journal.clear().ok();
+                cx.update(|_, cx| cx.quit()).ok();
+            }
+        })
+        .detach();
+    }
+
+    /// Hooked up to `Window::on_window_should_close`. Closing is deferred
+    /// until unsaved buffers have been saved or discarded.
+    pub fn handle_window_should_close(
+        &mut self,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        let dirty = self.dirty_editors(cx);
+        if dirty.is_empty() {
+            self.recovery.clear().ok();
+            return true;
+        }
+
+        let confirm = self.confirm_unsaved(dirty, window, cx);
+        let journal = self.recovery.clone();
+        cx.spawn_in(window, async move |_, cx| {
+            if confirm.await {
+                journal.clear().ok();
+                cx.update(|window, _| window.remove_window()).ok();
+            }
+        })
+        .detach();
+        false
     }
 
     pub fn toggle_file_switcher(
         &mut self,
         _: &ToggleFileSwitcher,
-        _: &mut Window,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.modal_is::<FileFinder>() {
+            self.dismiss_modal(window, cx);
+            return;
+        }
+
+        // Open tabs count as recent even if they weren't opened through OpenPath
+        let mut recent = self.recent_files.clone();
+        for path in self.items.iter().filter_map(|item| item.path(cx)) {
+            if !recent.contains(&path) {
+                recent.push(path);
+            }
+        }
+
+        let finder = cx.new(|cx| FileFinder::new(self.workspace.clone(), recent, cx));
+
+        let (sender, receiver) = oneshot::channel();
+        let roots = self.workspace.walk_roots();
+        self.state.read(cx).pools.threads.spawn(move || {
+            let files: Vec<PathBuf> = roots
+                .into_iter()
+                .flat_map(|(folder, nested)| {
+                    project_files(&folder, &SearchScope::default().skipping(nested))
+                        .unwrap_or_default()
+                })
+                .collect();
+            sender.send(files).ok();
+        });
+        let weak_finder = finder.downgrade();
+        cx.spawn(async move |_, cx| {
+            if let Ok(files) = receiver.await {
+                weak_finder
+                    .update(cx, |finder, cx| finder.set_files(files, cx))
+                    .ok();
+            }
+        })
+        .detach();
+
+        self.show_modal(finder, window, cx);
+    }
+
+    pub fn go_to_line(&mut self, _: &GoToLine, window: &mut Window, cx: &mut Context<Self>) {
+        if self.modal_is::<GoToLinePrompt>() {
+            self.dismiss_modal(window, cx);
+        } else if let Some(editor) = self.active_editor() {
+            let prompt = cx.new(|cx| GoToLinePrompt::new(editor, cx));
+            self.show_modal(prompt, window, cx);
+        }
+    }
+
+    pub fn go_to_symbol(&mut self, _: &GoToSymbol, window: &mut Window, cx: &mut Context<Self>) {
+        if self.modal_is::<SymbolPicker>() {
+            self.dismiss_modal(window, cx);
+        } else if let Some(editor) = self.active_editor() {
+            let picker = cx.new(|cx| SymbolPicker::new(editor, cx));
+            self.show_modal(picker, window, cx);
+        }
+    }
+
+    pub fn toggle_command_palette(
+        &mut self,
+        _: &ToggleCommandPalette,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.modal_is::<CommandPalette>() {
+            self.dismiss_modal(window, cx);
+        } else {
+            let palette = cx.new(CommandPalette::new);
+            self.show_modal(palette, window, cx);
+        }
+    }
+
+    pub fn run_saved_search(
+        &mut self,
+        action: &RunSavedSearch,
+        window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        self.show_file_switcher = !self.show_file_switcher;
+        let Some(search) = SearchHistory::get(cx).saved(&action.name).cloned() else {
+            return;
+        };
+        self.search_bar.load_saved(&search, window, cx);
+        if self.search_bar.error.is_none() {
+            let action = self.search_bar.search_action(cx);
+            self.perform_search(&action, window, cx);
+        }
+    }
+
+    pub fn delete_saved_search(
+        &mut self,
+        action: &DeleteSavedSearch,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        SearchHistory::update(cx, |history| history.unpin(&action.name));
+    }
+
+    fn modal_is<V: 'static>(&self) -> bool {
+        self.modal
+            .as_ref()
+            .is_some_and(|modal| modal.entity_type() == std::any::TypeId::of::<V>())
+    }
+
+    /// Shows `view` over the workspace, replacing any other picker, until it
+    /// emits `DismissEvent`.
+    fn show_modal<V>(&mut self, view: Entity<V>, window: &mut Window, cx: &mut Context<Self>)
+    where
+        V: Render + Focusable + EventEmitter<DismissEvent>,
+    {
+        self.modal_subscription = Some(cx.subscribe_in(
+            &view,
+            window,
+            |this, _, _: &DismissEvent, window, cx| this.dismiss_modal(window, cx),
+        ));
+        window.focus(&view.focus_handle(cx));
+        self.modal = Some(view.into());
+        cx.notify();
+    }
+
+    fn dismiss_modal(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.modal.take().is_none() {
+            return;
+        }
+        self.modal_subscription = None;
+        match self.active_editor() {
+            Some(editor) => window.focus(&editor.read(cx).focus_handle),
+            None => window.focus(&self.focus_handle),
+        }
         cx.notify();
     }
 
@@ -280,34 +1265,486 @@ impl MainScreen {
         self.search_bar.toggle(action.global, window, cx);
     }
 
-    pub fn perform_search(
+    pub fn toggle_replace(
         &mut self,
-        action: &PerformSearch,
+        _: &ToggleReplace,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.search_bar.toggle_replace(false, window, cx);
+    }
+
+    pub fn toggle_project_replace(
+        &mut self,
+        _: &ToggleProjectReplace,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.search_bar.toggle_replace(true, window, cx);
+    }
+
+    fn active_editor(&self) -> Option<Entity<TextEditor>> {
+        match self.items.get(self.active_item_index) {
+            Some(WorkspaceItem::Editor(editor)) => Some(editor.clone()),
+            _ => None,
+        }
+    }
+
+    /// Refreshes the match count and replace preview shown in the search bar.
+    fn update_search_preview(
+        &mut self,
+        query: &SearchQuery,
+        editor: &Entity<TextEditor>,
+        cx: &App,
+    ) {
+        let editor = editor.read(cx);
+        self.search_bar.match_count = Some(query.count(&editor.get_text(cx)));
+        self.search_bar.preview = if self.search_bar.replace_visible {
+            editor.replace_preview(query, &self.search_bar.replacement_text(cx), cx)
+        } else {
+            None
+        };
+    }
+
+    /// Recounts matches and rebuilds the replace preview for the query in
+    /// the search bar, leaving the selection where it is.
+    pub fn refresh_search_preview(&mut self, cx: &mut Context<Self>) {
+        let text = self.search_bar.query_text(cx);
+        if self.search_bar.is_global || text.is_empty() {
+            return;
+        }
+        let Ok(query) = SearchQuery::new(&text, self.search_bar.options) else {
+            return;
+        };
+        if let Some(editor) = self.active_editor() {
+            self.update_search_preview(&query, &editor, cx);
+            cx.notify();
+        }
+    }
+
+    pub fn perform_replace(
+        &mut self,
+        action: &PerformReplace,
         _window: &mut Window,
         cx: &mut Context<Self>,
     ) {
+        if action.query.is_empty() {
+            return;
+        }
+        let Ok(query) = SearchQuery::new(&action.query, action.options) else {
+            return;
+        };
         if action.global {
-            // Reuse existing trigger_search logic but with query
-            let query = action.query.clone();
-            let root_path = std::path::PathBuf::from(".");
+            self.preview_project_replace(query, action, cx);
+            return;
+        }
+        let Some(editor) = self.active_editor() else {
+            return;
+        };
 
-            let _state = self.state.clone();
-            let executor = cx.background_executor().clone();
-            let pools = self.state.read(cx).pools.clone();
+        if action.all {
+            let count = editor.update(cx, |editor, cx| {
+                editor.replace_all(&query, &action.replacement, cx)
+            });
+            self.search_bar.status = Some(match count {
+                0 => "No matches".to_string(),
+                1 => "Replaced 1 occurrence".to_string(),
+                n => format!("Replaced {} occurrences", n),
+            });
+        } else {
+            editor.update(cx, |editor, cx| {
+                editor.replace_next(&query, &action.replacement, cx)
+            });
+            self.search_bar.status = None;
+        }
+        self.update_search_preview(&query, &editor, cx);
+        cx.notify();
+    }
+
+    /// Opens a preview tab and scans the project for matches in the background.
+    fn preview_project_replace(
+        &mut self,
+        query: SearchQuery,
+        action: &PerformReplace,
+        cx: &mut Context<Self>,
+    ) {
+        // Open buffers are searched as they are, unsaved changes included
+        let open_buffers: HashMap<PathBuf, String> = self
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                WorkspaceItem::Editor(editor) => {
+                    let editor = editor.read(cx);
+                    Some((
+                        canonical_path(editor.file_path.as_ref()?),
+                        editor.get_text(cx),
+                    ))
+                }
+                _ => None,
+            })
+            .collect();
+
+        let view = cx.new(|cx| {
+            ProjectReplaceView::new(action.query.clone(), action.replacement.clone(), cx)
+        });
+        self.items.push(WorkspaceItem::ProjectReplace(view.clone()));
+        self.active_item_index = self.items.len() - 1;
+        cx.notify();
 
-            // TODO: Update state with results instead of just printing or detaching
-            executor
+        let pools = self.state.read(cx).pools.clone();
+        let replacement = action.replacement.clone();
+        let scope = action.scope.clone();
+        let roots = self.workspace.clone();
+        cx.spawn(async move |_, cx| {
+            let plan = cx
+                .background_executor()
                 .spawn(async move {
-                    let results = crate::editor::search::perform_search(query, root_path, pools);
-                    println!("Search results: {:?}", results.len());
+                    ProjectReplace::plan(&query, &replacement, &roots, &scope, &open_buffers, pools)
                 })
-                .detach();
+                .await;
+            view.update(cx, |view, cx| view.set_plan(plan, cx)).ok();
+        })
+        .detach();
+    }
+
+    /// Shows the results tab (reusing an open one) and streams matches into
+    /// it from the thread pool as each file is searched.
+    fn start_project_search(
+        &mut self,
+        text: String,
+        query: SearchQuery,
+        scope: SearchScope,
+        cx: &mut Context<Self>,
+    ) {
+        let view = match self.search_results_view() {
+            Some((index, view)) => {
+                self.active_item_index = index;
+                view
+            }
+            None => {
+                let view = cx.new(SearchResultsView::new);
+                self.items.push(WorkspaceItem::SearchResults(view.clone()));
+                self.active_item_index = self.items.len() - 1;
+                view
+            }
+        };
+        let roots = self.workspace.clone();
+        let generation = view.update(cx, |view, cx| view.start(text, roots, cx));
+        cx.notify();
+
+        // Only one project search runs at a time
+        let cancel = CancelFlag::default();
+        if let Some(previous) = self.search_cancel.replace(cancel.clone()) {
+            previous.cancel();
+        }
+
+        let (sender, mut receiver) = mpsc::unbounded();
+        let pools = self.state.read(cx).pools.clone();
+        let roots = self.workspace.walk_roots();
+        let index = self.search_index.clone();
+        pools.threads.spawn({
+            let pools = pools.clone();
+            // One folder after another, the results close when the last one is done
+            move || {
+                for (folder, nested) in roots {
+                    perform_search_streaming(
+                        query.clone(),
+                        folder,
+                        scope.skipping(nested),
+                        pools.clone(),
+                        index.clone(),
+                        cancel.clone(),
+                        sender.clone(),
+                    );
+                }
+            }
+        });
+
+        let view = view.downgrade();
+        cx.spawn(async move |_, cx| {
+            while let Some(results) = receiver.next().await {
+                // Dropping the receiver stops the search once the tab is closed or superseded
+                let current = view
+                    .update(cx, |view, cx| view.push(generation, results, cx))
+                    .unwrap_or(false);
+                if !current {
+                    return;
+                }
+            }
+            view.update(cx, |view, cx| view.finish(generation, cx)).ok();
+        })
+        .detach();
+    }
+
+    /// Drops the search index, saving what it has learned.
+    fn stop_search_index(&mut self, cx: &mut Context<Self>) {
+        if let Some(index) = self.search_index.take() {
+            self.state
+                .read(cx)
+                .pools
+                .threads
+                .spawn(move || index.save());
+        }
+    }
+
+    /// Starts or stops the search index to match the `search_index` setting.
+    fn sync_search_index(&mut self, cx: &mut Context<Self>) {
+        let enabled = Settings::get(cx).search_index;
+        if enabled == self.search_index.is_some() {
+            return;
+        }
+        if !enabled {
+            self.stop_search_index(cx);
+            cx.notify();
+            return;
+        }
+
+        let index = Arc::new(SearchIndex::load(self.workspace.primary().to_path_buf()));
+        let pools = self.state.read(cx).pools.clone();
+        pools.threads.spawn({
+            let index = index.clone();
+            let pools = pools.clone();
+            move || index.refresh(&pools)
+        });
+
+        // Repaints the status bar while building and saves changes now and then
+        let weak_index = Arc::downgrade(&index);
+        cx.spawn(async move |this, cx| {
+            let mut ticks = 0;
+            let mut was_building = true;
+            loop {
+                cx.background_executor().timer(Duration::from_secs(1)).await;
+                let Some(index) = weak_index.upgrade() else {
+                    break;
+                };
+                ticks += 1;
+                let building = index.progress().building;
+                if (building || was_building) && this.update(cx, |_, cx| cx.notify()).is_err() {
+                    break;
+                }
+                was_building = building;
+                if ticks % SEARCH_INDEX_SAVE_INTERVAL_SECS == 0 {
+                    pools.threads.spawn(move || index.save());
+                }
+            }
+        })
+        .detach();
+
+        self.search_index = Some(index);
+        cx.notify();
+    }
+
+    pub fn cancel_project_search(
+        &mut self,
+        _: &CancelProjectSearch,
+        _window: &mut Window,
+        _cx: &mut Context<Self>,
+    ) {
+        if let Some(cancel) = self.search_cancel.take() {
+            cancel.cancel();
+        }
+    }
+
+    fn search_results_view(&self) -> Option<(usize, Entity<SearchResultsView>)> {
+        self.items
+            .iter()
+            .enumerate()
+            .find_map(|(index, item)| match item {
+                WorkspaceItem::SearchResults(view) => Some((index, view.clone())),
+                _ => None,
+            })
+    }
+
+    pub fn next_result(&mut self, _: &NextResult, window: &mut Window, cx: &mut Context<Self>) {
+        self.open_adjacent_result(true, window, cx);
+    }
+
+    pub fn previous_result(
+        &mut self,
+        _: &PreviousResult,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.open_adjacent_result(false, window, cx);
+    }
+
+    fn open_adjacent_result(&mut self, forward: bool, window: &mut Window, cx: &mut Context<Self>) {
+        let Some((_, view)) = self.search_results_view() else {
+            return;
+        };
+        if let Some(action) = view.update(cx, |view, cx| view.select_adjacent(forward, cx)) {
+            self.open_path(&action, window, cx);
+        }
+    }
+
+    fn active_project_replace(&self) -> Option<Entity<ProjectReplaceView>> {
+        match self.items.get(self.active_item_index) {
+            Some(WorkspaceItem::ProjectReplace(view)) => Some(view.clone()),
+            _ => None,
+        }
+    }
+
+    /// Moves each file from `before` to `after`. Open files go through their
+    /// editor as a single undo step; closed files are written together with
+    /// one atomic `ResourcePool` call. Files whose current text isn't
+    /// `before` changed in the meantime and are skipped.
+    fn apply_file_edits(
+        &mut self,
+        edits: Vec<FileEdit>,
+        cx: &mut Context<Self>,
+    ) -> (Vec<FileEdit>, Vec<PathBuf>) {
+        let pools = self.state.read(cx).pools.clone();
+        let mut applied = Vec::new();
+        let mut skipped = Vec::new();
+        let mut writes = Vec::new();
+
+        for edit in edits {
+            let path = canonical_path(&edit.path);
+            let editor = self.items.iter().find_map(|item| match item {
+                WorkspaceItem::Editor(editor)
+                    if editor
+                        .read(cx)
+                        .file_path
+                        .as_ref()
+                        .is_some_and(|p| canonical_path(p) == path) =>
+                {
+                    Some(editor.clone())
+                }
+                _ => None,
+            });
+
+            if let Some(editor) = editor {
+                if editor.read(cx).get_text(cx) == edit.before {
+                    let after = edit.after.clone();
+                    editor.update(cx, |editor, cx| editor.replace_contents(after, cx));
+                    applied.push(edit);
+                } else {
+                    skipped.push(edit.path);
+                }
+            // Read from disk rather than the cache so outside changes are noticed
+            } else if std::fs::read_to_string(&edit.path).is_ok_and(|text| text == edit.before) {
+                writes.push(edit);
+            } else {
+                skipped.push(edit.path);
+            }
+        }
+
+        let files = writes
+            .iter()
+            .map(|edit| (edit.path.clone(), edit.after.clone()))
+            .collect();
+        let results = pools.resources.save_files_atomic(files);
+        for (edit, result) in writes.into_iter().zip(results) {
+            match result {
+                Ok(()) => applied.push(edit),
+                Err(e) => {
+                    eprintln!("Failed to write {}: {}", edit.path.display(), e);
+                    skipped.push(edit.path);
+                }
+            }
+        }
+
+        (applied, skipped)
+    }
+
+    pub fn apply_project_replace(
+        &mut self,
+        _: &ApplyProjectReplace,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(view) = self.active_project_replace() else {
+            return;
+        };
+        let plan = match view.read(cx) {
+            ProjectReplaceView {
+                plan: Some(plan),
+                summary: None,
+                ..
+            } => plan.clone(),
+            _ => return,
+        };
+
+        let (edits, skipped) = self.apply_file_edits(plan.edits(), cx);
+        let replacements = plan
+            .files
+            .iter()
+            .filter(|file| edits.iter().any(|edit| edit.path == file.path))
+            .map(|file| file.included_count())
+            .sum();
+        view.update(cx, |view, cx| {
+            view.summary = Some(ReplaceSummary {
+                replacements,
+                edits,
+                skipped,
+            });
+            cx.notify();
+        });
+        cx.notify();
+    }
+
+    /// Reverts every file changed by the replace in the active preview tab.
+    pub fn undo_project_replace(
+        &mut self,
+        _: &UndoProjectReplace,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(view) = self.active_project_replace() else {
+            return;
+        };
+        let Some(summary) = view.read(cx).summary.clone() else {
+            return;
+        };
+        if view.read(cx).status.is_some() {
+            return;
+        }
+
+        let edits = summary.edits.iter().map(FileEdit::reversed).collect();
+        let (reverted, skipped) = self.apply_file_edits(edits, cx);
+        let mut status = format!(
+            "Undone in {} of {} files",
+            reverted.len(),
+            summary.edits.len()
+        );
+        if !skipped.is_empty() {
+            status.push_str(&format!(
+                ", {} changed since and were left alone",
+                skipped.len()
+            ));
+        }
+        view.update(cx, |view, cx| {
+            view.status = Some(status);
+            cx.notify();
+        });
+        cx.notify();
+    }
+
+    pub fn perform_search(
+        &mut self,
+        action: &PerformSearch,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if action.query.is_empty() {
+            self.search_bar.match_count = None;
+            self.search_bar.preview = None;
+            return;
+        }
+        // The search bar reports compile errors inline, so an invalid query just doesn't run
+        let Ok(query) = SearchQuery::new(&action.query, action.options) else {
+            return;
+        };
+
+        if action.global {
+            self.start_project_search(action.query.clone(), query, action.scope.clone(), cx);
         } else {
             // Local search
-            if let Some(WorkspaceItem::Editor(editor)) = self.items.get(self.active_item_index) {
+            if let Some(editor) = self.active_editor() {
                 editor.update(cx, |editor, cx| {
-                    editor.find_next(&action.query, cx);
+                    editor.find_next(&query, cx);
                 });
+                self.update_search_preview(&query, &editor, cx);
             }
         }
     }
@@ -319,15 +1756,18 @@ impl MainScreen {
         cx: &mut Context<Self>,
     ) {
         let _state = self.state.clone();
-        let query = "struct".to_string(); // Hardcoded for demo
+        let Ok(query) = SearchQuery::new("struct", SearchOptions::default()) else {
+            return;
+        }; // Hardcoded for demo
         let root_path = std::path::PathBuf::from("src"); // Search in src
 
         let _async_cx = cx.to_async();
         let executor = cx.background_executor().clone();
 
         let pools = self.state.read(cx).pools.clone();
-        let _background_task = executor
-            .spawn(async move { crate::editor::search::perform_search(query, root_path, pools) });
+        let _background_task = executor.spawn(async move {
+            crate::editor::search::perform_search(query, root_path, SearchScope::default(), pools)
+        });
 
         // cx.spawn(|_, _| async move {
         //     let results = background_task.await;
@@ -347,24 +1787,110 @@ impl MainScreen {
     }
 }
 
+/// Drag payload for the browser's resize handle.
+struct ResizeBrowser;
+
+impl Render for ResizeBrowser {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        Empty
+    }
+}
+
+impl MainScreen {
+    /// The file browser with a resize handle on its inner edge. Dragging
+    /// the handle resizes it, double-clicking resets the width.
+    fn render_browser(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let dock = self.layout.browser_dock;
+        let handle = div()
+            .id("browser-resize-handle")
+            .absolute()
+            .top_0()
+            .w_1()
+            .h_full()
+            .cursor_col_resize()
+            .hover(|s| s.bg(rgb(0x007acc)))
+            .when(dock == Dock::Left, |this| this.right_0())
+            .when(dock == Dock::Right, |this| this.left_0())
+            .on_drag(ResizeBrowser, |_, _, _, cx| cx.new(|_| ResizeBrowser))
+            .on_click(cx.listener(|this, event: &ClickEvent, _, cx| {
+                if event.click_count() == 2 {
+                    this.layout.set_browser_width(BROWSER_DEFAULT_WIDTH);
+                    this.save_layout();
+                    cx.notify();
+                }
+            }));
+
+        div()
+            .relative()
+            .flex()
+            .h_full()
+            .flex_none()
+            .w(px(self.layout.browser_width))
+            .border_color(rgb(0x404040))
+            .when(dock == Dock::Left, |this| this.border_r_1())
+            .when(dock == Dock::Right, |this| this.border_l_1())
+            .on_drag_move(
+                cx.listener(move |this, event: &DragMoveEvent<ResizeBrowser>, _, cx| {
+                    let x = event.event.position.x;
+                    let width = match dock {
+                        Dock::Left => x - event.bounds.left(),
+                        Dock::Right => event.bounds.right() - x,
+                    };
+                    this.layout.set_browser_width(f32::from(width));
+                    cx.notify();
+                }),
+            )
+            .child(self.file_browser.clone())
+            .child(handle)
+    }
+}
+
 impl Render for MainScreen {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let active_item = self.items.get(self.active_item_index);
+        let browser_dock = self.layout.show_browser.then_some(self.layout.browser_dock);
 
         div()
             .track_focus(&self.focus_handle)
             .on_action(cx.listener(MainScreen::toggle_browser))
+            .on_action(cx.listener(MainScreen::switch_browser_side))
+            .on_action(cx.listener(MainScreen::focus_browser))
+            .on_action(cx.listener(MainScreen::reveal_active_file))
             .on_action(cx.listener(MainScreen::toggle_info_panel))
             .on_action(cx.listener(MainScreen::open_path))
+            .on_action(cx.listener(MainScreen::open_folder))
+            .on_action(cx.listener(MainScreen::open_workspace))
+            .on_action(cx.listener(MainScreen::open_workspace_file))
+            .on_action(cx.listener(MainScreen::add_folder_to_workspace))
+            .on_action(cx.listener(MainScreen::remove_workspace_folder))
+            .on_action(cx.listener(MainScreen::save_workspace_as))
             .on_action(cx.listener(MainScreen::open_settings))
+            .on_action(cx.listener(MainScreen::show_source_control))
             .on_action(cx.listener(MainScreen::switch_tab))
             .on_action(cx.listener(MainScreen::close_tab))
             .on_action(cx.listener(MainScreen::toggle_file_switcher))
             .on_action(cx.listener(MainScreen::toggle_file_switcher))
+            .on_action(cx.listener(MainScreen::go_to_line))
+            .on_action(cx.listener(MainScreen::go_to_symbol))
+            .on_action(cx.listener(MainScreen::toggle_command_palette))
+            .on_action(cx.listener(MainScreen::run_saved_search))
+            .on_action(cx.listener(MainScreen::delete_saved_search))
             .on_action(cx.listener(MainScreen::trigger_search))
             .on_action(cx.listener(MainScreen::toggle_search))
             .on_action(cx.listener(MainScreen::perform_search))
-            .on_action(cx.listener(|_, _: &Quit, _, cx| cx.quit()))
+            .on_action(cx.listener(MainScreen::toggle_replace))
+            .on_action(cx.listener(MainScreen::perform_replace))
+            .on_action(cx.listener(MainScreen::toggle_project_replace))
+            .on_action(cx.listener(MainScreen::apply_project_replace))
+            .on_action(cx.listener(MainScreen::undo_project_replace))
+            .on_action(cx.listener(MainScreen::cancel_project_search))
+            .on_action(cx.listener(MainScreen::next_result))
+            .on_action(cx.listener(MainScreen::previous_result))
+            .on_action(cx.listener(MainScreen::show_cache_stats))
+            .on_action(cx.listener(MainScreen::flush_cache))
+            .on_action(cx.listener(MainScreen::quit))
+            // Dropped anywhere, so the root is the one place sure to see it
+            .on_drop(cx.listener(|this, _: &ResizeBrowser, _, _| this.save_layout()))
             .bg(rgb(0x252526))
             .text_color(PRIMARY_COLOR)
             .size_full()
@@ -378,14 +1904,8 @@ impl Render for MainScreen {
                     .flex_row()
                     .flex_1()
                     .h_full()
-                    .when(self.show_browser, |this| {
-                        this.child(
-                            div()
-                                .flex()
-                                .h_full()
-                                .flex_none()
-                                .child(self.file_browser.clone()),
-                        )
+                    .when(browser_dock == Some(Dock::Left), |this| {
+                        this.child(self.render_browser(cx))
                     })
                     .child(
                         div()
@@ -403,18 +1923,11 @@ impl Render for MainScreen {
                                     .text_color(rgb(0xcccccc))
                                     .children(self.items.iter().enumerate().map(|(i, item)| {
                                         let is_active = i == self.active_item_index;
-                                        let title = match item {
-                                            WorkspaceItem::Editor(e) => {
-                                                let editor = e.read(cx);
-                                                editor
-                                                    .file_path
-                                                    .as_ref()
-                                                    .and_then(|p| p.to_str())
-                                                    .map(|s| s.to_owned())
-                                                    .unwrap_or_else(|| "Editor".to_string())
-                                            }
-                                            WorkspaceItem::Settings(_) => "Settings".to_string(),
-                                        };
+                                        let title = item.title(cx);
+                                        let is_dirty = item.dirty_editor(cx).is_some();
+                                        let status = item.path(cx).and_then(|path| {
+                                            self.file_browser.read(cx).git_status(&path)
+                                        });
                                         div()
                                             .h_full()
                                             .px_3()
@@ -434,7 +1947,29 @@ impl Render for MainScreen {
                                                     cx.dispatch_action(&SwitchTab { index: i });
                                                 }),
                                             )
-                                            .child(div().text_xs().child(title))
+                                            .child(
+                                                div()
+                                                    .text_xs()
+                                                    .when_some(status, |this, status| {
+                                                        this.text_color(rgb(status.color()))
+                                                    })
+                                                    .child(title),
+                                            )
+                                            .when_some(
+                                                status.filter(|s| *s != GitStatus::Ignored),
+                                                |this, status| {
+                                                    this.child(
+                                                        div()
+                                                            .ml_1()
+                                                            .text_xs()
+                                                            .text_color(rgb(status.color()))
+                                                            .child(status.letter()),
+                                                    )
+                                                },
+                                            )
+                                            .when(is_dirty, |this| {
+                                                this.child(div().ml_2().text_xs().child("●"))
+                                            })
                                             .when(is_active, |this| {
                                                 this.child(
                                                     div()
@@ -453,62 +1988,16 @@ impl Render for MainScreen {
                             )
                             // Content
                             .children(active_item.map(|item| item.render(cx))),
-                    ),
+                    )
+                    .when(browser_dock == Some(Dock::Right), |this| {
+                        this.child(self.render_browser(cx))
+                    }),
             )
-            .child(self.status_bar.render(cx)) // Bottom Bar
-            .when(self.show_file_switcher, |this| {
-                this.child(
-                    div()
-                        .absolute()
-                        .top(px(40.0))
-                        .left_1_2()
-                        .w(px(400.0))
-                        .ml(px(-200.0))
-                        .bg(rgb(0x252526))
-                        .border_1()
-                        .border_color(rgb(0x454545))
-                        .shadow_lg()
-                        .flex()
-                        .flex_col()
-                        .children(self.items.iter().enumerate().map(|(i, item)| {
-                            let is_active = i == self.active_item_index;
-                            let title = match item {
-                                WorkspaceItem::Editor(e) => {
-                                    let editor = e.read(cx);
-                                    editor
-                                        .file_path
-                                        .as_ref()
-                                        .and_then(|p| p.to_str())
-                                        .map(|s| s.to_owned())
-                                        .unwrap_or_else(|| "Editor".to_string())
-                                }
-                                WorkspaceItem::Settings(_) => "Settings".to_string(),
-                            };
-                            div()
-                                .px_4()
-                                .py_2()
-                                .text_color(if is_active {
-                                    rgb(0xffffff)
-                                } else {
-                                    rgb(0xcccccc)
-                                })
-                                .bg(if is_active {
-                                    rgb(0x04395e)
-                                } else {
-                                    rgb(0x252526)
-                                })
-                                .hover(|s| s.bg(rgb(0x2a2d2e)))
-                                .cursor_pointer()
-                                .child(div().text_xs().child(format!("{}", title)))
-                                .on_mouse_down(
-                                    MouseButton::Left,
-                                    cx.listener(move |_, _, _, cx| {
-                                        cx.dispatch_action(&SwitchTab { index: i });
-                                        cx.dispatch_action(&ToggleFileSwitcher); // Close switcher
-                                    }),
-                                )
-                        })),
-                )
-            })
+            .child(self.status_bar.render(
+                active_item,
+                self.search_index.as_ref().map(|index| index.progress()),
+                cx,
+            )) // Bottom Bar
+            .children(self.modal.clone())
     }
 }
@@ -1,4 +1,8 @@
 use gpui::{App, Action, KeyBinding, Rgba};
+use std::path::{Path, PathBuf};
+
+use crate::config::DATA_DIR_NAME;
+use crate::ui::file_finder::parse_position;
 
 pub const fn hex_to_rgba(hex: u32) -> Rgba {
     Rgba {
@@ -24,3 +28,78 @@ pub fn bind_editor_action<A: Action + Clone>(cx: &mut App, keystroke: &str, acti
         KeyBinding::new(keystroke, action, Some("Editor"))
     ]);
 }
+
+/// Per-user directory for settings and recovery data.
+/// Falls back to a local directory when the platform has none.
+pub fn data_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(DATA_DIR_NAME)
+}
+
+/// `path` with symlinks and `.`/`..` resolved, for telling whether two paths
+/// name the same file: search hands out `./src/x` while the Open dialog gives
+/// absolute paths. For paths that don't exist, such as where a file was
+/// before it was moved, the deepest folder that does is resolved.
+pub fn canonical_path(path: &Path) -> PathBuf {
+    if let Ok(canonical) = path.canonicalize() {
+        return canonical;
+    }
+    let Ok(absolute) = std::path::absolute(path) else {
+        return path.to_path_buf();
+    };
+    absolute
+        .ancestors()
+        .skip(1)
+        .find_map(|ancestor| {
+            let rest = absolute.strip_prefix(ancestor).ok()?;
+            Some(ancestor.canonicalize().ok()?.join(rest))
+        })
+        .unwrap_or(absolute)
+}
+
+/// Formats a byte count as a short human readable string, e.g. `12.3 KB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Byte offset of a 1-based `line` and character `column` in `text`,
+/// clamped to the end of the line (or of the text).
+pub fn position_to_offset(text: &str, line: usize, column: usize) -> usize {
+    let line_start = if line <= 1 {
+        0
+    } else {
+        match text.match_indices('\n').nth(line - 2) {
+            Some((i, _)) => i + 1,
+            None => return text.len(),
+        }
+    };
+    let line_text = text[line_start..].split('\n').next().unwrap_or("");
+    let column_offset = line_text
+        .char_indices()
+        .nth(column.saturating_sub(1))
+        .map_or(line_text.len(), |(i, _)| i);
+    line_start + column_offset
+}
+
+/// Splits a `file:line:col` argument from the command line into the path
+/// and position. A path that exists as typed wins, so names containing
+/// colons still open.
+pub fn parse_launch_path(arg: &str) -> (PathBuf, Option<(usize, usize)>) {
+    if Path::new(arg).exists() {
+        return (PathBuf::from(arg), None);
+    }
+    let (path, position) = parse_position(arg);
+    (PathBuf::from(path), position)
+}
@@ -1,24 +1,78 @@
+use crate::editor::imageviewer::ImageViewer;
 use crate::editor::texteditor::TextEditor;
+use crate::ui::project_replace::ProjectReplaceView;
+use crate::ui::search_results::SearchResultsView;
 use crate::ui::settings::SettingsView;
-use gpui::{AnyElement, Context, Entity, IntoElement, Window};
+use crate::ui::source_control::SourceControlView;
+use gpui::{AnyElement, App, Context, Entity, EntityId, IntoElement};
+use std::path::PathBuf;
 
 pub enum WorkspaceItem {
     Editor(Entity<TextEditor>),
     Settings(Entity<SettingsView>),
+    Image(Entity<ImageViewer>),
+    ProjectReplace(Entity<ProjectReplaceView>),
+    SearchResults(Entity<SearchResultsView>),
+    SourceControl(Entity<SourceControlView>),
 }
 
 impl WorkspaceItem {
-    pub fn title(&self, cx: &Window) -> String {
+    pub fn title(&self, cx: &App) -> String {
         match self {
-            WorkspaceItem::Editor(_) => "Editor".to_string(), // Need access to filename
+            WorkspaceItem::Editor(editor) => editor
+                .read(cx)
+                .file_path
+                .as_ref()
+                .and_then(|p| p.to_str())
+                .map(|s| s.to_owned())
+                .unwrap_or_else(|| "Editor".to_string()),
             WorkspaceItem::Settings(_) => "Settings".to_string(),
+            WorkspaceItem::Image(viewer) => viewer.read(cx).path.to_string_lossy().to_string(),
+            WorkspaceItem::ProjectReplace(view) => format!("Replace: {}", view.read(cx).query),
+            WorkspaceItem::SearchResults(view) => format!("Search: {}", view.read(cx).query),
+            WorkspaceItem::SourceControl(_) => "Source Control".to_string(),
         }
     }
 
-    pub fn render(&self, cx: &mut Context<crate::ui::workspace::MainScreen>) -> AnyElement {
+    /// The file backing this item, if any.
+    pub fn path(&self, cx: &App) -> Option<PathBuf> {
+        match self {
+            WorkspaceItem::Editor(editor) => editor.read(cx).file_path.clone(),
+            WorkspaceItem::Settings(_) => None,
+            WorkspaceItem::Image(viewer) => Some(viewer.read(cx).path.clone()),
+            WorkspaceItem::ProjectReplace(_)
+            | WorkspaceItem::SearchResults(_)
+            | WorkspaceItem::SourceControl(_) => None,
+        }
+    }
+
+    /// The editor behind this item if it holds unsaved changes.
+    pub fn dirty_editor(&self, cx: &App) -> Option<Entity<TextEditor>> {
+        match self {
+            WorkspaceItem::Editor(editor) if editor.read(cx).is_dirty() => Some(editor.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn entity_id(&self) -> EntityId {
+        match self {
+            WorkspaceItem::Editor(editor) => editor.entity_id(),
+            WorkspaceItem::Settings(settings) => settings.entity_id(),
+            WorkspaceItem::Image(viewer) => viewer.entity_id(),
+            WorkspaceItem::ProjectReplace(view) => view.entity_id(),
+            WorkspaceItem::SearchResults(view) => view.entity_id(),
+            WorkspaceItem::SourceControl(view) => view.entity_id(),
+        }
+    }
+
+    pub fn render(&self, _cx: &mut Context<crate::ui::workspace::MainScreen>) -> AnyElement {
         match self {
             WorkspaceItem::Editor(editor) => editor.clone().into_any_element(),
             WorkspaceItem::Settings(settings) => settings.clone().into_any_element(),
+            WorkspaceItem::Image(viewer) => viewer.clone().into_any_element(),
+            WorkspaceItem::ProjectReplace(view) => view.clone().into_any_element(),
+            WorkspaceItem::SearchResults(view) => view.clone().into_any_element(),
+            WorkspaceItem::SourceControl(view) => view.clone().into_any_element(),
         }
     }
 }